@@ -0,0 +1,127 @@
+//! Generates `src/rom/database/games.txt` from a NesCartDB XML export, which lists the board each
+//! cartridge was dumped from along with the checksums of its PRG-ROM and CHR-ROM.
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+
+const HEADER: &'static str = "\
+# Game database used to correct iNES headers. Each line describes one headerless image:
+#
+#   crc32 sha1 mapper submapper mirroring battery region title
+#
+# - crc32 and sha1 are of the PRG-ROM followed by the CHR-ROM. Use - for an unknown SHA-1.
+# - mirroring is H (horizontal), V (vertical), 4 (four-screen), L or U (single-screen lower or
+#   upper).
+# - battery is 1 if the board has battery-backed PRG-RAM, otherwise 0.
+# - region is NTSC, PAL, MULTI or DENDY.
+# - title is the rest of the line.
+#
+# Generated from NesCartDB with:
+#
+#   cargo run --example game_database -- nescartdb.xml > src/rom/database/games.txt";
+
+#[derive(Default)]
+struct Cartridge {
+    crc32: String,
+    sha1: String,
+    region: &'static str,
+    mapper: Option<u16>,
+    mirroring: Option<&'static str>,
+    has_battery: bool,
+}
+
+fn main() {
+    let path = env::args().nth(1).expect("Usage: game_database <nescartdb.xml>");
+    let mut xml = String::new();
+    File::open(&path)
+        .and_then(|mut f| f.read_to_string(&mut xml))
+        .expect("Couldn't read NesCartDB export");
+
+    println!("{}", HEADER);
+    let mut title = String::new();
+    let mut cartridge = None;
+    for tag in xml.split('<').skip(1).map(|tag| tag.split('>').next().unwrap()) {
+        let name = tag.split_whitespace().next().unwrap_or("");
+        match name {
+            "game" => title = unescape(attribute(tag, "name").unwrap_or("")),
+            "cartridge" => {
+                let region = match attribute(tag, "system") {
+                    Some(system) if system.starts_with("NES-PAL") => "PAL",
+                    Some("Dendy") => "DENDY",
+                    _ => "NTSC",
+                };
+                cartridge = Some(Cartridge {
+                                     crc32: attribute(tag, "crc").unwrap_or("").to_lowercase(),
+                                     sha1: attribute(tag, "sha1").unwrap_or("-").to_lowercase(),
+                                     region: region,
+                                     ..Cartridge::default()
+                                 });
+            }
+            "board" | "pad" | "wram" | "vram" => {
+                if let Some(ref mut cartridge) = cartridge {
+                    read_board_tag(cartridge, name, tag);
+                }
+            }
+            "/cartridge" => {
+                if let Some(cartridge) = cartridge.take() {
+                    print_entry(&cartridge, &title);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn read_board_tag(cartridge: &mut Cartridge, name: &str, tag: &str) {
+    match name {
+        "board" => cartridge.mapper = attribute(tag, "mapper").and_then(|m| m.parse().ok()),
+
+        // The solder pads are named after the mirroring they select
+        "pad" if attribute(tag, "h") == Some("1") => cartridge.mirroring = Some("H"),
+        "pad" if attribute(tag, "v") == Some("1") => cartridge.mirroring = Some("V"),
+
+        // Boards with their own nametable RAM have four-screen mirroring
+        "vram" => cartridge.mirroring = Some("4"),
+        "wram" if attribute(tag, "battery") == Some("1") => cartridge.has_battery = true,
+        _ => (),
+    }
+}
+
+// Dumps without a checksum or a known mapper can't be used to correct anything. Boards whose
+// mapper controls mirroring have no pads, and are given horizontal mirroring until the game
+// changes it.
+fn print_entry(cartridge: &Cartridge, title: &str) {
+    let mapper = match cartridge.mapper {
+        Some(mapper) if cartridge.crc32.len() == 8 => mapper,
+        _ => return,
+    };
+    let sha1 = if cartridge.sha1.len() == 40 {
+        &cartridge.sha1[..]
+    } else {
+        "-"
+    };
+    println!("{} {} {} 0 {} {} {} {}",
+             cartridge.crc32,
+             sha1,
+             mapper,
+             cartridge.mirroring.unwrap_or("H"),
+             cartridge.has_battery as u8,
+             cartridge.region,
+             title);
+}
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!(" {}=\"", name);
+    tag.find(&pattern)
+        .map(|start| &tag[start + pattern.len()..])
+        .and_then(|rest| rest.split('"').next())
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
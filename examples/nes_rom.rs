@@ -6,34 +6,54 @@ extern crate log;
 extern crate env_logger;
 extern crate rs_nes;
 
+use rs_nes::apu::Apu;
+use rs_nes::audio::NullSink;
 use rs_nes::cpu::*;
 use rs_nes::input::InputBase;
+use rs_nes::mapper::{self, Cartridge};
 use rs_nes::memory::nes_memory::NesMemoryImpl;
 use rs_nes::ppu::{Ppu, PpuImpl};
 use rs_nes::rom::NesRom;
 use rs_nes::screen::NesScreen;
 use std::cell::RefCell;
 use std::env;
-use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// The debugger runs until the process is killed, so battery saves are written every so often
+// rather than on exit
+#[cfg(feature = "debugger")]
+const SAVE_INTERVAL_SECS: u64 = 5;
 
 #[cfg(feature = "debugger")]
 fn main() {
     env_logger::init().unwrap();
     let file = env::args().last().unwrap();
-    let rom = Rc::new(Box::new(NesRom::read(format!("{}", file)).expect("Couldn't find rom file")));
+    let rom = NesRom::read(format!("{}", file)).expect("Couldn't find rom file");
+    println!("ROM Title: {}", rom.identify().unwrap_or("Unknown"));
     println!("ROM Mapper: {} CHR banks: {} CHR size: {}",
              rom.mapper,
              rom.chr_rom_banks,
              rom.chr.len());
+    let cart = Cartridge::new(rom).expect("Unsupported cartridge");
+    let save_path = mapper::save_path(&file);
+    cart.load_save(&save_path).expect("Couldn't load save file");
+
+    let ppu = PpuImpl::new(cart.clone());
 
-    let ppu = PpuImpl::new(rom.clone());
-    let mem = NesMemoryImpl::new(rom, ppu, InputBase::default());
+    // Nothing plays the audio, so it's discarded rather than left waiting for `take_samples`
+    let apu = Apu::new(Box::new(NullSink));
+    let mem = NesMemoryImpl::new(cart.clone(), ppu, InputBase::default(), apu);
     let mut cpu = Cpu::new(mem);
     cpu.reset();
     let mut debugger = rs_nes::cpu::debugger::HttpDebugger::new(cpu);
     debugger.start();
+    let mut last_save = Instant::now();
     loop {
         debugger.step();
+        if last_save.elapsed() >= Duration::from_secs(SAVE_INTERVAL_SECS) {
+            cart.flush_save(&save_path).expect("Couldn't write save file");
+            last_save = Instant::now();
+        }
     }
 }
 
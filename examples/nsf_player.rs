@@ -0,0 +1,48 @@
+extern crate rs_nes;
+extern crate sdl2;
+
+use rs_nes::apu::Apu;
+use rs_nes::audio_out;
+use rs_nes::nsf::NsfPlayer;
+use rs_nes::rom::nsf::NsfFile;
+use std::env;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const FRAMES_PER_SECOND: u32 = 60;
+
+fn main() {
+    let sdl_context = sdl2::init().unwrap();
+
+    let args = env::args().collect::<Vec<String>>();
+    let file = &args[1];
+    let nsf = NsfFile::read(file).expect("Couldn't read NSF file");
+    println!("Title: {}", nsf.name);
+    println!("Artist: {}", nsf.artist);
+    println!("Copyright: {}", nsf.copyright);
+    println!("Songs: {}", nsf.total_songs);
+
+    let sink = audio_out::open(&sdl_context).expect("Couldn't open audio device");
+    let sample_rate = sink.sample_rate();
+    let mut apu = Apu::new(Box::new(sink));
+    apu.set_sample_rate(sample_rate);
+    let mut player = NsfPlayer::new(&nsf, apu);
+
+    // Tracks are numbered from 1 on the command line, like they are in the file
+    if let Some(track) = args.get(2) {
+        let track = track.parse::<u8>().expect("Invalid track number");
+        player.select_track(track.saturating_sub(1));
+    }
+    println!("Playing song {}", player.track() + 1);
+
+    let cycles_per_frame = (player.cpu_frequency() / FRAMES_PER_SECOND) as u64;
+    let frame_duration = Duration::new(0, 1_000_000_000 / FRAMES_PER_SECOND);
+    loop {
+        let frame_start = Instant::now();
+        player.run(cycles_per_frame);
+        let elapsed = frame_start.elapsed();
+        if elapsed < frame_duration {
+            thread::sleep(frame_duration - elapsed);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+extern crate rs_nes;
+
+use rs_nes::apu::{Apu, Channel};
+use rs_nes::audio::wav::WavRecorder;
+use rs_nes::nsf::NsfPlayer;
+use rs_nes::rom::nsf::NsfFile;
+use std::env;
+use std::path::Path;
+
+const SAMPLE_RATE: u32 = 44100;
+
+const CHANNELS: [(Channel, &'static str); 5] = [(Channel::Pulse1, "pulse_1"),
+                                                (Channel::Pulse2, "pulse_2"),
+                                                (Channel::Triangle, "triangle"),
+                                                (Channel::Noise, "noise"),
+                                                (Channel::Dmc, "dmc")];
+
+fn main() {
+    // Passing --channels also records each channel to its own file next to the output, such as
+    // song.pulse_1.wav for song.wav
+    let mut args = env::args().skip(1).collect::<Vec<String>>();
+    let record_channels = args.iter().any(|arg| arg == "--channels");
+    args.retain(|arg| arg != "--channels");
+
+    let file = &args[0];
+    let output = &args[1];
+    let nsf = NsfFile::read(file).expect("Couldn't read NSF file");
+
+    let mixed = WavRecorder::new(SAMPLE_RATE);
+    let mut apu = Apu::new(Box::new(mixed.clone()));
+    apu.set_sample_rate(SAMPLE_RATE);
+    let mut channels = Vec::new();
+    if record_channels {
+        for &(channel, name) in CHANNELS.iter() {
+            let recorder = WavRecorder::new(SAMPLE_RATE);
+            apu.set_channel_sink(channel, Some(Box::new(recorder.clone())));
+            let path = Path::new(output).with_extension(format!("{}.wav", name));
+            channels.push((recorder, path));
+        }
+    }
+    let mut player = NsfPlayer::new(&nsf, apu);
+
+    // Tracks are numbered from 1 on the command line, like they are in the file
+    if let Some(track) = args.get(2) {
+        let track = track.parse::<u8>().expect("Invalid track number");
+        player.select_track(track.saturating_sub(1));
+    }
+    let seconds = match args.get(3) {
+        Some(seconds) => seconds.parse::<u64>().expect("Invalid length"),
+        None => 30,
+    };
+
+    println!("Recording song {} for {} seconds", player.track() + 1, seconds);
+    let cycles = player.cpu_frequency() as u64 * seconds;
+    player.run(cycles);
+    player.apu_mut().flush_sinks();
+
+    mixed.save(output).expect("Couldn't save recording");
+    for &(ref recorder, ref path) in &channels {
+        recorder.save(path).expect("Couldn't save recording");
+    }
+}
@@ -6,16 +6,20 @@ use rs_nes::audio::Audio;
 use rs_nes::audio_out;
 use rs_nes::cpu::*;
 use rs_nes::input::{Button, Input, InputBase};
+use rs_nes::mapper::{self, Cartridge};
 use rs_nes::memory::Memory;
 use rs_nes::memory::nes_memory::NesMemoryImpl;
 use rs_nes::ppu::{Ppu, PpuImpl};
 use rs_nes::rom::NesRom;
+use rs_nes::rom::fds::FdsImage;
 use sdl2::audio::{AudioCallback, AudioSpecDesired};
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::pixels::PixelFormatEnum;
 use std::env;
-use std::rc::Rc;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
 use std::sync::Arc;
 use std::sync::RwLock;
 use std::thread;
@@ -29,17 +33,17 @@ fn main() {
 
     // INIT NES
     let file = env::args().last().unwrap();
-    let rom = Rc::new(Box::new(NesRom::read(format!("{}", file)).expect("Couldn't find rom file")));
-    println!("ROM Mapper: {} CHR banks: {} CHR size: {}",
-             rom.mapper,
-             rom.chr_rom_banks,
-             rom.chr.len());
+    let cart = load_cartridge(&file);
+    let save_path = mapper::save_path(&file);
+    cart.load_save(&save_path).expect("Couldn't load save file");
 
-    let audio_output_buffer = audio_out::open(&sdl_context);
-    let apu = Apu::new(audio_output_buffer);
-    let ppu = PpuImpl::new(rom.clone());
+    let sink = audio_out::open(&sdl_context).expect("Couldn't open audio device");
+    let sample_rate = sink.sample_rate();
+    let mut apu = Apu::new(Box::new(sink));
+    apu.set_sample_rate(sample_rate);
+    let ppu = PpuImpl::new(cart.clone());
     let input = InputBase::default();
-    let mem = NesMemoryImpl::new(rom, ppu, input, apu);
+    let mem = NesMemoryImpl::new(cart.clone(), ppu, input, apu);
     let mut cpu = Cpu::new(mem);
     cpu.reset();
 
@@ -68,6 +72,7 @@ fn main() {
     let mut event_pump = sdl_context.event_pump().unwrap();
     let mut accumulator = Duration::new(0, 0);
     let mut previous_clock = Instant::now();
+    let mut disk_side = 0;
 
     'running: loop {
         for event in event_pump.poll_iter() {
@@ -86,6 +91,14 @@ fn main() {
                         Keycode::Return => cpu.memory.input().player1_press(Button::Start),
                         Keycode::J => cpu.memory.input().player1_press(Button::B),
                         Keycode::K => cpu.memory.input().player1_press(Button::A),
+                        Keycode::F5 => {
+                            cart.flush_save(&save_path).expect("Couldn't write save file")
+                        }
+                        Keycode::F6 if cart.disk_sides() > 0 => {
+                            disk_side = (disk_side + 1) % cart.disk_sides();
+                            println!("Inserting disk side {}", disk_side);
+                            cart.insert_disk(Some(disk_side))
+                        }
                         _ => (),
                     }
                 }
@@ -116,18 +129,49 @@ fn main() {
         while accumulator >= fixed_time_stamp {
             accumulator -= fixed_time_stamp;
             loop {
-                if cpu.step() == Interrupt::Nmi {
-                    let screen_buffer = &*cpu.memory.screen().screen_buffer;
-                    texture
-                        .update(None, screen_buffer, SCREEN_WIDTH as usize * 3)
-                        .unwrap();
-                    renderer.clear();
-                    renderer.copy(&texture, None, None).unwrap();
-                    renderer.present();
-                    break;
+                match cpu.step() {
+                    StepResult::Executed(Interrupt::Nmi) => {
+                        let screen_buffer = &*cpu.memory.screen().screen_buffer;
+                        texture
+                            .update(None, screen_buffer, SCREEN_WIDTH as usize * 3)
+                            .unwrap();
+                        renderer.clear();
+                        renderer.copy(&texture, None, None).unwrap();
+                        renderer.present();
+                        break;
+                    }
+                    StepResult::Executed(_) => (),
+                    StepResult::Jammed { opcode, addr } |
+                    StepResult::IllegalOpcode { opcode, addr } => {
+                        println!("CPU jammed by opcode {:0>2X} at {:0>4X}", opcode, addr);
+                        break 'running;
+                    }
                 }
             }
         }
         thread::sleep(fixed_time_stamp - accumulator);
     }
+
+    cart.flush_save(&save_path).expect("Couldn't write save file");
+}
+
+fn load_cartridge(file: &str) -> Cartridge {
+    let extension = Path::new(file).extension().and_then(|ext| ext.to_str());
+    if extension == Some("fds") || extension == Some("qd") {
+        let image = FdsImage::read(file).expect("Couldn't read disk image");
+        let mut bios = Vec::new();
+        File::open(mapper::fds_bios_path(file))
+            .and_then(|mut f| f.read_to_end(&mut bios))
+            .expect("Couldn't find disksys.rom next to the disk image");
+        println!("Disk sides: {}", image.sides.len());
+        return Cartridge::from_fds(bios, image).expect("Invalid FDS BIOS");
+    }
+
+    let rom = NesRom::read(file).expect("Couldn't find rom file");
+    println!("ROM Title: {}", rom.identify().unwrap_or("Unknown"));
+    println!("ROM Mapper: {} CHR banks: {} CHR size: {}",
+             rom.mapper,
+             rom.chr_rom_banks,
+             rom.chr.len());
+    Cartridge::new(rom).expect("Unsupported cartridge")
 }
@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod spec_tests;
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+// Each step is spread over this many output samples around where it occurs, so output is delayed
+// by half of them
+const KERNEL_WIDTH: usize = 16;
+const HALF_WIDTH: usize = KERNEL_WIDTH / 2;
+
+// The number of sub-sample positions a step can be placed at
+const PHASE_COUNT: usize = 32;
+
+// The cutoff of the low-pass filter, relative to the output's Nyquist frequency
+const CUTOFF: f64 = 0.9;
+
+lazy_static! {
+    // For each phase, the derivative of a band-limited step that occurs that far between two
+    // output samples. It's a windowed sinc, normalized so that the step it produces has a height of
+    // exactly 1.
+    static ref KERNEL: Vec<[f64; KERNEL_WIDTH]> = {
+        (0..PHASE_COUNT)
+            .map(|phase| {
+                let center = (HALF_WIDTH - 1) as f64 + phase as f64 / PHASE_COUNT as f64;
+                let mut taps = [0.0; KERNEL_WIDTH];
+                for (i, tap) in taps.iter_mut().enumerate() {
+                    let x = i as f64 - center;
+                    let sinc = if x == 0.0 {
+                        1.0
+                    } else {
+                        (PI * CUTOFF * x).sin() / (PI * CUTOFF * x)
+                    };
+
+                    // Blackman window, which falls to 0 at HALF_WIDTH samples from the center
+                    let w = PI * x / HALF_WIDTH as f64;
+                    let window = if x.abs() < HALF_WIDTH as f64 {
+                        0.42 + 0.5 * w.cos() + 0.08 * (2.0 * w).cos()
+                    } else {
+                        0.0
+                    };
+                    *tap = sinc * window;
+                }
+
+                let sum = taps.iter().fold(0.0, |sum, tap| sum + tap);
+                for tap in taps.iter_mut() {
+                    *tap /= sum;
+                }
+                taps
+            })
+            .collect()
+    };
+}
+
+/// Converts the APU output, which can change every CPU cycle, to the host's sample rate without
+/// aliasing.
+///
+/// Rather than sampling the output, each change in level is recorded as a band-limited step at the
+/// exact cycle it happened on. The steps are accumulated as differences, which are summed to
+/// produce the output.
+pub struct BlipBuffer {
+    clock_rate: u32,
+    sample_rate: u32,
+    phase: u32,
+    level: f32,
+    // The differences between each of the next KERNEL_WIDTH output samples and the one before it
+    differences: VecDeque<f64>,
+    output: f64,
+}
+
+impl Default for BlipBuffer {
+    fn default() -> Self {
+        BlipBuffer::new(super::CLOCK_RATE, super::OUTPUT_SAMPLE_RATE)
+    }
+}
+
+impl BlipBuffer {
+    pub fn new(clock_rate: u32, sample_rate: u32) -> Self {
+        BlipBuffer {
+            clock_rate: clock_rate,
+            sample_rate: sample_rate,
+            phase: 0,
+            level: 0.0,
+            differences: vec![0.0; KERNEL_WIDTH].into_iter().collect(),
+            output: 0.0,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Adds the level for one clock, returning an output sample if this clock completes one
+    pub fn clock(&mut self, level: f32) -> Option<f32> {
+        if level != self.level {
+            let delta = (level - self.level) as f64;
+            self.level = level;
+            self.add_step(delta);
+        }
+
+        // The phase advances by the sample rate every clock, and a sample is due each time it
+        // passes the clock rate. This keeps the output rate exact without any rounding drift.
+        self.phase += self.sample_rate;
+        if self.phase < self.clock_rate {
+            return None;
+        }
+
+        self.phase -= self.clock_rate;
+        self.output += self.differences.pop_front().unwrap();
+        self.differences.push_back(0.0);
+        Some(self.output as f32)
+    }
+
+    fn add_step(&mut self, delta: f64) {
+        // How far the current clock is between the previous output sample and the next one
+        let kernel_phase = (self.phase as u64 * PHASE_COUNT as u64 / self.clock_rate as u64) as
+                           usize;
+        for (difference, tap) in self.differences.iter_mut().zip(KERNEL[kernel_phase].iter()) {
+            *difference += delta * tap;
+        }
+    }
+}
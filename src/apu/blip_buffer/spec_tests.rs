@@ -0,0 +1,51 @@
+use super::{BlipBuffer, HALF_WIDTH, KERNEL_WIDTH};
+
+#[test]
+fn output_rate() {
+    let mut blip_buffer = BlipBuffer::new(1_789_773, 44_100);
+    let samples = (0..1_789_773).filter_map(|_| blip_buffer.clock(0.5)).count();
+    assert_eq!(44_100, samples);
+}
+
+#[test]
+fn constant_level_is_silent() {
+    let mut blip_buffer = BlipBuffer::new(40, 1);
+    for _ in 0..40 * KERNEL_WIDTH {
+        if let Some(sample) = blip_buffer.clock(0.0) {
+            assert_eq!(0.0, sample);
+        }
+    }
+}
+
+#[test]
+fn step_settles_at_new_level() {
+    let mut blip_buffer = BlipBuffer::new(40, 1);
+    let samples = (0..40 * KERNEL_WIDTH * 2)
+        .filter_map(|i| blip_buffer.clock(if i >= 20 { 0.75 } else { 0.0 }))
+        .collect::<Vec<f32>>();
+
+    // The step is delayed by half the kernel, and rises smoothly instead of all at once
+    assert!(samples[0].abs() < 0.01);
+    assert!(samples.iter().any(|&sample| sample > 0.1 && sample < 0.65));
+    for sample in &samples[KERNEL_WIDTH..] {
+        assert!((sample - 0.75).abs() < 0.000001);
+    }
+}
+
+#[test]
+fn steps_are_placed_between_samples() {
+    // The same step, a quarter and three quarters of the way into an output sample
+    let mut early = BlipBuffer::new(40, 1);
+    let mut late = BlipBuffer::new(40, 1);
+    let mut early_samples = Vec::new();
+    let mut late_samples = Vec::new();
+    for i in 0..40 * KERNEL_WIDTH * 2 {
+        early_samples.extend(early.clock(if i >= 10 { 1.0 } else { 0.0 }));
+        late_samples.extend(late.clock(if i >= 30 { 1.0 } else { 0.0 }));
+    }
+
+    // The earlier step is further along while the output is rising
+    let rising = HALF_WIDTH - 1;
+    assert!(early_samples[rising] > late_samples[rising]);
+    assert!((early_samples[KERNEL_WIDTH] - late_samples[KERNEL_WIDTH]).abs() < 0.000001);
+}
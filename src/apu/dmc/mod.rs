@@ -1,24 +1,202 @@
+#[cfg(test)]
+mod spec_tests;
+
+use apu::timer::Timer;
+
+// The number of CPU cycles between output level changes, indexed by the rate in $4010 (NTSC)
+const RATE_TABLE: [u16; 16] = [428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106,
+                               84, 72, 54];
+
 pub trait Dmc: Default {
     fn write_4010(&mut self, val: u8);
     fn write_4011(&mut self, val: u8);
     fn write_4012(&mut self, val: u8);
     fn write_4013(&mut self, val: u8);
-    fn output(&self) -> f32;
+    fn clock_timer(&mut self);
+    fn set_enabled(&mut self, enabled: bool);
+    fn bytes_remaining_is_nonzero(&self) -> bool;
+    fn interrupt_flag(&self) -> bool;
+    fn clear_interrupt_flag(&mut self);
+
+    /// The address of the next sample byte if the sample buffer needs to be refilled. Each fetch
+    /// is requested once, and must be completed with `fill_sample_buffer`.
+    fn fetch_request(&mut self) -> Option<u16>;
+    fn fill_sample_buffer(&mut self, val: u8);
+    fn output(&self) -> u8;
+}
+
+pub struct DmcImpl {
+    irq_enabled: bool,
+    loop_flag: bool,
+    timer: Timer,
+
+    // Memory reader
+    sample_address: u16,
+    sample_length: u16,
+    current_address: u16,
+    bytes_remaining: u16,
+    sample_buffer: Option<u8>,
+    fetching: bool,
+
+    // Output unit
+    shift_register: u8,
+    bits_remaining: u8,
+    silence: bool,
+    output_level: u8,
+
+    interrupt_flag: bool,
+}
+
+impl Default for DmcImpl {
+    fn default() -> Self {
+        let mut timer = Timer::default();
+        timer.set_period(RATE_TABLE[0] - 1);
+        DmcImpl {
+            irq_enabled: false,
+            loop_flag: false,
+            timer: timer,
+            sample_address: 0xc000,
+            sample_length: 1,
+            current_address: 0xc000,
+            bytes_remaining: 0,
+            sample_buffer: None,
+            fetching: false,
+            shift_register: 0,
+            bits_remaining: 8,
+            silence: true,
+            output_level: 0,
+            interrupt_flag: false,
+        }
+    }
 }
 
-#[derive(Default)]
-pub struct DmcImpl {}
+impl DmcImpl {
+    fn restart(&mut self) {
+        self.current_address = self.sample_address;
+        self.bytes_remaining = self.sample_length;
+    }
+
+    fn clock_output_unit(&mut self) {
+        // The output level only changes if the shifter holds sample data, and it never wraps
+        if !self.silence {
+            if self.shift_register & 1 == 1 {
+                if self.output_level <= 125 {
+                    self.output_level += 2;
+                }
+            } else if self.output_level >= 2 {
+                self.output_level -= 2;
+            }
+        }
+        self.shift_register >>= 1;
+        self.bits_remaining -= 1;
+
+        // When an output cycle ends, a new one begins with the contents of the sample buffer
+        if self.bits_remaining == 0 {
+            self.bits_remaining = 8;
+            match self.sample_buffer.take() {
+                Some(sample) => {
+                    self.silence = false;
+                    self.shift_register = sample;
+                }
+                None => self.silence = true,
+            }
+        }
+    }
+}
 
 impl Dmc for DmcImpl {
-    fn write_4010(&mut self, _: u8) {}
+    fn write_4010(&mut self, val: u8) {
+        // IL-- RRRR : IRQ enabled flag (I), loop flag (L), rate index (R)
+        // Clearing the IRQ enabled flag also clears the interrupt flag.
+        self.irq_enabled = val & 0b_1000_0000 > 0;
+        self.loop_flag = val & 0b_0100_0000 > 0;
+        self.timer
+            .set_period(RATE_TABLE[val as usize & 0b_1111] - 1);
 
-    fn write_4011(&mut self, _: u8) {}
+        if !self.irq_enabled {
+            self.interrupt_flag = false;
+        }
+    }
 
-    fn write_4012(&mut self, _: u8) {}
+    fn write_4011(&mut self, val: u8) {
+        // -DDD DDDD : The output level is loaded directly
+        self.output_level = val & 0b_0111_1111;
+    }
 
-    fn write_4013(&mut self, _: u8) {}
+    fn write_4012(&mut self, val: u8) {
+        // AAAA AAAA : Sample address = %11AAAAAA.AA000000 = $C000 + (A * 64)
+        self.sample_address = 0xc000 | ((val as u16) << 6);
+    }
+
+    fn write_4013(&mut self, val: u8) {
+        // LLLL LLLL : Sample length = %LLLL.LLLL0001 = (L * 16) + 1 bytes
+        self.sample_length = ((val as u16) << 4) | 1;
+    }
+
+    fn clock_timer(&mut self) {
+        if self.timer.clock() {
+            self.clock_output_unit();
+        }
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        // Disabling lets the sample buffer play out, while enabling only restarts the sample if
+        // it has already finished.
+        if !enabled {
+            self.bytes_remaining = 0;
+        } else if self.bytes_remaining == 0 {
+            self.restart();
+        }
+    }
+
+    fn bytes_remaining_is_nonzero(&self) -> bool {
+        self.bytes_remaining > 0
+    }
+
+    fn interrupt_flag(&self) -> bool {
+        self.interrupt_flag
+    }
+
+    fn clear_interrupt_flag(&mut self) {
+        self.interrupt_flag = false;
+    }
+
+    fn fetch_request(&mut self) -> Option<u16> {
+        if self.sample_buffer.is_none() && self.bytes_remaining > 0 && !self.fetching {
+            self.fetching = true;
+            Some(self.current_address)
+        } else {
+            None
+        }
+    }
+
+    fn fill_sample_buffer(&mut self, val: u8) {
+        self.fetching = false;
+        self.sample_buffer = Some(val);
+
+        // The address wraps around to $8000 rather than $0000
+        self.current_address = if self.current_address == 0xffff {
+            0x8000
+        } else {
+            self.current_address + 1
+        };
+
+        // The sample may have been stopped by $4015 while the fetch was in progress
+        if self.bytes_remaining == 0 {
+            return;
+        }
+
+        self.bytes_remaining -= 1;
+        if self.bytes_remaining == 0 {
+            if self.loop_flag {
+                self.restart();
+            } else if self.irq_enabled {
+                self.interrupt_flag = true;
+            }
+        }
+    }
 
-    fn output(&self) -> f32 {
-        0.0
+    fn output(&self) -> u8 {
+        self.output_level
     }
 }
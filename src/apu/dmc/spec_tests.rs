@@ -0,0 +1,120 @@
+use super::{Dmc, DmcImpl};
+
+// Clocks the DMC through `n` output bits at the fastest rate
+fn clock_bits(dmc: &mut DmcImpl, n: u32) {
+    for _ in 0..n * 54 {
+        dmc.clock_timer();
+    }
+}
+
+fn dmc_with_sample(address: u8, length: u8) -> DmcImpl {
+    let mut dmc = DmcImpl::default();
+    dmc.write_4010(0x0f);
+    dmc.write_4012(address);
+    dmc.write_4013(length);
+    dmc.set_enabled(true);
+    dmc
+}
+
+#[test]
+fn sample_address_and_length() {
+    let mut dmc = dmc_with_sample(0xff, 0x01);
+    assert!(dmc.bytes_remaining_is_nonzero());
+    assert_eq!(0xffc0, dmc.current_address);
+    assert_eq!(17, dmc.bytes_remaining);
+    assert_eq!(Some(0xffc0), dmc.fetch_request());
+}
+
+#[test]
+fn fetch_is_requested_once_per_byte() {
+    let mut dmc = dmc_with_sample(0x00, 0x01);
+    assert_eq!(Some(0xc000), dmc.fetch_request());
+    assert_eq!(None, dmc.fetch_request());
+
+    dmc.fill_sample_buffer(0x55);
+    assert_eq!(16, dmc.bytes_remaining);
+
+    // The buffer is full until the output unit takes the byte
+    assert_eq!(None, dmc.fetch_request());
+    clock_bits(&mut dmc, 8);
+    assert_eq!(Some(0xc001), dmc.fetch_request());
+}
+
+#[test]
+fn address_wraps_to_8000() {
+    let mut dmc = dmc_with_sample(0xff, 0x04);
+    dmc.current_address = 0xffff;
+    dmc.fetch_request();
+    dmc.fill_sample_buffer(0);
+    assert_eq!(0x8000, dmc.current_address);
+}
+
+#[test]
+fn interrupt_at_end_of_sample() {
+    let mut dmc = dmc_with_sample(0x00, 0x00);
+    dmc.write_4010(0x8f);
+    dmc.fetch_request();
+    dmc.fill_sample_buffer(0);
+    assert!(!dmc.bytes_remaining_is_nonzero());
+    assert!(dmc.interrupt_flag());
+
+    // Clearing the IRQ enabled flag acknowledges the interrupt
+    dmc.write_4010(0x0f);
+    assert!(!dmc.interrupt_flag());
+}
+
+#[test]
+fn looping_sample_restarts_without_interrupt() {
+    let mut dmc = dmc_with_sample(0x10, 0x00);
+    dmc.write_4010(0xcf);
+    dmc.fetch_request();
+    dmc.fill_sample_buffer(0);
+    assert!(!dmc.interrupt_flag());
+    assert_eq!(1, dmc.bytes_remaining);
+    assert_eq!(0xc400, dmc.current_address);
+}
+
+#[test]
+fn disabling_stops_sample() {
+    let mut dmc = dmc_with_sample(0x00, 0x01);
+    dmc.set_enabled(false);
+    assert!(!dmc.bytes_remaining_is_nonzero());
+    assert_eq!(None, dmc.fetch_request());
+
+    // Enabling again restarts it
+    dmc.set_enabled(true);
+    assert_eq!(17, dmc.bytes_remaining);
+}
+
+#[test]
+fn output_follows_sample_bits() {
+    let mut dmc = dmc_with_sample(0x00, 0x01);
+    dmc.write_4011(64);
+    dmc.fetch_request();
+    dmc.fill_sample_buffer(0b_0000_1111);
+
+    // The output unit starts silent, and picks up the sample when its current cycle ends
+    clock_bits(&mut dmc, 8);
+    assert_eq!(64, dmc.output());
+
+    clock_bits(&mut dmc, 4);
+    assert_eq!(72, dmc.output());
+    clock_bits(&mut dmc, 4);
+    assert_eq!(64, dmc.output());
+}
+
+#[test]
+fn output_level_doesnt_wrap() {
+    let mut dmc = dmc_with_sample(0x00, 0x01);
+    dmc.write_4011(126);
+    dmc.fetch_request();
+    dmc.fill_sample_buffer(0xff);
+    clock_bits(&mut dmc, 16);
+    assert_eq!(126, dmc.output());
+
+    dmc.write_4011(1);
+    dmc.fetch_request();
+    dmc.fill_sample_buffer(0x00);
+    clock_bits(&mut dmc, 16);
+    assert_eq!(1, dmc.output());
+}
@@ -1,6 +1,7 @@
-#![allow(dead_code)]
+#[cfg(test)]
+mod spec_tests;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 enum SequenceMode {
     FourStep,
     FiveStep,
@@ -12,15 +13,21 @@ impl Default for SequenceMode {
     }
 }
 
+#[derive(Debug, PartialEq)]
 pub enum Clock {
     None,
     All(bool),
     EnvelopeAndTriangleLinearCounter,
+    /// Sets the frame interrupt flag without clocking any units
+    Interrupt,
 }
 
 pub trait FrameCounter: Default {
-    fn write_4017(&mut self, val: u8) -> Clock;
+    fn write_4017(&mut self, val: u8);
     fn half_step(&mut self) -> Clock;
+
+    /// Whether the frame interrupt flag will be set by the next call to `half_step`
+    fn interrupt_imminent(&self) -> bool;
 }
 
 #[derive(Default)]
@@ -28,15 +35,26 @@ pub struct FrameCounterImpl {
     half_steps: u16,
     interrupt_inhibit: bool,
     mode: SequenceMode,
+
+    // A $4017 write resets the sequencer a few cycles after it occurs
+    pending_mode: SequenceMode,
+    reset_delay: u8,
+    odd_cycle: bool,
+}
+
+impl FrameCounterImpl {
+    fn sets_interrupt(&self, half_steps: u16) -> bool {
+        self.mode == SequenceMode::FourStep && !self.interrupt_inhibit &&
+        half_steps >= 29828 && half_steps <= 29830
+    }
 }
 
 impl FrameCounter for FrameCounterImpl {
-    fn write_4017(&mut self, val: u8) -> Clock {
+    fn write_4017(&mut self, val: u8) {
         // Bit 7    M--- ----   Sequencer mode: 0 selects 4-step sequence, 1 selects 5-step sequence
         // Bit 6    -I-- ----   Interrupt inhibit flag. If set, the frame interrupt flag is cleared,
         //                      otherwise it is unaffected.
         //
-        // TODO:
         // Side effects: After 3 or 4 CPU clock cycles*, the timer is reset. If the mode flag is
         // set, then both "quarter frame" and "half frame" signals are also generated.
         //
@@ -44,33 +62,64 @@ impl FrameCounter for FrameCounterImpl {
         //   $4017 write cycle, and if the write occurs between APU cycles, the effects occurs 4
         //   CPU cycles after the write cycle.
         self.interrupt_inhibit = val & 0b_0100_0000 > 0;
-
-        // Writing to $4017 with bit 7 set ($80) will immediately clock all of its controlled units
-        // at the beginning of the 5-step sequence; with bit 7 clear, only the sequence is reset
-        // without clocking any of its units.
-        if val & 0b_1000_0000 == 0 {
-            self.mode = SequenceMode::FourStep;
-            Clock::None
+        self.pending_mode = if val & 0b_1000_0000 == 0 {
+            SequenceMode::FourStep
         } else {
-            self.mode = SequenceMode::FiveStep;
-            Clock::All(false)
-        }
+            SequenceMode::FiveStep
+        };
+
+        // The write cycle itself is yet to be clocked, so it's counted in the delay
+        self.reset_delay = if self.odd_cycle { 5 } else { 4 };
     }
 
     fn half_step(&mut self) -> Clock {
+        self.odd_cycle = !self.odd_cycle;
+
+        if self.reset_delay > 0 {
+            self.reset_delay -= 1;
+            if self.reset_delay == 0 {
+                self.half_steps = 0;
+                self.mode = self.pending_mode;
+
+                // Resetting into the 5-step sequence immediately clocks all of its units, while
+                // the 4-step sequence is reset without clocking any of them.
+                return match self.mode {
+                           SequenceMode::FourStep => Clock::None,
+                           SequenceMode::FiveStep => Clock::All(false),
+                       };
+            }
+        }
+
         self.half_steps += 1;
+        let interrupt = self.sets_interrupt(self.half_steps);
+
+        // The frame interrupt flag is set on three consecutive cycles at the end of the 4-step
+        // sequence, so a read of $4015 on the first two can't clear it for good.
         match (self.half_steps, self.mode) {
-            (7547, _) => Clock::EnvelopeAndTriangleLinearCounter,
+            (7457, _) => Clock::EnvelopeAndTriangleLinearCounter,
             (14913, _) => Clock::All(false),
             (22371, _) => Clock::EnvelopeAndTriangleLinearCounter,
-            (29829, SequenceMode::FourStep) => Clock::All(!self.interrupt_inhibit),
+            (29829, SequenceMode::FourStep) => Clock::All(interrupt),
             (29830, SequenceMode::FourStep) |
             (37282, SequenceMode::FiveStep) => {
                 self.half_steps = 0;
-                Clock::None
+                if interrupt {
+                    Clock::Interrupt
+                } else {
+                    Clock::None
+                }
             }
             (37281, SequenceMode::FiveStep) => Clock::All(false),
+            _ if interrupt => Clock::Interrupt,
             _ => Clock::None,
         }
     }
+
+    fn interrupt_imminent(&self) -> bool {
+        // The next cycle resets the sequencer instead of stepping it
+        if self.reset_delay == 1 {
+            return false;
+        }
+        self.sets_interrupt(self.half_steps + 1)
+    }
 }
@@ -0,0 +1,95 @@
+use super::{Clock, FrameCounter, FrameCounterImpl};
+
+// Steps the frame counter until it produces a clock other than `Clock::None`, returning the clock
+// and the number of steps it took
+fn run_until_clock(frame_counter: &mut FrameCounterImpl) -> (Clock, u32) {
+    let mut steps = 0;
+    loop {
+        steps += 1;
+        let clock = frame_counter.half_step();
+        if clock != Clock::None {
+            return (clock, steps);
+        }
+    }
+}
+
+#[test]
+fn four_step_sequence() {
+    let mut frame_counter = FrameCounterImpl::default();
+    assert_eq!((Clock::EnvelopeAndTriangleLinearCounter, 7457),
+               run_until_clock(&mut frame_counter));
+    assert_eq!((Clock::All(false), 14913 - 7457),
+               run_until_clock(&mut frame_counter));
+    assert_eq!((Clock::EnvelopeAndTriangleLinearCounter, 22371 - 14913),
+               run_until_clock(&mut frame_counter));
+
+    // The interrupt flag is set on three consecutive cycles
+    assert!(!frame_counter.interrupt_imminent());
+    assert_eq!((Clock::Interrupt, 29828 - 22371),
+               run_until_clock(&mut frame_counter));
+    assert!(frame_counter.interrupt_imminent());
+    assert_eq!((Clock::All(true), 1), run_until_clock(&mut frame_counter));
+    assert_eq!((Clock::Interrupt, 1), run_until_clock(&mut frame_counter));
+    assert!(!frame_counter.interrupt_imminent());
+
+    // The sequence repeats every 29830 cycles
+    assert_eq!((Clock::EnvelopeAndTriangleLinearCounter, 7457),
+               run_until_clock(&mut frame_counter));
+}
+
+#[test]
+fn interrupt_inhibit() {
+    let mut frame_counter = FrameCounterImpl::default();
+    frame_counter.write_4017(0x40);
+    for _ in 0..22371 + 4 {
+        frame_counter.half_step();
+    }
+    assert_eq!((Clock::All(false), 29829 - 22371),
+               run_until_clock(&mut frame_counter));
+    assert_eq!((Clock::EnvelopeAndTriangleLinearCounter, 7457 + 1),
+               run_until_clock(&mut frame_counter));
+}
+
+#[test]
+fn five_step_sequence() {
+    let mut frame_counter = FrameCounterImpl::default();
+    frame_counter.write_4017(0x80);
+
+    // Entering 5-step mode clocks everything once the reset takes effect
+    assert_eq!((Clock::All(false), 4), run_until_clock(&mut frame_counter));
+    assert_eq!((Clock::EnvelopeAndTriangleLinearCounter, 7457),
+               run_until_clock(&mut frame_counter));
+    assert_eq!((Clock::All(false), 14913 - 7457),
+               run_until_clock(&mut frame_counter));
+    assert_eq!((Clock::EnvelopeAndTriangleLinearCounter, 22371 - 14913),
+               run_until_clock(&mut frame_counter));
+    assert_eq!((Clock::All(false), 37281 - 22371),
+               run_until_clock(&mut frame_counter));
+    assert_eq!((Clock::EnvelopeAndTriangleLinearCounter, 7457 + 1),
+               run_until_clock(&mut frame_counter));
+}
+
+#[test]
+fn reset_delay_depends_on_cycle_parity() {
+    // The write cycle is clocked after the write, and the reset lands 3 cycles after that
+    let mut frame_counter = FrameCounterImpl::default();
+    frame_counter.write_4017(0x80);
+    assert_eq!((Clock::All(false), 4), run_until_clock(&mut frame_counter));
+
+    // Between APU cycles, it takes one cycle longer
+    let mut frame_counter = FrameCounterImpl::default();
+    frame_counter.half_step();
+    frame_counter.write_4017(0x80);
+    assert_eq!((Clock::All(false), 5), run_until_clock(&mut frame_counter));
+}
+
+#[test]
+fn reset_postpones_sequence() {
+    let mut frame_counter = FrameCounterImpl::default();
+    for _ in 0..7000 {
+        frame_counter.half_step();
+    }
+    frame_counter.write_4017(0x00);
+    assert_eq!((Clock::EnvelopeAndTriangleLinearCounter, 4 + 7457),
+               run_until_clock(&mut frame_counter));
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod spec_tests;
+
+// pulse_out = 95.52 / (8128.0 / (pulse1 + pulse2) + 100)
+const PULSE_TABLE: [f32; 31] = [0.0,
+                                0.011609139523578026,
+                                0.022939481268011527,
+                                0.03400094921689606,
+                                0.04480300187617261,
+                                0.05535465924895688,
+                                0.06566452795600367,
+                                0.07574082464884459,
+                                0.08559139784946236,
+                                0.09522374833850243,
+                                0.10464504820333041,
+                                0.11386215864759427,
+                                0.12288164665523155,
+                                0.13170980059397538,
+                                0.14035264483627205,
+                                0.1488159534690486,
+                                0.15710526315789472,
+                                0.16522588522588522,
+                                0.1731829170024174,
+                                0.18098125249301955,
+                                0.18862559241706162,
+                                0.19612045365662886,
+                                0.20347017815646784,
+                                0.21067894131185272,
+                                0.21775075987841944,
+                                0.2246894994354535,
+                                0.2314988814317673,
+                                0.23818248984115256,
+                                0.2447437774524158,
+                                0.2511860718171926,
+                                0.25751258087706685];
+
+lazy_static! {
+    // tnd_out = 163.67 / (24329.0 / (3 * triangle + 2 * noise + dmc) + 100)
+    static ref TND_TABLE: [f32; 203] = {
+        let mut table = [0.0; 203];
+        for n in 1..203 {
+            table[n] = 163.67 / (24329.0 / n as f32 + 100.0);
+        }
+        table
+    };
+}
+
+/// Combines the channel outputs the way the APU's nonlinear DACs do, using the lookup table
+/// approximation. The result ranges from 0.0 to 1.0.
+pub fn mix(pulse_1: u8, pulse_2: u8, triangle: u8, noise: u8, dmc: u8) -> f32 {
+    let pulse_out = PULSE_TABLE[(pulse_1 + pulse_2) as usize];
+    let tnd_index = 3 * triangle as usize + 2 * noise as usize + dmc as usize;
+    pulse_out + TND_TABLE[tnd_index]
+}
@@ -0,0 +1,19 @@
+use super::mix;
+
+#[test]
+fn silence() {
+    assert_eq!(0.0, mix(0, 0, 0, 0, 0));
+}
+
+#[test]
+fn pulse_channels_share_a_dac() {
+    assert_eq!(mix(15, 0, 0, 0, 0), mix(7, 8, 0, 0, 0));
+    assert!((mix(15, 15, 0, 0, 0) - 0.2575).abs() < 0.0001);
+}
+
+#[test]
+fn triangle_noise_and_dmc_are_weighted() {
+    assert_eq!(mix(0, 0, 2, 0, 0), mix(0, 0, 0, 3, 0));
+    assert_eq!(mix(0, 0, 0, 1, 0), mix(0, 0, 0, 0, 2));
+    assert!((mix(0, 0, 15, 15, 127) - 0.7425).abs() < 0.0001);
+}
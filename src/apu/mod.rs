@@ -10,53 +10,46 @@ mod envelope;
 mod timer;
 mod dmc;
 mod sweep;
+mod mixer;
+mod blip_buffer;
 
 use apu::dmc::{Dmc, DmcImpl};
+use apu::blip_buffer::BlipBuffer;
 use apu::frame_counter::{Clock, FrameCounter, FrameCounterImpl};
 use apu::noise::{Noise, NoiseImpl};
 use apu::pulse::{Pulse, Pulse1, Pulse2};
 use apu::triangle::{Triangle, TriangleImpl};
-use audio::Audio;
-use audio_out::OutputBuffer;
-use cpu::Interrupt;
+use audio::{Audio, AudioSink};
+use std::cell::{Cell, RefCell};
 
-const SAMPLE_COUNT: usize = 178992;
-const NES_SAMPLE_RATE: u32 = 1789920; // Actual is 1789800, but this is divisible by 240.
+// The APU produces a new output level every CPU cycle
+const CLOCK_RATE: u32 = 1_789_773;
 const OUTPUT_SAMPLE_RATE: u32 = 44100;
-const TICK_FREQUENCY: u32 = 240;
-const NES_SAMPLES_PER_TICK: u32 = NES_SAMPLE_RATE / TICK_FREQUENCY;
-
-const PULSE_FREQUENCY_TABLE: [f32; 31] = [0.0,
-                                          0.011609139523578026,
-                                          0.022939481268011527,
-                                          0.03400094921689606,
-                                          0.04480300187617261,
-                                          0.05535465924895688,
-                                          0.06566452795600367,
-                                          0.07574082464884459,
-                                          0.08559139784946236,
-                                          0.09522374833850243,
-                                          0.10464504820333041,
-                                          0.11386215864759427,
-                                          0.12288164665523155,
-                                          0.13170980059397538,
-                                          0.14035264483627205,
-                                          0.1488159534690486,
-                                          0.15710526315789472,
-                                          0.16522588522588522,
-                                          0.1731829170024174,
-                                          0.18098125249301955,
-                                          0.18862559241706162,
-                                          0.19612045365662886,
-                                          0.20347017815646784,
-                                          0.21067894131185272,
-                                          0.21775075987841944,
-                                          0.2246894994354535,
-                                          0.2314988814317673,
-                                          0.23818248984115256,
-                                          0.2447437774524158,
-                                          0.2511860718171926,
-                                          0.25751258087706685];
+
+// How many samples are collected before they're written to the sink
+const SINK_BATCH_SIZE: usize = 64;
+
+// Without a sink, samples wait for `take_samples`. If they aren't taken, the oldest are dropped
+// once there's about a second of them.
+const MAX_BUFFERED_SAMPLES: usize = OUTPUT_SAMPLE_RATE as usize;
+
+/// The APU's sound channels, which can be recorded separately with `set_channel_sink`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Channel {
+    Pulse1,
+    Pulse2,
+    Triangle,
+    Noise,
+    Dmc,
+}
+
+// Resamples a single channel's output for its own sink
+struct ChannelSink {
+    channel: Channel,
+    blip_buffer: BlipBuffer,
+    samples: Vec<f32>,
+    sink: Box<AudioSink>,
+}
 
 pub type Apu = ApuImpl<Pulse1, Pulse2, TriangleImpl, NoiseImpl, FrameCounterImpl, DmcImpl>;
 
@@ -68,16 +61,42 @@ pub struct ApuImpl<P1: Pulse, P2: Pulse, T: Triangle, N: Noise, F: FrameCounter,
     triangle: T,
     noise: N,
     dmc: D,
-    status: u8,
+    frame_interrupt: Cell<bool>,
     on_full_cycle: bool,
-    output_buffer: Option<*mut OutputBuffer>,
-    sample_buffer_offset: usize,
+    sink: Option<Box<AudioSink>>,
+    channel_sinks: Vec<ChannelSink>,
+    blip_buffer: BlipBuffer,
+    samples: RefCell<Vec<f32>>,
+    expansion_audio: f32,
 }
 
 pub trait ApuContract: Audio + Default {
-    fn half_step(&mut self) -> Interrupt;
+    fn half_step(&mut self);
     fn write(&mut self, _: u16, _: u8);
     fn read_status(&self) -> u8;
+
+    /// Whether the APU is holding the IRQ line
+    fn irq_pending(&self) -> bool {
+        false
+    }
+
+    /// The address the DMC needs a sample byte read from, if any. The bus halts the CPU while it
+    /// performs the read, then hands the byte back with `dmc_fill`.
+    fn dmc_fetch_request(&mut self) -> Option<u16> {
+        None
+    }
+
+    fn dmc_fill(&mut self, _: u8) {}
+
+    /// Whether the coming CPU cycle is a put cycle, the second half of an APU cycle. DMA reads on
+    /// get cycles and writes on put cycles.
+    fn is_put_cycle(&self) -> bool {
+        false
+    }
+
+    /// The current output of cartridge expansion sound hardware, already scaled to its level
+    /// relative to the APU's own channels, which it is added to
+    fn set_expansion_audio(&mut self, _: f32) {}
 }
 
 impl<P1, P2, T, N, F, D> ApuImpl<P1, P2, T, N, F, D>
@@ -88,30 +107,99 @@ impl<P1, P2, T, N, F, D> ApuImpl<P1, P2, T, N, F, D>
           F: FrameCounter,
           D: Dmc
 {
-    pub fn new(output_buffer: Option<*mut OutputBuffer>) -> Self {
+    /// Creates an APU that writes its output to `sink`. Without one, output is only available
+    /// through `take_samples`.
+    pub fn new(sink: Box<AudioSink>) -> Self {
         let mut apu = Self::default();
-        apu.output_buffer = output_buffer;
+        apu.sink = Some(sink);
         apu
     }
 
+    pub fn set_sink(&mut self, sink: Option<Box<AudioSink>>) {
+        self.sink = sink;
+    }
+
+    /// Sends a single channel's output to `sink`, as it would sound with the other channels
+    /// silent. This is separate from the mixed output, which is unaffected.
+    pub fn set_channel_sink(&mut self, channel: Channel, sink: Option<Box<AudioSink>>) {
+        self.channel_sinks.retain(|channel_sink| channel_sink.channel != channel);
+        if let Some(sink) = sink {
+            self.channel_sinks
+                .push(ChannelSink {
+                          channel: channel,
+                          blip_buffer: BlipBuffer::new(CLOCK_RATE, self.sample_rate()),
+                          samples: Vec::new(),
+                          sink: sink,
+                      });
+        }
+    }
+
+    /// Writes samples that are waiting to fill a batch to the sinks, such as at the end of a
+    /// recording
+    pub fn flush_sinks(&mut self) {
+        if let Some(ref mut sink) = self.sink {
+            let mut samples = self.samples.borrow_mut();
+            sink.write_samples(&samples);
+            samples.clear();
+        }
+        for channel_sink in &mut self.channel_sinks {
+            channel_sink.sink.write_samples(&channel_sink.samples);
+            channel_sink.samples.clear();
+        }
+    }
+
+    /// Sets the host sample rate that output is generated at, which defaults to 44.1kHz
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.blip_buffer = BlipBuffer::new(CLOCK_RATE, sample_rate);
+        for channel_sink in &mut self.channel_sinks {
+            channel_sink.blip_buffer = BlipBuffer::new(CLOCK_RATE, sample_rate);
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.blip_buffer.sample_rate()
+    }
+
+    fn output(&self) -> f32 {
+        mixer::mix(self.pulse_1.output(),
+                   self.pulse_2.output(),
+                   self.triangle.output(),
+                   self.noise.output(),
+                   self.dmc.output()) + self.expansion_audio
+    }
+
+    fn channel_output(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Pulse1 => mixer::mix(self.pulse_1.output(), 0, 0, 0, 0),
+            Channel::Pulse2 => mixer::mix(0, self.pulse_2.output(), 0, 0, 0),
+            Channel::Triangle => mixer::mix(0, 0, self.triangle.output(), 0, 0),
+            Channel::Noise => mixer::mix(0, 0, 0, self.noise.output(), 0),
+            Channel::Dmc => mixer::mix(0, 0, 0, 0, self.dmc.output()),
+        }
+    }
+
     fn read_4015(&self) -> u8 {
         // IF-D NT21
         // DMC interrupt (I), frame interrupt (F), DMC active (D), length counter > 0 (N/T/2/1)
         //
         // - N/T/2/1 will read as 1 if the corresponding length counter is greater than 0. For the
         //   triangle channel, the status of the linear counter is irrelevant.
-        // - TODO: Reading this register clears the frame interrupt flag (but not the DMC interrupt
+        // - Reading this register clears the frame interrupt flag (but not the DMC interrupt
         //   flag).
-        // - TODO: D will read as 1 if the DMC bytes remaining is more than 0.
-        // - TODO: If an interrupt flag was set at the same moment of the read, it will read back as
-        //   1 but it will not be cleared.
-        let status_high = self.status & 0b_1111_0000;
+        // - D will read as 1 if the DMC bytes remaining is more than 0.
+        // - If an interrupt flag was set at the same moment of the read, it will read back as 1
+        //   but it will not be cleared. The read happens before the APU is clocked for the cycle,
+        //   so the frame counter is asked whether it's about to set the flag.
+        let frame_interrupt = self.frame_interrupt.get() || self.frame_counter.interrupt_imminent();
+        let status_high = ((self.dmc.interrupt_flag() as u8) << 7) |
+                          ((frame_interrupt as u8) << 6) |
+                          ((self.dmc.bytes_remaining_is_nonzero() as u8) << 4);
         let status_low = ((self.noise.length_is_nonzero() as u8) << 3) |
                          ((self.triangle.length_is_nonzero() as u8) << 2) |
                          ((self.pulse_2.length_is_nonzero() as u8) << 1) |
                          self.pulse_1.length_is_nonzero() as u8;
         let status = status_high | status_low;
-        // self.status.set(status & 0b_1011_1111);
+        self.frame_interrupt.set(false);
         status
     }
 
@@ -121,11 +209,11 @@ impl<P1, P2, T, N, F, D> ApuImpl<P1, P2, T, N, F, D>
         //
         // - Writing a zero to any of the channel enable bits will silence that channel and
         //   immediately set its length counter to 0.
-        // - TODO: If the DMC bit is clear, the DMC bytes remaining will be set to 0 and the DMC
-        //   will silence when it empties.
-        // - TODO: If the DMC bit is set, the DMC sample will be restarted only if its bytes
-        //   remaining is 0. If there are bits remaining in the 1-byte sample buffer, these will
-        //   finish playing before the next sample is fetched.
+        // - If the DMC bit is clear, the DMC bytes remaining will be set to 0 and the DMC will
+        //   silence when it empties.
+        // - If the DMC bit is set, the DMC sample will be restarted only if its bytes remaining
+        //   is 0. If there are bits remaining in the 1-byte sample buffer, these will finish
+        //   playing before the next sample is fetched.
         // - Writing to this register clears the DMC interrupt flag.
         // - Power-up and reset have the effect of writing $00, silencing all channels.
 
@@ -211,7 +299,8 @@ impl<P1, P2, T, N, F, D> ApuImpl<P1, P2, T, N, F, D>
             _ => unreachable!(),
         }
 
-        self.status = val & 0b_0111_1111;
+        self.dmc.set_enabled(val & 0b_0001_0000 > 0);
+        self.dmc.clear_interrupt_flag();
     }
 }
 
@@ -223,6 +312,14 @@ impl<P1, P2, T, N, F, D> Audio for ApuImpl<P1, P2, T, N, F, D>
           F: FrameCounter,
           D: Dmc
 {
+    fn take_samples(&self) -> Vec<f32> {
+        // Samples waiting to fill a batch belong to the sink
+        if self.sink.is_some() {
+            return Vec::new();
+        }
+        let mut samples = self.samples.borrow_mut();
+        samples.drain(..).collect()
+    }
 }
 
 impl<P1, P2, T, N, F, D> ApuContract for ApuImpl<P1, P2, T, N, F, D>
@@ -254,17 +351,14 @@ impl<P1, P2, T, N, F, D> ApuContract for ApuImpl<P1, P2, T, N, F, D>
             0x4012 => self.dmc.write_4012(val),
             0x4013 => self.dmc.write_4013(val),
             0x4015 => self.write_4015(val),
+            // Unused registers
+            0x4009 | 0x400d => (),
             0x4017 => {
-                if let Clock::All(_) = self.frame_counter.write_4017(val) {
-                    self.pulse_1.clock_length_counter();
-                    self.pulse_1.clock_envelope();
-                    self.pulse_2.clock_length_counter();
-                    self.pulse_2.clock_envelope();
-                    self.noise.clock_length_counter();
-                    self.noise.clock_envelope();
-                    self.triangle.clock_length_counter();
-                    self.triangle.clock_linear_counter();
+                // Setting the interrupt inhibit flag also clears the frame interrupt flag
+                if val & 0b_0100_0000 != 0 {
+                    self.frame_interrupt.set(false);
                 }
+                self.frame_counter.write_4017(val);
             }
             _ => panic!("Unexpected APU write"),
         }
@@ -274,8 +368,28 @@ impl<P1, P2, T, N, F, D> ApuContract for ApuImpl<P1, P2, T, N, F, D>
         self.read_4015()
     }
 
-    fn half_step(&mut self) -> Interrupt {
-        let ret = match self.frame_counter.half_step() {
+    fn set_expansion_audio(&mut self, level: f32) {
+        self.expansion_audio = level;
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.frame_interrupt.get() || self.dmc.interrupt_flag()
+    }
+
+    fn dmc_fetch_request(&mut self) -> Option<u16> {
+        self.dmc.fetch_request()
+    }
+
+    fn dmc_fill(&mut self, val: u8) {
+        self.dmc.fill_sample_buffer(val)
+    }
+
+    fn is_put_cycle(&self) -> bool {
+        self.on_full_cycle
+    }
+
+    fn half_step(&mut self) {
+        match self.frame_counter.half_step() {
             Clock::All(interrupt) => {
                 self.pulse_1.clock_length_counter();
                 self.pulse_1.clock_sweep();
@@ -289,9 +403,7 @@ impl<P1, P2, T, N, F, D> ApuContract for ApuImpl<P1, P2, T, N, F, D>
                 self.triangle.clock_linear_counter();
 
                 if interrupt {
-                    Interrupt::Irq
-                } else {
-                    Interrupt::None
+                    self.frame_interrupt.set(true);
                 }
             }
             Clock::EnvelopeAndTriangleLinearCounter => {
@@ -299,10 +411,10 @@ impl<P1, P2, T, N, F, D> ApuContract for ApuImpl<P1, P2, T, N, F, D>
                 self.pulse_2.clock_envelope();
                 self.noise.clock_envelope();
                 self.triangle.clock_linear_counter();
-                Interrupt::None
             }
-            Clock::None => Interrupt::None,
-        };
+            Clock::Interrupt => self.frame_interrupt.set(true),
+            Clock::None => (),
+        }
 
         if self.on_full_cycle {
             // These timers are clocked every other CPU cycle, or every full APU cycle
@@ -311,27 +423,38 @@ impl<P1, P2, T, N, F, D> ApuContract for ApuImpl<P1, P2, T, N, F, D>
             self.noise.clock_timer();
         }
 
-        // Triangle timer is clocked every CPU cycle, or every APU half-cycle
+        // Triangle and DMC timers are clocked every CPU cycle, or every APU half-cycle
         self.triangle.clock_timer();
+        self.dmc.clock_timer();
 
+        // Channel outputs only change when the timers or frame counter clock them, and the blip
+        // buffer records each change at the exact cycle it occurs on
+        let level = self.output();
+        if let Some(sample) = self.blip_buffer.clock(level) {
+            let mut samples = self.samples.borrow_mut();
+            samples.push(sample);
+            if let Some(ref mut sink) = self.sink {
+                if samples.len() >= SINK_BATCH_SIZE {
+                    sink.write_samples(&samples);
+                    samples.clear();
+                }
+            } else if samples.len() > MAX_BUFFERED_SAMPLES {
+                samples.drain(..MAX_BUFFERED_SAMPLES / 2);
+            }
+        }
 
+        for i in 0..self.channel_sinks.len() {
+            let level = self.channel_output(self.channel_sinks[i].channel);
+            let channel_sink = &mut self.channel_sinks[i];
+            if let Some(sample) = channel_sink.blip_buffer.clock(level) {
+                channel_sink.samples.push(sample);
+                if channel_sink.samples.len() >= SINK_BATCH_SIZE {
+                    channel_sink.sink.write_samples(&channel_sink.samples);
+                    channel_sink.samples.clear();
+                }
+            }
+        }
 
         self.on_full_cycle = !self.on_full_cycle;
-        ret
-    }
-}
-
-fn get_or_zero_sample_buffer(buffer: &mut [i16],
-                             offset: usize,
-                             audible: bool)
-                             -> Option<&mut [i16]> {
-    let buffer = &mut buffer[offset..offset + NES_SAMPLES_PER_TICK as usize];
-    if audible {
-        return Some(buffer);
-    }
-
-    for dest in buffer.iter_mut() {
-        *dest = 0;
     }
-    None
 }
@@ -15,7 +15,7 @@ pub trait Noise: Default {
     fn clock_length_counter(&mut self);
     fn zero_length_counter(&mut self);
     fn length_is_nonzero(&self) -> bool;
-    fn output(&self) -> f32;
+    fn output(&self) -> u8;
 }
 
 pub struct NoiseImpl {
@@ -99,7 +99,13 @@ impl Noise for NoiseImpl {
         self.length_counter.is_nonzero()
     }
 
-    fn output(&self) -> f32 {
-        0.0
+    fn output(&self) -> u8 {
+        // The mixer receives the current envelope volume except when bit 0 of the shift register
+        // is set, or the length counter is zero.
+        if self.shift_register & 1 == 1 || !self.length_counter.is_nonzero() {
+            0
+        } else {
+            self.envelope.output()
+        }
     }
 }
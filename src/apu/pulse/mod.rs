@@ -6,7 +6,7 @@ use apu::timer::Timer;
 use std::marker::PhantomData;
 
 pub type Pulse1 = PulseImpl<Pulse1Negater>;
-pub type Pulse2 = PulseImpl<Pulse1Negater>;
+pub type Pulse2 = PulseImpl<Pulse2Negater>;
 
 pub trait Pulse: Default {
     fn write_4000_4004(&mut self, val: u8);
@@ -19,8 +19,7 @@ pub trait Pulse: Default {
     fn clock_sweep(&mut self);
     fn zero_length_counter(&mut self);
     fn length_is_nonzero(&self) -> bool;
-    fn sample(&mut self, sample_buffer_offset: usize);
-    fn sample_buffer(&self) -> &[i16; super::SAMPLE_COUNT];
+    fn output(&self) -> u8;
 }
 
 /// Trait for implementing negation logic used during sweep adjustment, which differs between pulse
@@ -56,6 +55,7 @@ impl Negater for Pulse2Negater {
     }
 }
 
+#[derive(Default)]
 pub struct PulseImpl<N: Negater> {
     sweep: Sweep,
     duty_cycle: bool,
@@ -64,39 +64,22 @@ pub struct PulseImpl<N: Negater> {
     sequencer: Sequencer,
     envelope: Envelope,
     raw_timer_period: u16,
-    sample_buffer: Box<[i16; super::SAMPLE_COUNT]>,
     phantom: PhantomData<N>,
 }
 
-impl<N: Negater> Default for PulseImpl<N> {
-    fn default() -> Self {
-        PulseImpl {
-            sweep: Sweep::default(),
-            duty_cycle: false,
-            timer: Timer::default(),
-            length_counter: LengthCounter::default(),
-            sequencer: Sequencer::default(),
-            envelope: Envelope::default(),
-            raw_timer_period: 0,
-            sample_buffer: Box::new([0; super::SAMPLE_COUNT]),
-            phantom: PhantomData::default(),
-        }
-    }
-}
-
 impl<N: Negater> PulseImpl<N> {
     fn set_raw_timer_period_low(&mut self, val: u8) {
         self.raw_timer_period = (self.raw_timer_period & 0b_0111_0000_0000) | val as u16;
-        self.update_timer_period()
+        self.timer.set_period(self.raw_timer_period)
     }
 
     fn set_raw_timer_period_high(&mut self, val: u8) {
         self.raw_timer_period = (self.raw_timer_period & 0b_1111_1111) |
                                 ((val as u16 & 0b111) << 8);
-        self.update_timer_period()
+        self.timer.set_period(self.raw_timer_period)
     }
 
-    fn update_timer_period(&mut self) {
+    fn target_period(&self) -> u16 {
         // The sweep unit continuously calculates each channel's target period in this way:
         //
         //     1. A barrel shifter shifts the channel's 11-bit raw timer period right by the shift
@@ -110,16 +93,22 @@ impl<N: Negater> PulseImpl<N> {
         //
         // Whenever the current period changes for any reason, whether by $400x writes or by sweep,
         // the target period also changes.
-        let target_period = {
-            let change_amount = self.raw_timer_period >> self.sweep.shift_count();
-            if self.sweep.negate_flag() {
-                // TODO differences between pulse1 and pulse2
-                self.raw_timer_period - N::negate_amount(change_amount)
-            } else {
-                self.raw_timer_period + change_amount
-            }
-        };
-        self.timer.set_period(target_period)
+        let change_amount = self.raw_timer_period >> self.sweep.shift_count();
+        if self.sweep.negate_flag() {
+            self.raw_timer_period
+                .saturating_sub(N::negate_amount(change_amount))
+        } else {
+            self.raw_timer_period + change_amount
+        }
+    }
+
+    fn is_muted(&self) -> bool {
+        // Two conditions cause the sweep unit to mute the channel, even if it's disabled:
+        //
+        //   1. If the current period is less than 8, the sweep unit mutes the channel.
+        //   2. If at any time the target period is greater than $7FF, the sweep unit mutes the
+        //      channel.
+        self.raw_timer_period < 8 || self.target_period() > 0x7ff
     }
 }
 
@@ -173,37 +162,26 @@ impl<N: Negater> Pulse for PulseImpl<N> {
     }
 
     fn clock_sweep(&mut self) {
-        if self.sweep.clock() {
-            self.update_timer_period()
+        // If the shift count is zero, the pulse's period is never updated, but muting logic still
+        // applies.
+        if self.sweep.clock() && self.sweep.shift_count() > 0 && !self.is_muted() {
+            self.raw_timer_period = self.target_period();
+            self.timer.set_period(self.raw_timer_period);
         }
     }
 
-    fn sample(&mut self, sample_buffer_offset: usize) {
+    fn output(&self) -> u8 {
         // The mixer receives the current envelope volume except when
         //   - The sequencer output is zero, or
-        //   - TODO: overflow from the sweep unit's adder is silencing the channel, or
+        //   - overflow from the sweep unit's adder is silencing the channel, or
         //   - the length counter is zero, or
         //   - the timer has a value less than eight.
-        let volume = if !self.length_is_nonzero() || self.timer.counter() < 8 {
+        if !self.duty_cycle || self.is_muted() || !self.length_is_nonzero() {
             0
         } else {
             self.envelope.output()
-        };
-
-        let audible = volume > 0;
-        let buffer_opt = super::get_or_zero_sample_buffer(&mut (self.sample_buffer),
-                                                          sample_buffer_offset,
-                                                          audible);
-        if let Some(buffer) = buffer_opt {
-            for dest in buffer.iter_mut() {
-                *dest = volume * self.duty_cycle as u8;
-            }
         }
     }
-
-    fn sample_buffer(&self) -> &[i16; super::SAMPLE_COUNT] {
-        &self.sample_buffer
-    }
 }
 
 mod sequencer {
@@ -1,12 +1,148 @@
 use self::mocks::apu_mock;
-use apu::ApuContract;
+use apu::{ApuContract, CLOCK_RATE, Channel, MAX_BUFFERED_SAMPLES};
+use apu::mixer;
+use audio::{Audio, CaptureSink};
 
 #[test]
 fn memory_read_mapping() {
     let mut apu = apu_mock();
-    apu.status = 0xb0;
+    apu.dmc.interrupt_flag = true;
+    apu.dmc.bytes_remaining = 1;
     let result = apu.read_status();
-    assert_eq!(0xb0, result)
+    assert_eq!(0x90, result)
+}
+
+#[test]
+fn frame_interrupt_cleared_by_read() {
+    let apu = apu_mock();
+    apu.frame_interrupt.set(true);
+    assert_eq!(0x40, apu.read_status());
+    assert_eq!(0x00, apu.read_status());
+    assert!(!apu.irq_pending());
+}
+
+#[test]
+fn frame_interrupt_set_during_read() {
+    let mut apu = apu_mock();
+    apu.frame_counter.interrupt_imminent = true;
+    assert_eq!(0x40, apu.read_status());
+}
+
+#[test]
+fn frame_interrupt_cleared_by_inhibit() {
+    let mut apu = apu_mock();
+    apu.frame_interrupt.set(true);
+    apu.write(0x4017, 0x40);
+    assert_eq!(0x40, apu.frame_counter.reg_4017);
+    assert!(!apu.irq_pending());
+}
+
+#[test]
+fn dmc_enable_and_interrupt() {
+    let mut apu = apu_mock();
+    apu.dmc.interrupt_flag = true;
+    assert!(apu.irq_pending());
+
+    // Writing $4015 sets the DMC enable state and acknowledges its interrupt
+    apu.write(0x4015, 0x10);
+    assert!(apu.dmc.enabled);
+    assert!(!apu.irq_pending());
+
+    apu.write(0x4015, 0x00);
+    assert!(!apu.dmc.enabled);
+}
+
+#[test]
+fn samples_per_frame() {
+    let mut apu = apu_mock();
+    for _ in 0..29780 {
+        apu.half_step();
+    }
+    let samples = apu.take_samples();
+    assert_eq!(733, samples.len());
+    assert!(samples.iter().all(|&sample| sample == 0.0));
+    assert!(apu.take_samples().is_empty());
+
+    apu.set_sample_rate(48000);
+    for _ in 0..29780 {
+        apu.half_step();
+    }
+    assert_eq!(798, apu.take_samples().len());
+}
+
+#[test]
+fn untaken_samples_are_capped() {
+    let mut apu = apu_mock();
+    for _ in 0..CLOCK_RATE * 2 {
+        apu.half_step();
+    }
+
+    // The oldest samples are dropped, leaving at most about a second of them
+    let samples = apu.take_samples().len();
+    assert!(samples > MAX_BUFFERED_SAMPLES / 2);
+    assert!(samples <= MAX_BUFFERED_SAMPLES);
+}
+
+#[test]
+fn sink_receives_samples() {
+    let mut apu = apu_mock();
+    let capture = CaptureSink::default();
+    apu.set_sink(Some(Box::new(capture.clone())));
+    for _ in 0..29780 {
+        apu.half_step();
+    }
+
+    // Samples are written in batches, and the remainder is held back for the sink
+    let written = capture.samples().len();
+    assert!(written > 0);
+    assert!(apu.take_samples().is_empty());
+    apu.flush_sinks();
+    assert_eq!(733, capture.samples().len());
+}
+
+#[test]
+fn channel_sinks_receive_single_channel() {
+    let mut apu = apu_mock();
+    apu.pulse_1.output = 15;
+    let pulse_1 = CaptureSink::default();
+    let pulse_2 = CaptureSink::default();
+    apu.set_channel_sink(Channel::Pulse1, Some(Box::new(pulse_1.clone())));
+    apu.set_channel_sink(Channel::Pulse2, Some(Box::new(pulse_2.clone())));
+    for _ in 0..29780 {
+        apu.half_step();
+    }
+
+    // Samples still waiting for a full batch are written by the flush
+    apu.flush_sinks();
+    let pulse_1 = pulse_1.samples();
+    let pulse_2 = pulse_2.samples();
+    assert_eq!(733, pulse_1.len());
+    assert_eq!(733, pulse_2.len());
+    let expected = mixer::mix(15, 0, 0, 0, 0);
+    assert!((pulse_1[pulse_1.len() - 1] - expected).abs() < 0.000001);
+    assert!(pulse_2.iter().all(|&sample| sample == 0.0));
+
+    // Removing a channel's sink stops its output
+    let capture = CaptureSink::default();
+    apu.set_channel_sink(Channel::Pulse2, None);
+    apu.set_channel_sink(Channel::Pulse1, Some(Box::new(capture.clone())));
+    apu.set_channel_sink(Channel::Pulse1, None);
+    for _ in 0..29780 {
+        apu.half_step();
+    }
+    apu.flush_sinks();
+    assert!(capture.samples().is_empty());
+}
+
+#[test]
+fn expansion_audio_is_mixed() {
+    let mut apu = apu_mock();
+    apu.set_expansion_audio(0.5);
+    for _ in 0..2000 {
+        apu.half_step();
+    }
+    let samples = apu.take_samples();
+    assert!((samples[samples.len() - 1] - 0.5).abs() < 0.000001);
 }
 
 #[test]
@@ -105,6 +241,7 @@ mod mocks {
     #[derive(Default)]
     pub struct FrameCounterMock {
         pub reg_4017: u8,
+        pub interrupt_imminent: bool,
     }
 
     impl FrameCounter for FrameCounterMock {
@@ -112,9 +249,12 @@ mod mocks {
             Clock::None
         }
 
-        fn write_4017(&mut self, val: u8) -> Clock {
+        fn write_4017(&mut self, val: u8) {
             self.reg_4017 = val;
-            Clock::None
+        }
+
+        fn interrupt_imminent(&self) -> bool {
+            self.interrupt_imminent
         }
     }
 
@@ -150,8 +290,8 @@ mod mocks {
             false
         }
 
-        fn output(&self) -> f32 {
-            0.0
+        fn output(&self) -> u8 {
+            0
         }
     }
 
@@ -161,6 +301,7 @@ mod mocks {
         pub reg_4001_4005: u8,
         pub reg_4002_4006: u8,
         pub reg_4003_4007: u8,
+        pub output: u8,
     }
 
     impl Pulse for PulseMock {
@@ -195,7 +336,7 @@ mod mocks {
         fn clock_sweep(&mut self) {}
 
         fn output(&self) -> u8 {
-            0
+            self.output
         }
     }
 
@@ -231,8 +372,8 @@ mod mocks {
             false
         }
 
-        fn output(&self) -> f32 {
-            0.0
+        fn output(&self) -> u8 {
+            0
         }
     }
 
@@ -242,6 +383,9 @@ mod mocks {
         pub reg_4011: u8,
         pub reg_4012: u8,
         pub reg_4013: u8,
+        pub enabled: bool,
+        pub bytes_remaining: u16,
+        pub interrupt_flag: bool,
     }
 
     impl Dmc for DmcMock {
@@ -261,8 +405,32 @@ mod mocks {
             self.reg_4013 = val
         }
 
-        fn output(&self) -> f32 {
-            0.0
+        fn clock_timer(&mut self) {}
+
+        fn set_enabled(&mut self, enabled: bool) {
+            self.enabled = enabled
+        }
+
+        fn bytes_remaining_is_nonzero(&self) -> bool {
+            self.bytes_remaining > 0
+        }
+
+        fn interrupt_flag(&self) -> bool {
+            self.interrupt_flag
+        }
+
+        fn clear_interrupt_flag(&mut self) {
+            self.interrupt_flag = false
+        }
+
+        fn fetch_request(&mut self) -> Option<u16> {
+            None
+        }
+
+        fn fill_sample_buffer(&mut self, _: u8) {}
+
+        fn output(&self) -> u8 {
+            0
         }
     }
 }
@@ -12,7 +12,7 @@ pub trait Triangle: Default {
     fn clock_length_counter(&mut self);
     fn zero_length_counter(&mut self);
     fn length_is_nonzero(&self) -> bool;
-    fn output(&self) -> f32;
+    fn output(&self) -> u8;
 }
 
 #[derive(Default)]
@@ -81,8 +81,8 @@ impl Triangle for TriangleImpl {
         self.length_counter.is_nonzero()
     }
 
-    fn output(&self) -> f32 {
-        0.0
+    fn output(&self) -> u8 {
+        self.sequencer.output()
     }
 }
 
@@ -107,6 +107,10 @@ mod sequencer {
             }
             self.current_output = SEQUENCER_VALUE_TABLE[31 - step as usize];
         }
+
+        pub fn output(&self) -> u8 {
+            self.current_output
+        }
     }
 }
 
@@ -122,7 +126,7 @@ mod linear_counter {
     impl LinearCounter {
         pub fn set_flags(&mut self, val: u8) {
             self.control_flag = val & 0b_1000_0000 > 0;
-            self.reload_value = val & 0b_0111_0000;
+            self.reload_value = val & 0b_0111_1111;
         }
 
         pub fn set_reload_flag(&mut self) {
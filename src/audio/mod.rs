@@ -1,5 +1,91 @@
-pub trait Audio {}
+pub mod ring_buffer;
+pub mod wav;
+
+use std::cell::RefCell;
+use std::i16;
+use std::rc::Rc;
+
+// How much of the previous output the DC blocker keeps, which puts its cutoff at about 35Hz
+const DC_BLOCKER_POLE: f32 = 0.995;
+
+pub trait Audio {
+    /// Removes and returns the samples generated since the last call, such as over the course of
+    /// a frame. They are at the host sample rate, and range from 0.0 to 1.0. When an `AudioSink`
+    /// is attached, every sample goes to it instead and nothing is returned.
+    ///
+    /// Without a sink, this needs to be called regularly. Only about a second of samples is kept,
+    /// and older ones are dropped.
+    fn take_samples(&self) -> Vec<f32> {
+        Vec::new()
+    }
+}
 
 pub struct NoAudio;
 
 impl Audio for NoAudio {}
+
+/// A destination for the APU's output, such as an audio device
+pub trait AudioSink {
+    /// Receives the next batch of samples. They are at the APU's sample rate, and range from 0.0
+    /// to 1.0.
+    fn write_samples(&mut self, samples: &[f32]);
+}
+
+/// Discards everything written to it
+pub struct NullSink;
+
+impl AudioSink for NullSink {
+    fn write_samples(&mut self, _: &[f32]) {}
+}
+
+/// Keeps everything written to it in memory. Clones share the same samples, so one can be given
+/// to the APU while another is used to inspect the output.
+#[derive(Clone, Default)]
+pub struct CaptureSink {
+    samples: Rc<RefCell<Vec<f32>>>,
+}
+
+impl CaptureSink {
+    pub fn samples(&self) -> Vec<f32> {
+        self.samples.borrow().clone()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.borrow_mut().clear()
+    }
+}
+
+impl AudioSink for CaptureSink {
+    fn write_samples(&mut self, samples: &[f32]) {
+        self.samples.borrow_mut().extend_from_slice(samples)
+    }
+}
+
+/// Centres samples on zero so they can be converted to signed PCM. The APU's output is never
+/// negative, so silence would otherwise be a DC offset. Like the NES's own output stage, this is
+/// a first-order high-pass filter, and it settles within a fraction of a second.
+#[derive(Default)]
+pub struct DcBlocker {
+    last_input: f32,
+    last_output: f32,
+}
+
+impl DcBlocker {
+    pub fn filter(&mut self, sample: f32) -> f32 {
+        self.last_output = sample - self.last_input + DC_BLOCKER_POLE * self.last_output;
+        self.last_input = sample;
+        self.last_output
+    }
+}
+
+/// Converts a sample from -1.0 to 1.0 to signed 16-bit PCM
+pub fn to_i16(sample: f32) -> i16 {
+    let sample = if sample > 1.0 {
+        1.0
+    } else if sample < -1.0 {
+        -1.0
+    } else {
+        sample
+    };
+    (sample * i16::MAX as f32) as i16
+}
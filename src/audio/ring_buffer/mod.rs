@@ -0,0 +1,77 @@
+//! A lock-free ring buffer for handing samples from the emulator to an audio callback running on
+//! another thread. There must be exactly one producer and one consumer.
+
+#[cfg(test)]
+mod spec_tests;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Shared {
+    // Samples are stored as their 16-bit pattern, since there are no atomic 16-bit integers
+    slots: Vec<AtomicUsize>,
+    // Both indexes only ever increase, and wrap around the slots when used
+    read_index: AtomicUsize,
+    write_index: AtomicUsize,
+}
+
+pub struct Producer {
+    shared: Arc<Shared>,
+}
+
+pub struct Consumer {
+    shared: Arc<Shared>,
+}
+
+/// Creates a ring buffer that holds up to `capacity` samples
+pub fn ring_buffer(capacity: usize) -> (Producer, Consumer) {
+    let shared = Arc::new(Shared {
+                              slots: (0..capacity).map(|_| AtomicUsize::new(0)).collect(),
+                              read_index: AtomicUsize::new(0),
+                              write_index: AtomicUsize::new(0),
+                          });
+    (Producer { shared: shared.clone() }, Consumer { shared: shared })
+}
+
+impl Producer {
+    /// Adds a sample, returning false if the buffer is full and the sample was dropped
+    pub fn push(&self, sample: i16) -> bool {
+        let shared = &self.shared;
+        let write_index = shared.write_index.load(Ordering::Relaxed);
+        let read_index = shared.read_index.load(Ordering::Acquire);
+        if write_index.wrapping_sub(read_index) == shared.slots.len() {
+            return false;
+        }
+
+        shared.slots[write_index % shared.slots.len()].store(sample as u16 as usize,
+                                                             Ordering::Relaxed);
+        shared
+            .write_index
+            .store(write_index.wrapping_add(1), Ordering::Release);
+        true
+    }
+}
+
+impl Consumer {
+    /// Removes the oldest sample, if there is one
+    pub fn pop(&self) -> Option<i16> {
+        let shared = &self.shared;
+        let read_index = shared.read_index.load(Ordering::Relaxed);
+        let write_index = shared.write_index.load(Ordering::Acquire);
+        if read_index == write_index {
+            return None;
+        }
+
+        let sample = shared.slots[read_index % shared.slots.len()].load(Ordering::Relaxed);
+        shared
+            .read_index
+            .store(read_index.wrapping_add(1), Ordering::Release);
+        Some(sample as u16 as i16)
+    }
+
+    /// The number of samples waiting to be consumed
+    pub fn len(&self) -> usize {
+        let write_index = self.shared.write_index.load(Ordering::Acquire);
+        write_index.wrapping_sub(self.shared.read_index.load(Ordering::Relaxed))
+    }
+}
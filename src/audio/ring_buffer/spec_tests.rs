@@ -0,0 +1,49 @@
+use super::ring_buffer;
+use std::thread;
+
+#[test]
+fn samples_come_out_in_order() {
+    let (producer, consumer) = ring_buffer(4);
+    assert_eq!(None, consumer.pop());
+    assert!(producer.push(1));
+    assert!(producer.push(-1));
+    assert_eq!(2, consumer.len());
+    assert_eq!(Some(1), consumer.pop());
+    assert_eq!(Some(-1), consumer.pop());
+    assert_eq!(None, consumer.pop());
+}
+
+#[test]
+fn full_buffer_drops_samples() {
+    let (producer, consumer) = ring_buffer(2);
+    assert!(producer.push(1));
+    assert!(producer.push(2));
+    assert!(!producer.push(3));
+    assert_eq!(Some(1), consumer.pop());
+
+    // Space is freed up, and the slots wrap around
+    assert!(producer.push(4));
+    assert_eq!(Some(2), consumer.pop());
+    assert_eq!(Some(4), consumer.pop());
+}
+
+#[test]
+fn consumer_on_another_thread() {
+    let (producer, consumer) = ring_buffer(16);
+    let reader = thread::spawn(move || {
+        let mut samples = Vec::new();
+        while samples.len() < 1000 {
+            if let Some(sample) = consumer.pop() {
+                samples.push(sample);
+            }
+        }
+        samples
+    });
+
+    for sample in 0..1000 {
+        while !producer.push(sample) {
+            thread::yield_now();
+        }
+    }
+    assert_eq!((0..1000).collect::<Vec<i16>>(), reader.join().unwrap());
+}
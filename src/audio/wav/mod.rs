@@ -0,0 +1,118 @@
+//! Records audio to 16-bit mono PCM WAV files
+
+#[cfg(test)]
+mod spec_tests;
+
+use audio::{AudioSink, DcBlocker, to_i16};
+use byte_utils::lo_hi;
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+
+// The RIFF header, format chunk and data chunk header
+const HEADER_SIZE: usize = 44;
+
+/// Records everything written to it while recording is on. Clones share the same recording, so
+/// one can be given to the APU while another is kept to toggle recording and save the result.
+#[derive(Clone)]
+pub struct WavRecorder {
+    sample_rate: u32,
+    recording: Rc<Cell<bool>>,
+    samples: Rc<RefCell<Vec<i16>>>,
+    dc_blocker: Rc<RefCell<DcBlocker>>,
+}
+
+impl WavRecorder {
+    /// Creates a recorder for samples at the given rate, which should be the APU's sample rate.
+    /// It starts out recording.
+    pub fn new(sample_rate: u32) -> Self {
+        WavRecorder {
+            sample_rate: sample_rate,
+            recording: Rc::new(Cell::new(true)),
+            samples: Rc::new(RefCell::new(Vec::new())),
+            dc_blocker: Rc::new(RefCell::new(DcBlocker::default())),
+        }
+    }
+
+    /// Pauses or resumes recording. Samples written while paused are discarded.
+    pub fn set_recording(&self, recording: bool) {
+        self.recording.set(recording)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.get()
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.samples.borrow().len()
+    }
+
+    pub fn clear(&self) {
+        self.samples.borrow_mut().clear()
+    }
+
+    /// The contents of a WAV file holding everything recorded so far
+    pub fn to_wav(&self) -> Vec<u8> {
+        let samples = self.samples.borrow();
+        let data_size = samples.len() * 2;
+        let mut wav = Vec::with_capacity(HEADER_SIZE + data_size);
+
+        wav.extend_from_slice(b"RIFF");
+        push_u32(&mut wav, (HEADER_SIZE - 8 + data_size) as u32);
+        wav.extend_from_slice(b"WAVE");
+
+        // Format chunk: PCM, 1 channel, 2 bytes per sample
+        wav.extend_from_slice(b"fmt ");
+        push_u32(&mut wav, 16);
+        push_u16(&mut wav, 1);
+        push_u16(&mut wav, 1);
+        push_u32(&mut wav, self.sample_rate);
+        push_u32(&mut wav, self.sample_rate * 2);
+        push_u16(&mut wav, 2);
+        push_u16(&mut wav, 16);
+
+        wav.extend_from_slice(b"data");
+        push_u32(&mut wav, data_size as u32);
+        for &sample in samples.iter() {
+            push_u16(&mut wav, sample as u16);
+        }
+        wav
+    }
+
+    /// Writes everything recorded so far to a WAV file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), &'static str> {
+        let mut f = match File::create(path) {
+            Ok(file) => file,
+            Err(_) => return Err("Unable to create WAV file."),
+        };
+
+        match f.write_all(&self.to_wav()) {
+            Ok(_) => Ok(()),
+            Err(_) => Err("An error occurred writing the WAV file."),
+        }
+    }
+}
+
+impl AudioSink for WavRecorder {
+    fn write_samples(&mut self, samples: &[f32]) {
+        if self.recording.get() {
+            let mut recorded = self.samples.borrow_mut();
+            let mut dc_blocker = self.dc_blocker.borrow_mut();
+            recorded.extend(samples.iter().map(|&sample| to_i16(dc_blocker.filter(sample))));
+        }
+    }
+}
+
+// WAV files are little-endian
+fn push_u16(wav: &mut Vec<u8>, val: u16) {
+    let (low_byte, high_byte) = lo_hi(val);
+    wav.push(low_byte);
+    wav.push(high_byte);
+}
+
+fn push_u32(wav: &mut Vec<u8>, val: u32) {
+    push_u16(wav, val as u16);
+    push_u16(wav, (val >> 16) as u16);
+}
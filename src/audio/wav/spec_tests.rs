@@ -0,0 +1,103 @@
+use super::WavRecorder;
+use apu::{Apu, ApuContract, Channel};
+use audio::AudioSink;
+use std::{env, fs, i16, process};
+use std::fs::File;
+use std::io::Read;
+
+#[test]
+fn header() {
+    let mut recorder = WavRecorder::new(44100);
+    recorder.write_samples(&[0.0, 1.0, 0.5]);
+    let wav = recorder.to_wav();
+    assert_eq!(50, wav.len());
+    assert_eq!(b"RIFF", &wav[0..4]);
+    assert_eq!([42, 0, 0, 0], wav[4..8]);
+    assert_eq!(b"WAVEfmt ", &wav[8..16]);
+
+    // PCM, mono, 44100Hz, 88200 bytes per second, 2 byte blocks, 16 bits per sample
+    assert_eq!([16, 0, 0, 0, 1, 0, 1, 0, 0x44, 0xac, 0, 0, 0x88, 0x58, 0x01, 0, 2, 0, 16, 0],
+               wav[16..36]);
+    assert_eq!(b"data", &wav[36..40]);
+    assert_eq!([6, 0, 0, 0], wav[40..44]);
+}
+
+#[test]
+fn samples_are_16_bit_little_endian() {
+    let mut recorder = WavRecorder::new(44100);
+    recorder.write_samples(&[0.0, 1.0]);
+    let wav = recorder.to_wav();
+    let (low_byte, high_byte) = (i16::MAX as u8, (i16::MAX >> 8) as u8);
+    assert_eq!([0, 0, low_byte, high_byte], wav[44..]);
+}
+
+#[test]
+fn dc_offset_is_removed() {
+    // The APU's output is never negative, so a steady level is filtered back towards silence
+    let mut recorder = WavRecorder::new(44100);
+    recorder.write_samples(&[0.5; 4410]);
+    let wav = recorder.to_wav();
+    let sample = |i: usize| (wav[44 + i * 2] as u16 | (wav[45 + i * 2] as u16) << 8) as i16;
+    assert_eq!(i16::MAX / 2, sample(0));
+    assert!(sample(4409).abs() < 100);
+}
+
+#[test]
+fn recording_can_be_paused() {
+    let recorder = WavRecorder::new(44100);
+    let mut sink = recorder.clone();
+    sink.write_samples(&[0.5; 10]);
+
+    recorder.set_recording(false);
+    assert!(!sink.is_recording());
+    sink.write_samples(&[0.5; 10]);
+    assert_eq!(10, recorder.sample_count());
+
+    recorder.set_recording(true);
+    sink.write_samples(&[0.5; 10]);
+    assert_eq!(20, recorder.sample_count());
+
+    recorder.clear();
+    assert_eq!(0, sink.sample_count());
+}
+
+#[test]
+fn save() {
+    let path = env::temp_dir().join(format!("rs_nes_{}.wav", process::id()));
+    let mut recorder = WavRecorder::new(48000);
+    recorder.write_samples(&[0.25; 100]);
+    recorder.save(&path).unwrap();
+
+    let mut saved = Vec::new();
+    File::open(&path).unwrap().read_to_end(&mut saved).unwrap();
+    assert_eq!(recorder.to_wav(), saved);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn apu_recordings_are_reproducible() {
+    // Recordings of the same run must match exactly, so they can be compared with golden files
+    let record = || {
+        let mixed = WavRecorder::new(44100);
+        let pulse = WavRecorder::new(44100);
+        let mut apu = Apu::new(Box::new(mixed.clone()));
+        apu.set_channel_sink(Channel::Pulse1, Some(Box::new(pulse.clone())));
+
+        // A 440Hz square wave at full volume on pulse 1
+        apu.write(0x4015, 0x01);
+        apu.write(0x4000, 0xbf);
+        apu.write(0x4002, 0xfd);
+        apu.write(0x4003, 0x00);
+        for _ in 0..29780 {
+            apu.half_step();
+        }
+        apu.flush_sinks();
+        (mixed.to_wav(), pulse.to_wav())
+    };
+
+    let (mixed, pulse) = record();
+    assert_eq!(44 + 733 * 2, mixed.len());
+    assert_eq!(mixed.len(), pulse.len());
+    assert!(mixed[44..].iter().any(|&byte| byte != 0));
+    assert_eq!((mixed, pulse), record());
+}
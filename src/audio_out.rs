@@ -1,118 +1,79 @@
-//! SDL audio interface. Used by the APU to actually play audio.
-
-//
-// Author: Patrick Walton
-//
-
-// TODO: This module is very unsafe. Adding a reader-writer audio lock to SDL would help make it
-// safe.
+//! SDL audio backend. Samples written to the sink are passed through a ring buffer to the SDL
+//! audio callback, which runs on its own thread.
 
+use audio::{AudioSink, DcBlocker, to_i16};
+use audio::ring_buffer::{Consumer, Producer, ring_buffer};
 use sdl2::Sdl;
-use sdl2::audio::{AudioCallback, AudioDevice, AudioDeviceLockGuard, AudioSpecDesired};
-use std::cmp;
-use std::mem;
-use std::slice::from_raw_parts_mut;
-use std::sync::{Condvar, Mutex};
-
-//
-// The audio callback
-//
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
 
-const SAMPLE_COUNT: usize = 4410 * 2;
+const SAMPLE_RATE: i32 = 44100;
 
-static mut G_AUDIO_DEVICE: Option<*mut AudioDevice<NesAudioCallback>> = None;
-
-static mut G_OUTPUT_BUFFER: Option<*mut OutputBuffer> = None;
-
-lazy_static! {
-    pub static ref AUDIO_MUTEX: Mutex<()> = Mutex::new(());
-    pub static ref AUDIO_CONDVAR: Condvar = Condvar::new();
-}
+// A tenth of a second, which is enough to cover the odd late frame without adding much latency
+const BUFFER_SIZE: usize = 4410;
 
-pub struct OutputBuffer {
-    pub samples: [u8; SAMPLE_COUNT],
-    pub play_offset: usize,
+struct SdlCallback {
+    consumer: Consumer,
+    last_sample: i16,
 }
 
-pub struct NesAudioCallback;
-
-impl AudioCallback for NesAudioCallback {
+impl AudioCallback for SdlCallback {
     type Channel = i16;
 
-    fn callback(&mut self, buf: &mut [Self::Channel]) {
-        unsafe {
-            let samples: &mut [u8] = from_raw_parts_mut(&mut buf[0] as *mut i16 as *mut u8,
-                                                        buf.len() * 2);
-            let output_buffer: &mut OutputBuffer = mem::transmute(G_OUTPUT_BUFFER.unwrap());
-            let play_offset = output_buffer.play_offset;
-            let output_buffer_len = output_buffer.samples.len();
-
-            for i in 0..samples.len() {
-                if i + play_offset >= output_buffer_len {
-                    break;
-                }
-                samples[i] = output_buffer.samples[i + play_offset];
+    fn callback(&mut self, buf: &mut [i16]) {
+        // If the emulator falls behind, the last sample is held to avoid popping
+        for dest in buf.iter_mut() {
+            if let Some(sample) = self.consumer.pop() {
+                self.last_sample = sample;
             }
-
-            let _ = AUDIO_MUTEX.lock();
-            output_buffer.play_offset = cmp::min(play_offset + samples.len(), output_buffer_len);
-            AUDIO_CONDVAR.notify_one();
+            *dest = self.last_sample;
         }
     }
 }
 
-/// Audio initialization. If successful, returns a pointer to an allocated `OutputBuffer` that can
-/// be filled with raw audio data.
-pub fn open(context: &Sdl) -> Option<*mut OutputBuffer> {
-    let output_buffer = Box::new(OutputBuffer {
-                                     samples: [0; SAMPLE_COUNT],
-                                     play_offset: 0,
-                                 });
-    let output_buffer_ptr: *mut OutputBuffer = unsafe { mem::transmute(&*output_buffer) };
+pub struct SdlSink {
+    device: AudioDevice<SdlCallback>,
+    producer: Producer,
+    dc_blocker: DcBlocker,
+}
 
-    unsafe {
-        G_OUTPUT_BUFFER = Some(output_buffer_ptr);
-        mem::forget(output_buffer);
+impl SdlSink {
+    /// The sample rate the device was opened with, which the APU should generate samples at
+    pub fn sample_rate(&self) -> u32 {
+        self.device.spec().freq as u32
     }
+}
 
-    let spec = AudioSpecDesired {
-        freq: Some(44100),
-        channels: Some(1),
-        samples: Some(4410),
-    };
-
-    unsafe {
-        let audio_subsystem = context.audio().unwrap();
-        match audio_subsystem.open_playback(None, &spec, |_| NesAudioCallback) {
-            Ok(device) => {
-                device.resume();
-                G_AUDIO_DEVICE = Some(mem::transmute(Box::new(device)));
-                return Some(output_buffer_ptr);
-            }
-            Err(e) => {
-                println!("Error initializing AudioDevice: {}", e);
-                return None;
-            }
+impl AudioSink for SdlSink {
+    fn write_samples(&mut self, samples: &[f32]) {
+        // Samples that don't fit are dropped, rather than holding up emulation
+        for &sample in samples {
+            self.producer.push(to_i16(self.dc_blocker.filter(sample)));
         }
     }
 }
 
-//
-// Audio tear-down
-//
+/// Opens the default audio device, and starts playing whatever is written to the returned sink
+pub fn open(context: &Sdl) -> Result<SdlSink, String> {
+    let spec = AudioSpecDesired {
+        freq: Some(SAMPLE_RATE),
+        channels: Some(1),
+        samples: Some(1024),
+    };
 
-pub fn close() {
-    unsafe {
-        match G_AUDIO_DEVICE {
-            None => {}
-            Some(ptr) => {
-                let _: Box<AudioDevice<NesAudioCallback>> = mem::transmute(ptr);
-                G_AUDIO_DEVICE = None;
+    let audio_subsystem = context.audio()?;
+    let (producer, consumer) = ring_buffer(BUFFER_SIZE);
+    let device = audio_subsystem
+        .open_playback(None, &spec, |_| {
+            SdlCallback {
+                consumer: consumer,
+                last_sample: 0,
             }
-        }
-    }
-}
-
-pub fn lock<'a>() -> Option<AudioDeviceLockGuard<'a, NesAudioCallback>> {
-    unsafe { G_AUDIO_DEVICE.map(|dev| (*dev).lock()) }
+        })?;
+    device.resume();
+
+    Ok(SdlSink {
+           device: device,
+           producer: producer,
+           dc_blocker: DcBlocker::default(),
+       })
 }
@@ -0,0 +1,162 @@
+//! Cheat codes. Two kinds of device are supported:
+//!
+//! - Game Genie: 6 and 8 letter codes that substitute a value whenever the CPU reads an address in
+//!   $8000-$FFFF. 8 letter codes only substitute when the original value matches a compare value,
+//!   which keeps them from affecting other banks mapped to the same address.
+//! - Pro Action Replay: RAM-freeze codes written as 6 hex digits (`AAAAVV` or `AAAA:VV`) that
+//!   store a value to RAM once per frame. Like the real device, this happens at NMI.
+
+#[cfg(test)]
+mod spec_tests;
+
+const GAME_GENIE_LETTERS: &'static str = "APZLGITYEOXUKSVN";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Cheat {
+    /// Returns `value` for CPU reads of `address`, if the original value matches `compare`
+    Substitute {
+        address: u16,
+        value: u8,
+        compare: Option<u8>,
+    },
+    /// Writes `value` to `address` each frame
+    Freeze { address: u16, value: u8 },
+}
+
+impl Cheat {
+    /// Decodes a Game Genie or Pro Action Replay code
+    pub fn decode(code: &str) -> Result<Cheat, &'static str> {
+        let code = code.trim().to_uppercase();
+        if code.chars().all(|c| GAME_GENIE_LETTERS.contains(c)) {
+            Cheat::decode_game_genie(&code)
+        } else {
+            Cheat::decode_pro_action_replay(&code)
+        }
+    }
+
+    pub fn decode_game_genie(code: &str) -> Result<Cheat, &'static str> {
+        let n = code.to_uppercase()
+            .chars()
+            .map(|c| GAME_GENIE_LETTERS.find(c).map(|n| n as u16))
+            .collect::<Option<Vec<u16>>>()
+            .ok_or("Game Genie codes can only contain the letters APZLGITYEOXUKSVN.")?;
+
+        if n.len() != 6 && n.len() != 8 {
+            return Err("Game Genie codes must be 6 or 8 letters long.");
+        }
+
+        // Each letter is 4 bits, which are scrambled across the address, value and compare value
+        let address = 0x8000 | ((n[3] & 7) << 12) | ((n[5] & 7) << 8) | ((n[4] & 8) << 8) |
+                      ((n[2] & 7) << 4) | ((n[1] & 8) << 4) | (n[4] & 7) |
+                      (n[3] & 8);
+        let value = ((n[1] & 7) << 4) | ((n[0] & 8) << 4) | (n[0] & 7);
+
+        if n.len() == 6 {
+            Ok(Cheat::Substitute {
+                   address: address,
+                   value: (value | (n[5] & 8)) as u8,
+                   compare: None,
+               })
+        } else {
+            let compare = ((n[7] & 7) << 4) | ((n[6] & 8) << 4) | (n[6] & 7) | (n[5] & 8);
+            Ok(Cheat::Substitute {
+                   address: address,
+                   value: (value | (n[7] & 8)) as u8,
+                   compare: Some(compare as u8),
+               })
+        }
+    }
+
+    pub fn decode_pro_action_replay(code: &str) -> Result<Cheat, &'static str> {
+        let digits = code.replace(':', "");
+        if digits.len() != 6 {
+            return Err("Pro Action Replay codes must be 6 hex digits long.");
+        }
+
+        let code = u32::from_str_radix(&digits, 16)
+            .map_err(|_| "Pro Action Replay codes can only contain hex digits.")?;
+        let address = (code >> 8) as u16;
+        if address >= 0x2000 && (address < 0x6000 || address >= 0x8000) {
+            return Err("Pro Action Replay codes must freeze RAM ($0000-$1FFF or $6000-$7FFF).");
+        }
+
+        Ok(Cheat::Freeze {
+               address: address,
+               value: code as u8,
+           })
+    }
+}
+
+#[derive(Clone)]
+pub struct CheatEntry {
+    pub code: String,
+    pub cheat: Cheat,
+    pub enabled: bool,
+}
+
+/// The active set of cheats, which can each be toggled on and off
+#[derive(Clone, Default)]
+pub struct Cheats {
+    entries: Vec<CheatEntry>,
+}
+
+impl Cheats {
+    /// Decodes and enables a cheat, returning its index
+    pub fn add(&mut self, code: &str) -> Result<usize, &'static str> {
+        let cheat = Cheat::decode(code)?;
+        self.entries
+            .push(CheatEntry {
+                      code: code.trim().to_uppercase(),
+                      cheat: cheat,
+                      enabled: true,
+                  });
+        Ok(self.entries.len() - 1)
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.entries.len() {
+            self.entries.remove(index);
+        }
+    }
+
+    /// Enables or disables a cheat, returning whether it is now enabled
+    pub fn toggle(&mut self, index: usize) -> Option<bool> {
+        self.entries
+            .get_mut(index)
+            .map(|entry| {
+                     entry.enabled = !entry.enabled;
+                     entry.enabled
+                 })
+    }
+
+    pub fn entries(&self) -> &[CheatEntry] {
+        &self.entries
+    }
+
+    /// The value the CPU sees when reading `address`, given the value actually there
+    pub fn substitute(&self, address: u16, value: u8) -> u8 {
+        for entry in self.entries.iter().filter(|entry| entry.enabled) {
+            if let Cheat::Substitute {
+                       address: cheat_address,
+                       value: cheat_value,
+                       compare,
+                   } = entry.cheat {
+                if cheat_address == address && compare.map_or(true, |c| c == value) {
+                    return cheat_value;
+                }
+            }
+        }
+        value
+    }
+
+    /// The enabled RAM-freezes, as (address, value) pairs
+    pub fn freezes<'a>(&'a self) -> Box<Iterator<Item = (u16, u8)> + 'a> {
+        Box::new(self.entries
+                     .iter()
+                     .filter(|entry| entry.enabled)
+                     .filter_map(|entry| match entry.cheat {
+                                     Cheat::Freeze { address, value } => Some((address, value)),
+                                     _ => None,
+                                 }))
+    }
+}
@@ -0,0 +1,94 @@
+use super::*;
+
+#[test]
+fn six_letter_game_genie() {
+    // Super Mario Bros. infinite lives
+    assert_eq!(Ok(Cheat::Substitute {
+                      address: 0x91d9,
+                      value: 0xad,
+                      compare: None,
+                  }),
+               Cheat::decode("SXIOPO"));
+}
+
+#[test]
+fn eight_letter_game_genie() {
+    assert_eq!(Ok(Cheat::Substitute {
+                      address: 0x94a7,
+                      value: 0x02,
+                      compare: Some(0x03),
+                  }),
+               Cheat::decode("zexpygla"));
+}
+
+#[test]
+fn game_genie_wrong_length() {
+    assert_eq!(Err("Game Genie codes must be 6 or 8 letters long."),
+               Cheat::decode("SXIOP"));
+}
+
+#[test]
+fn pro_action_replay() {
+    let expected = Ok(Cheat::Freeze {
+                          address: 0x075a,
+                          value: 0x09,
+                      });
+    assert_eq!(expected, Cheat::decode("075A09"));
+    assert_eq!(expected, Cheat::decode("075a:09"));
+}
+
+#[test]
+fn pro_action_replay_must_target_ram() {
+    assert_eq!(true, Cheat::decode("6000FF").is_ok());
+    assert_eq!(true, Cheat::decode("8000FF").is_err());
+    assert_eq!(true, Cheat::decode("2000FF").is_err());
+}
+
+#[test]
+fn invalid_code() {
+    assert_eq!(true, Cheat::decode("12345G").is_err());
+}
+
+#[test]
+fn substitute() {
+    let mut cheats = Cheats::default();
+    cheats.add("SXIOPO").unwrap();
+    assert_eq!(0xad, cheats.substitute(0x91d9, 0x00));
+    assert_eq!(0x00, cheats.substitute(0x91da, 0x00));
+}
+
+#[test]
+fn substitute_with_compare() {
+    let mut cheats = Cheats::default();
+    cheats.add("ZEXPYGLA").unwrap();
+    assert_eq!(0x02, cheats.substitute(0x94a7, 0x03));
+    assert_eq!(0x04, cheats.substitute(0x94a7, 0x04));
+}
+
+#[test]
+fn toggle() {
+    let mut cheats = Cheats::default();
+    let index = cheats.add("SXIOPO").unwrap();
+    assert_eq!(Some(false), cheats.toggle(index));
+    assert_eq!(0x00, cheats.substitute(0x91d9, 0x00));
+    assert_eq!(Some(true), cheats.toggle(index));
+    assert_eq!(0xad, cheats.substitute(0x91d9, 0x00));
+    assert_eq!(None, cheats.toggle(1));
+}
+
+#[test]
+fn freezes() {
+    let mut cheats = Cheats::default();
+    cheats.add("SXIOPO").unwrap();
+    cheats.add("075A09").unwrap();
+    let index = cheats.add("0010:FF").unwrap();
+    assert_eq!(vec![(0x075a, 0x09), (0x0010, 0xff)],
+               cheats.freezes().collect::<Vec<_>>());
+
+    cheats.toggle(index);
+    assert_eq!(vec![(0x075a, 0x09)], cheats.freezes().collect::<Vec<_>>());
+
+    cheats.remove(1);
+    assert_eq!(0, cheats.freezes().count());
+    assert_eq!(2, cheats.entries().len());
+}
@@ -6,7 +6,7 @@ use serde::ser::SerializeStruct;
 
 pub enum MemorySnapshot {
     NoChange(u64), // If no change, just send the hash.
-    Updated(u64, Vec<u8>), // Updated, send hash and memory
+    Updated(u64, Vec<u8>, Vec<u8>), // Updated, send hash, CPU memory and PPU memory
 }
 
 pub struct CpuSnapshot<S: Screen + Serialize> {
@@ -36,12 +36,14 @@ impl Serialize for MemorySnapshot {
                 state.serialize_field("hash", &hash)?;
                 state.end()
             }
-            MemorySnapshot::Updated(hash, ref memory) => {
+            MemorySnapshot::Updated(hash, ref memory, ref vram) => {
                 let base64 = base64::encode(&memory);
-                let mut state = serializer.serialize_struct("Memory", 3)?;
+                let vram_base64 = base64::encode(&vram);
+                let mut state = serializer.serialize_struct("Memory", 4)?;
                 state.serialize_field("state", "Updated")?;
                 state.serialize_field("hash", &hash)?;
                 state.serialize_field("base64", &base64)?;
+                state.serialize_field("vramBase64", &vram_base64)?;
                 state.end()
             }
         }
@@ -13,6 +13,7 @@ pub enum BreakReason {
     Step,
     Nmi,
     Trap,
+    IllegalOpcode,
 }
 
 impl ToString for BreakReason {
@@ -22,6 +23,7 @@ impl ToString for BreakReason {
             BreakReason::Step => "step".to_string(),
             BreakReason::Nmi => "nmi".to_string(),
             BreakReason::Trap => "trap".to_string(),
+            BreakReason::IllegalOpcode => "illegal_opcode".to_string(),
         }
     }
 }
@@ -1,4 +1,5 @@
 use super::breakpoint_map::BreakpointMap;
+use cheat::{CheatEntry, Cheats};
 use cpu::registers::Registers;
 use iron::{headers, status};
 use iron::Handler;
@@ -102,6 +103,123 @@ impl Handler for ToggleBreakpointHandler {
     }
 }
 
+#[derive(Serialize)]
+pub struct CheatResponse {
+    index: usize,
+    code: String,
+    enabled: bool,
+}
+
+impl CheatResponse {
+    pub fn new(index: usize, entry: &CheatEntry) -> Self {
+        CheatResponse {
+            index: index,
+            code: entry.code.clone(),
+            enabled: entry.enabled,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CheatErrorResponse {
+    error: &'static str,
+}
+
+pub struct ListCheatsHandler {
+    cheats: Arc<Mutex<Cheats>>,
+}
+
+impl ListCheatsHandler {
+    pub fn new(cheats: Arc<Mutex<Cheats>>) -> Self {
+        ListCheatsHandler { cheats: cheats }
+    }
+}
+
+impl Handler for ListCheatsHandler {
+    fn handle(&self, _: &mut Request) -> IronResult<Response> {
+        debug!("List cheats request received!");
+        let cheats = &(*self.cheats.lock().unwrap());
+        let resp_model = cheats
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| CheatResponse::new(i, entry))
+            .collect::<Vec<CheatResponse>>();
+        let resp_body = serde_json::to_string(&resp_model).unwrap();
+        Ok(response_with((status::Ok, resp_body)))
+    }
+}
+
+/// Cheat changes are picked up by the CPU thread the next time it steps, which `cheats_changed`
+/// signals.
+pub struct AddCheatHandler {
+    cheats: Arc<Mutex<Cheats>>,
+    cheats_changed: Arc<AtomicBool>,
+}
+
+impl AddCheatHandler {
+    pub fn new(cheats: Arc<Mutex<Cheats>>, cheats_changed: Arc<AtomicBool>) -> Self {
+        AddCheatHandler {
+            cheats: cheats,
+            cheats_changed: cheats_changed,
+        }
+    }
+}
+
+impl Handler for AddCheatHandler {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        debug!("Add cheat request received!");
+        let code = get_router(req).find("code").unwrap_or("").to_owned();
+        let cheats = &mut (*self.cheats.lock().unwrap());
+        match cheats.add(&code) {
+            Ok(index) => {
+                self.cheats_changed.store(true, Ordering::Relaxed);
+                let resp_model = CheatResponse::new(index, &cheats.entries()[index]);
+                let resp_body = serde_json::to_string(&resp_model).unwrap();
+                Ok(response_with((status::Ok, resp_body)))
+            }
+            Err(error) => {
+                let resp_model = CheatErrorResponse { error: error };
+                let resp_body = serde_json::to_string(&resp_model).unwrap();
+                Ok(response_with((status::BadRequest, resp_body)))
+            }
+        }
+    }
+}
+
+pub struct ToggleCheatHandler {
+    cheats: Arc<Mutex<Cheats>>,
+    cheats_changed: Arc<AtomicBool>,
+}
+
+impl ToggleCheatHandler {
+    pub fn new(cheats: Arc<Mutex<Cheats>>, cheats_changed: Arc<AtomicBool>) -> Self {
+        ToggleCheatHandler {
+            cheats: cheats,
+            cheats_changed: cheats_changed,
+        }
+    }
+}
+
+impl Handler for ToggleCheatHandler {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        debug!("Toggle cheat request received!");
+        let index = get_router(req)
+            .find("index")
+            .and_then(|i| i.parse::<usize>().ok());
+        let cheats = &mut (*self.cheats.lock().unwrap());
+        if let Some(index) = index {
+            if cheats.toggle(index).is_some() {
+                self.cheats_changed.store(true, Ordering::Relaxed);
+                let resp_model = CheatResponse::new(index, &cheats.entries()[index]);
+                let resp_body = serde_json::to_string(&resp_model).unwrap();
+                return Ok(response_with((status::Ok, resp_body)));
+            }
+        }
+        Ok(response_with((status::BadRequest)))
+    }
+}
+
 #[derive(Serialize)]
 struct ContinueResponse {
     continued: bool,
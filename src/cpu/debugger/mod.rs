@@ -5,15 +5,16 @@ mod cpu_snapshot;
 
 use audio::Audio;
 use byte_utils::from_lo_hi;
+use cheat::Cheats;
 use chan::{self, Receiver, Sender};
-use cpu::{Cpu, Interrupt};
+use cpu::{Cpu, Interrupt, StepResult};
 use cpu::debugger::breakpoint_map::BreakpointMap;
 use cpu::debugger::cpu_snapshot::{CpuSnapshot, MemorySnapshot};
 use cpu::debugger::debugger_command::{BreakReason, DebuggerCommand};
 use cpu::debugger::http_handlers::*;
 use input::Input;
 use iron::prelude::*;
-use memory::{ADDRESSABLE_MEMORY, Memory};
+use memory::{ADDRESSABLE_MEMORY, Memory, PPU_ADDRESSABLE_MEMORY};
 use router::Router;
 use screen::Screen;
 use serde::Serialize;
@@ -42,8 +43,11 @@ pub struct HttpDebugger<S: Screen + Serialize, I: Input, A: Audio, M: Memory<I,
     cpu_paused: Arc<AtomicBool>,
     break_on_nmi: Arc<AtomicBool>,
     break_on_trap: Arc<AtomicBool>,
+    cheats: Arc<Mutex<Cheats>>,
+    cheats_changed: Arc<AtomicBool>,
     last_pc: u16,
     last_mem_hash: u64,
+    last_step_result: StepResult,
 }
 
 impl<S: Screen + Serialize, I: Input, A: Audio, M: Memory<I, S, A>> HttpDebugger<S, I, A, M> {
@@ -58,8 +62,11 @@ impl<S: Screen + Serialize, I: Input, A: Audio, M: Memory<I, S, A>> HttpDebugger
             cpu_paused: Arc::new(AtomicBool::new(true)),
             break_on_nmi: Arc::new(AtomicBool::new(false)),
             break_on_trap: Arc::new(AtomicBool::new(false)),
+            cheats: Arc::new(Mutex::new(Cheats::default())),
+            cheats_changed: Arc::new(AtomicBool::new(false)),
             last_pc: 0,
             last_mem_hash: 0,
+            last_step_result: StepResult::Executed(Interrupt::None),
         }
     }
 
@@ -68,18 +75,43 @@ impl<S: Screen + Serialize, I: Input, A: Audio, M: Memory<I, S, A>> HttpDebugger
         self.start_websocket_thread();
     }
 
-    pub fn step(&mut self) -> Interrupt {
+    pub fn step(&mut self) -> StepResult {
         if let Some(break_reason) = self.break_reason() {
             let snapshot = self.cpu_snapshot();
             self.ws_tx
                 .send(DebuggerCommand::Break(break_reason, snapshot));
             thread::park();
         }
+        self.sync_cheats();
         self.last_pc = self.cpu.registers.pc;
-        self.cpu.step()
+        self.last_step_result = self.cpu.step();
+        self.last_step_result
+    }
+
+    // Cheats are edited by the HTTP thread, and copied to memory so that reads don't need a lock
+    fn sync_cheats(&mut self) {
+        if self.cheats_changed.swap(false, Ordering::Relaxed) {
+            let cheats = self.cheats.lock().unwrap().clone();
+            if let Some(mem_cheats) = self.cpu.memory.cheats_mut() {
+                *mem_cheats = cheats;
+            }
+        }
     }
 
     fn break_reason(&self) -> Option<BreakReason> {
+        match self.last_step_result {
+            StepResult::Jammed { opcode, addr } |
+            StepResult::IllegalOpcode { opcode, addr } => {
+                debug!("Illegal opcode {:0>2X} @ {:0>4X}. CPU thread paused.",
+                       opcode,
+                       addr);
+                self.cpu_paused
+                    .compare_and_swap(false, true, Ordering::Relaxed);
+                return Some(BreakReason::IllegalOpcode);
+            }
+            StepResult::Executed(_) => (),
+        }
+
         if self.interrupt_handler() == InterruptHandler::Nmi &&
            self.break_on_nmi.load(Ordering::Relaxed) {
             debug!("Break on NMI. CPU thread paused.");
@@ -111,8 +143,10 @@ impl<S: Screen + Serialize, I: Input, A: Audio, M: Memory<I, S, A>> HttpDebugger
             debug!("Memory altered");
             let mut buf = Vec::with_capacity(ADDRESSABLE_MEMORY);
             self.cpu.memory.dump(&mut buf);
+            let mut vram = Vec::with_capacity(PPU_ADDRESSABLE_MEMORY);
+            self.cpu.memory.dump_vram(&mut vram);
             self.last_mem_hash = hash;
-            MemorySnapshot::Updated(hash, buf)
+            MemorySnapshot::Updated(hash, buf, vram)
         } else {
             MemorySnapshot::NoChange(hash)
         };
@@ -159,6 +193,8 @@ impl<S: Screen + Serialize, I: Input, A: Audio, M: Memory<I, S, A>> HttpDebugger
         let breakpoints = self.breakpoints.clone();
         let cpu_paused = self.cpu_paused.clone();
         let break_on_nmi = self.break_on_nmi.clone();
+        let cheats = self.cheats.clone();
+        let cheats_changed = self.cheats_changed.clone();
 
         thread::spawn(move || {
             let mut router = Router::new();
@@ -173,6 +209,14 @@ impl<S: Screen + Serialize, I: Input, A: Audio, M: Memory<I, S, A>> HttpDebugger
             router.get("/toggle_break_on_nmi",
                        ToggleBreakOnNmiHandler::new(break_on_nmi),
                        "toggle_break_on_nmi");
+
+            router.get("/cheats", ListCheatsHandler::new(cheats.clone()), "cheats");
+            router.get("/add_cheat/:code",
+                       AddCheatHandler::new(cheats.clone(), cheats_changed.clone()),
+                       "add_cheat");
+            router.get("/toggle_cheat/:index",
+                       ToggleCheatHandler::new(cheats, cheats_changed),
+                       "toggle_cheat");
             Iron::new(router).http(DEBUGGER_HTTP_ADDR).unwrap();
         });
     }
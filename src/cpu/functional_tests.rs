@@ -1,5 +1,12 @@
+use apu::Apu;
+use audio::NullSink;
 use cpu::*;
+use input::InputBase;
+use mapper::Cartridge;
 use memory::*;
+use memory::nes_memory::NesMemoryImpl;
+use ppu::{Ppu, PpuImpl};
+use rom::NesRom;
 use std::fs::File;
 use std::io::Read;
 
@@ -50,17 +57,14 @@ fn interrupts() {
     loop {
         let interrupt_probe = cpu.read_memory(0xbffc);
 
-        if interrupt_probe != previous_interrupt_probe {
-            previous_interrupt_probe = interrupt_probe;
-
-            if interrupt_probe & 0x2 > 0 {
-                cpu.pending_interrupt = Interrupt::Nmi;
-            }
+        // NMI is edge triggered, on a rising edge of bit 1
+        if interrupt_probe & !previous_interrupt_probe & 0x2 > 0 {
+            cpu.nmi_pending = true;
         }
+        previous_interrupt_probe = interrupt_probe;
 
-        if !cpu.registers.interrupt_disable_flag() && (interrupt_probe & 0x1) > 0 {
-            cpu.pending_interrupt = Interrupt::Irq;
-        }
+        // IRQ is level triggered by bit 0
+        cpu.memory.set_irq_line(interrupt_probe & 0x1 > 0);
 
         cpu.step();
         // Prevent endless loop
@@ -80,3 +84,56 @@ fn interrupts() {
         last_pc = cpu.registers.pc;
     }
 }
+
+// blargg's test ROMs aren't distributed with the repo. Copy them into test_roms to run these with
+// --ignored.
+#[test]
+#[ignore]
+fn cpu_interrupts() {
+    blargg_test("test_roms/cpu_interrupts.nes");
+}
+
+#[test]
+#[ignore]
+fn sprdma_and_dmc_dma() {
+    blargg_test("test_roms/sprdma_and_dmc_dma.nes");
+}
+
+// Runs a test ROM that reports its status at $6000 once $6001-$6003 hold the signature DE B0 61.
+// $80 means the test is running, and anything after that is its result, with zero for a pass.
+// The result text is written from $6004.
+fn blargg_test(path: &str) {
+    let cart = Cartridge::new(NesRom::read(path).unwrap()).unwrap();
+    let ppu = PpuImpl::new(cart.clone());
+    let apu = Apu::new(Box::new(NullSink));
+    let mem = NesMemoryImpl::new(cart, ppu, InputBase::default(), apu);
+    let mut cpu = Cpu::new(mem);
+    cpu.reset();
+    let mut running = false;
+
+    loop {
+        cpu.step();
+        // Prevent endless loop
+        if cpu.cycles > MAX_CYCLES {
+            assert!(false, "Took too many cycles to complete");
+        }
+
+        let signature = (0x6001..0x6004).map(|addr| cpu.memory.read(addr)).collect::<Vec<u8>>();
+        if signature != [0xde, 0xb0, 0x61] {
+            continue;
+        }
+
+        let status = cpu.memory.read(0x6000);
+        if status == 0x80 {
+            running = true;
+        } else if running {
+            let text = (0x6004..0x8000)
+                .map(|addr| cpu.memory.read(addr))
+                .take_while(|&byte| byte != 0)
+                .map(|byte| byte as char)
+                .collect::<String>();
+            assert!(status == 0, "{}", text);
+            break;
+        }
+    }
+}
@@ -45,6 +45,32 @@ macro_rules! assert_length_and_cycles {
     }}
 }
 
+/// Same as `assert_length_and_cycles`, but takes raw instruction bytes for the unofficial opcodes
+/// that the assembler doesn't support.
+macro_rules! assert_length_and_cycles_raw {
+    ( $ bytes : expr , $ expected_len : expr , $ expected_cycles : expr ) => {{
+        let mut cpu = TestCpu::new_test();
+        cpu.registers.x = 1;
+        cpu.registers.y = 1;
+        cpu.memory.store_many(0x55, &[0xff, 0x33]);
+        cpu.memory.store_many(0x200, &$bytes[..]);
+        let expected_cycles = $expected_cycles;
+        let expected_len = $expected_len;
+        cpu.step();
+        let actual_len = cpu.registers.pc - 0x200;
+
+        if expected_len != actual_len {
+            panic!("Expected instruction length is {} but it was {}",
+                    expected_len, actual_len)
+        }
+
+        if expected_cycles != cpu.cycles {
+            panic!("Expected number of executed cycles to be {} but it was {}",
+                    expected_cycles, cpu.cycles)
+        }
+    }}
+}
+
 macro_rules! assert_cycles {
     ( $ cpu : expr , $ asm : expr , $ expected_cycles : expr ) => {{
         let asm = $asm;
@@ -908,3 +934,368 @@ fn tya() {
     // Implied
     assert_length_and_cycles!("TYA\n", 1, 2);
 }
+
+#[test]
+fn unofficial_alr() {
+    // Immediate
+    assert_length_and_cycles_raw!([0x4b, 0x44], 2, 2);
+}
+
+#[test]
+fn unofficial_anc() {
+    // Immediate
+    assert_length_and_cycles_raw!([0x0b, 0x44], 2, 2);
+    assert_length_and_cycles_raw!([0x2b, 0x44], 2, 2);
+}
+
+#[test]
+fn unofficial_arr() {
+    // Immediate
+    assert_length_and_cycles_raw!([0x6b, 0x44], 2, 2);
+}
+
+#[test]
+fn unofficial_axs() {
+    // Immediate
+    assert_length_and_cycles_raw!([0xcb, 0x44], 2, 2);
+}
+
+#[test]
+fn unofficial_dcp() {
+    // Zero Page
+    assert_length_and_cycles_raw!([0xc7, 0x44], 2, 5);
+
+    // Zero Page,X
+    assert_length_and_cycles_raw!([0xd7, 0x44], 2, 6);
+
+    // Absolute
+    assert_length_and_cycles_raw!([0xcf, 0x00, 0x44], 3, 6);
+
+    // Absolute,X
+    assert_length_and_cycles_raw!([0xdf, 0x00, 0x44], 3, 7);
+
+    // Absolute,X with page cross
+    assert_length_and_cycles_raw!([0xdf, 0xff, 0x44], 3, 7);
+
+    // Absolute,Y
+    assert_length_and_cycles_raw!([0xdb, 0x00, 0x44], 3, 7);
+
+    // Absolute,Y with page cross
+    assert_length_and_cycles_raw!([0xdb, 0xff, 0x44], 3, 7);
+
+    // Indirect,X
+    assert_length_and_cycles_raw!([0xc3, 0x44], 2, 8);
+
+    // Indirect,Y
+    assert_length_and_cycles_raw!([0xd3, 0x44], 2, 8);
+
+    // Indirect,Y with page cross
+    assert_length_and_cycles_raw!([0xd3, 0x55], 2, 8);
+}
+
+#[test]
+fn unofficial_isc() {
+    // Zero Page
+    assert_length_and_cycles_raw!([0xe7, 0x44], 2, 5);
+
+    // Zero Page,X
+    assert_length_and_cycles_raw!([0xf7, 0x44], 2, 6);
+
+    // Absolute
+    assert_length_and_cycles_raw!([0xef, 0x00, 0x44], 3, 6);
+
+    // Absolute,X
+    assert_length_and_cycles_raw!([0xff, 0x00, 0x44], 3, 7);
+
+    // Absolute,X with page cross
+    assert_length_and_cycles_raw!([0xff, 0xff, 0x44], 3, 7);
+
+    // Absolute,Y
+    assert_length_and_cycles_raw!([0xfb, 0x00, 0x44], 3, 7);
+
+    // Absolute,Y with page cross
+    assert_length_and_cycles_raw!([0xfb, 0xff, 0x44], 3, 7);
+
+    // Indirect,X
+    assert_length_and_cycles_raw!([0xe3, 0x44], 2, 8);
+
+    // Indirect,Y
+    assert_length_and_cycles_raw!([0xf3, 0x44], 2, 8);
+
+    // Indirect,Y with page cross
+    assert_length_and_cycles_raw!([0xf3, 0x55], 2, 8);
+}
+
+#[test]
+fn unofficial_las() {
+    // Absolute,Y
+    assert_length_and_cycles_raw!([0xbb, 0x00, 0x44], 3, 4);
+
+    // Absolute,Y with page cross
+    assert_length_and_cycles_raw!([0xbb, 0xff, 0x44], 3, 5);
+}
+
+#[test]
+fn unofficial_lax() {
+    // Immediate
+    assert_length_and_cycles_raw!([0xab, 0x44], 2, 2);
+
+    // Zero Page
+    assert_length_and_cycles_raw!([0xa7, 0x44], 2, 3);
+
+    // Zero Page,Y
+    assert_length_and_cycles_raw!([0xb7, 0x44], 2, 4);
+
+    // Absolute
+    assert_length_and_cycles_raw!([0xaf, 0x00, 0x44], 3, 4);
+
+    // Absolute,Y
+    assert_length_and_cycles_raw!([0xbf, 0x00, 0x44], 3, 4);
+
+    // Absolute,Y with page cross
+    assert_length_and_cycles_raw!([0xbf, 0xff, 0x44], 3, 5);
+
+    // Indirect,X
+    assert_length_and_cycles_raw!([0xa3, 0x44], 2, 6);
+
+    // Indirect,Y
+    assert_length_and_cycles_raw!([0xb3, 0x44], 2, 5);
+
+    // Indirect,Y with page cross
+    assert_length_and_cycles_raw!([0xb3, 0x55], 2, 6);
+}
+
+#[test]
+fn unofficial_nop() {
+    // Implied
+    for &opcode in &[0x1a, 0x3a, 0x5a, 0x7a, 0xda, 0xfa] {
+        assert_length_and_cycles_raw!([opcode], 1, 2);
+    }
+
+    // Immediate
+    for &opcode in &[0x80, 0x82, 0x89, 0xc2, 0xe2] {
+        assert_length_and_cycles_raw!([opcode, 0x44], 2, 2);
+    }
+
+    // Zero Page
+    for &opcode in &[0x04, 0x44, 0x64] {
+        assert_length_and_cycles_raw!([opcode, 0x44], 2, 3);
+    }
+
+    // Zero Page,X
+    for &opcode in &[0x14, 0x34, 0x54, 0x74, 0xd4, 0xf4] {
+        assert_length_and_cycles_raw!([opcode, 0x44], 2, 4);
+    }
+
+    // Absolute
+    assert_length_and_cycles_raw!([0x0c, 0x00, 0x44], 3, 4);
+
+    for &opcode in &[0x1c, 0x3c, 0x5c, 0x7c, 0xdc, 0xfc] {
+        // Absolute,X
+        assert_length_and_cycles_raw!([opcode, 0x00, 0x44], 3, 4);
+
+        // Absolute,X with page cross
+        assert_length_and_cycles_raw!([opcode, 0xff, 0x44], 3, 5);
+    }
+}
+
+#[test]
+fn unofficial_rla() {
+    // Zero Page
+    assert_length_and_cycles_raw!([0x27, 0x44], 2, 5);
+
+    // Zero Page,X
+    assert_length_and_cycles_raw!([0x37, 0x44], 2, 6);
+
+    // Absolute
+    assert_length_and_cycles_raw!([0x2f, 0x00, 0x44], 3, 6);
+
+    // Absolute,X
+    assert_length_and_cycles_raw!([0x3f, 0x00, 0x44], 3, 7);
+
+    // Absolute,X with page cross
+    assert_length_and_cycles_raw!([0x3f, 0xff, 0x44], 3, 7);
+
+    // Absolute,Y
+    assert_length_and_cycles_raw!([0x3b, 0x00, 0x44], 3, 7);
+
+    // Absolute,Y with page cross
+    assert_length_and_cycles_raw!([0x3b, 0xff, 0x44], 3, 7);
+
+    // Indirect,X
+    assert_length_and_cycles_raw!([0x23, 0x44], 2, 8);
+
+    // Indirect,Y
+    assert_length_and_cycles_raw!([0x33, 0x44], 2, 8);
+
+    // Indirect,Y with page cross
+    assert_length_and_cycles_raw!([0x33, 0x55], 2, 8);
+}
+
+#[test]
+fn unofficial_rra() {
+    // Zero Page
+    assert_length_and_cycles_raw!([0x67, 0x44], 2, 5);
+
+    // Zero Page,X
+    assert_length_and_cycles_raw!([0x77, 0x44], 2, 6);
+
+    // Absolute
+    assert_length_and_cycles_raw!([0x6f, 0x00, 0x44], 3, 6);
+
+    // Absolute,X
+    assert_length_and_cycles_raw!([0x7f, 0x00, 0x44], 3, 7);
+
+    // Absolute,X with page cross
+    assert_length_and_cycles_raw!([0x7f, 0xff, 0x44], 3, 7);
+
+    // Absolute,Y
+    assert_length_and_cycles_raw!([0x7b, 0x00, 0x44], 3, 7);
+
+    // Absolute,Y with page cross
+    assert_length_and_cycles_raw!([0x7b, 0xff, 0x44], 3, 7);
+
+    // Indirect,X
+    assert_length_and_cycles_raw!([0x63, 0x44], 2, 8);
+
+    // Indirect,Y
+    assert_length_and_cycles_raw!([0x73, 0x44], 2, 8);
+
+    // Indirect,Y with page cross
+    assert_length_and_cycles_raw!([0x73, 0x55], 2, 8);
+}
+
+#[test]
+fn unofficial_sax() {
+    // Zero Page
+    assert_length_and_cycles_raw!([0x87, 0x44], 2, 3);
+
+    // Zero Page,Y
+    assert_length_and_cycles_raw!([0x97, 0x44], 2, 4);
+
+    // Absolute
+    assert_length_and_cycles_raw!([0x8f, 0x00, 0x44], 3, 4);
+
+    // Indirect,X
+    assert_length_and_cycles_raw!([0x83, 0x44], 2, 6);
+}
+
+#[test]
+fn unofficial_sbc() {
+    // Immediate
+    assert_length_and_cycles_raw!([0xeb, 0x44], 2, 2);
+}
+
+#[test]
+fn unofficial_sha() {
+    // Absolute,Y
+    assert_length_and_cycles_raw!([0x9f, 0x00, 0x44], 3, 5);
+
+    // Absolute,Y with page cross
+    assert_length_and_cycles_raw!([0x9f, 0xff, 0x44], 3, 5);
+
+    // Indirect,Y
+    assert_length_and_cycles_raw!([0x93, 0x44], 2, 6);
+
+    // Indirect,Y with page cross
+    assert_length_and_cycles_raw!([0x93, 0x55], 2, 6);
+}
+
+#[test]
+fn unofficial_shx() {
+    // Absolute,Y
+    assert_length_and_cycles_raw!([0x9e, 0x00, 0x44], 3, 5);
+
+    // Absolute,Y with page cross
+    assert_length_and_cycles_raw!([0x9e, 0xff, 0x44], 3, 5);
+}
+
+#[test]
+fn unofficial_shy() {
+    // Absolute,X
+    assert_length_and_cycles_raw!([0x9c, 0x00, 0x44], 3, 5);
+
+    // Absolute,X with page cross
+    assert_length_and_cycles_raw!([0x9c, 0xff, 0x44], 3, 5);
+}
+
+#[test]
+fn unofficial_slo() {
+    // Zero Page
+    assert_length_and_cycles_raw!([0x07, 0x44], 2, 5);
+
+    // Zero Page,X
+    assert_length_and_cycles_raw!([0x17, 0x44], 2, 6);
+
+    // Absolute
+    assert_length_and_cycles_raw!([0x0f, 0x00, 0x44], 3, 6);
+
+    // Absolute,X
+    assert_length_and_cycles_raw!([0x1f, 0x00, 0x44], 3, 7);
+
+    // Absolute,X with page cross
+    assert_length_and_cycles_raw!([0x1f, 0xff, 0x44], 3, 7);
+
+    // Absolute,Y
+    assert_length_and_cycles_raw!([0x1b, 0x00, 0x44], 3, 7);
+
+    // Absolute,Y with page cross
+    assert_length_and_cycles_raw!([0x1b, 0xff, 0x44], 3, 7);
+
+    // Indirect,X
+    assert_length_and_cycles_raw!([0x03, 0x44], 2, 8);
+
+    // Indirect,Y
+    assert_length_and_cycles_raw!([0x13, 0x44], 2, 8);
+
+    // Indirect,Y with page cross
+    assert_length_and_cycles_raw!([0x13, 0x55], 2, 8);
+}
+
+#[test]
+fn unofficial_sre() {
+    // Zero Page
+    assert_length_and_cycles_raw!([0x47, 0x44], 2, 5);
+
+    // Zero Page,X
+    assert_length_and_cycles_raw!([0x57, 0x44], 2, 6);
+
+    // Absolute
+    assert_length_and_cycles_raw!([0x4f, 0x00, 0x44], 3, 6);
+
+    // Absolute,X
+    assert_length_and_cycles_raw!([0x5f, 0x00, 0x44], 3, 7);
+
+    // Absolute,X with page cross
+    assert_length_and_cycles_raw!([0x5f, 0xff, 0x44], 3, 7);
+
+    // Absolute,Y
+    assert_length_and_cycles_raw!([0x5b, 0x00, 0x44], 3, 7);
+
+    // Absolute,Y with page cross
+    assert_length_and_cycles_raw!([0x5b, 0xff, 0x44], 3, 7);
+
+    // Indirect,X
+    assert_length_and_cycles_raw!([0x43, 0x44], 2, 8);
+
+    // Indirect,Y
+    assert_length_and_cycles_raw!([0x53, 0x44], 2, 8);
+
+    // Indirect,Y with page cross
+    assert_length_and_cycles_raw!([0x53, 0x55], 2, 8);
+}
+
+#[test]
+fn unofficial_tas() {
+    // Absolute,Y
+    assert_length_and_cycles_raw!([0x9b, 0x00, 0x44], 3, 5);
+
+    // Absolute,Y with page cross
+    assert_length_and_cycles_raw!([0x9b, 0xff, 0x44], 3, 5);
+}
+
+#[test]
+fn unofficial_xaa() {
+    // Immediate
+    assert_length_and_cycles_raw!([0x8b, 0x44], 2, 2);
+}
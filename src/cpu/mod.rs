@@ -42,18 +42,64 @@ impl TestCpu {
 
 
 #[allow(dead_code)]
-#[derive(Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum Interrupt {
     None,
     Nmi,
     Irq,
 }
 
+/// How the CPU responds to a JAM (also known as KIL) opcode
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum IllegalOpcodePolicy {
+    /// Stop executing instructions until reset, like the hardware does. The rest of the system
+    /// continues to be clocked.
+    Halt,
+    /// Treat the opcode as a single byte NOP
+    Nop,
+    /// Leave the program counter on the opcode and report it on every step
+    Error,
+}
+
+impl Default for IllegalOpcodePolicy {
+    fn default() -> Self {
+        IllegalOpcodePolicy::Halt
+    }
+}
+
+/// The outcome of a single CPU step
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum StepResult {
+    /// An instruction was executed, along with the interrupt that was serviced afterwards
+    Executed(Interrupt),
+    /// The CPU is halted by the JAM opcode at `addr`
+    Jammed { opcode: u8, addr: u16 },
+    /// A JAM opcode was encountered at `addr` under `IllegalOpcodePolicy::Error`
+    IllegalOpcode { opcode: u8, addr: u16 },
+}
+
+impl StepResult {
+    /// The interrupt serviced during the step, if any
+    pub fn interrupt(&self) -> Interrupt {
+        match *self {
+            StepResult::Executed(interrupt) => interrupt,
+            _ => Interrupt::None,
+        }
+    }
+}
+
 pub struct Cpu<S: Screen, I: Input, A: Audio, M: Memory<I, S, A>> {
     registers: Registers,
     pub memory: M,
-    pending_interrupt: Interrupt,
     pub cycles: u64,
+    // An NMI edge has been detected but not yet serviced
+    nmi_pending: bool,
+    // The interrupt that would be serviced if the instruction ended on the current cycle, and on
+    // the cycle before it. Hardware decides based on the penultimate cycle of an instruction.
+    poll: Interrupt,
+    prev_poll: Interrupt,
+    pub illegal_opcode_policy: IllegalOpcodePolicy,
+    jammed: Option<(u8, u16)>,
     phantom_s: PhantomData<S>,
     phantom_i: PhantomData<I>,
     phantom_a: PhantomData<A>,
@@ -71,59 +117,88 @@ impl<S: Screen, I: Input, A: Audio, M: Memory<I, S, A>> Cpu<S, I, A, M> {
             registers: Registers::new(),
             memory: memory,
             cycles: 0,
-            pending_interrupt: Interrupt::None,
+            nmi_pending: false,
+            poll: Interrupt::None,
+            prev_poll: Interrupt::None,
+            illegal_opcode_policy: IllegalOpcodePolicy::default(),
+            jammed: None,
             phantom_s: PhantomData,
             phantom_i: PhantomData,
             phantom_a: PhantomData,
         }
     }
 
-    pub fn step(&mut self) -> Interrupt {
+    pub fn step(&mut self) -> StepResult {
+        if let Some((opcode, addr)) = self.jammed {
+            // A jammed CPU no longer fetches instructions, but the rest of the system keeps running
+            self.tick();
+            return StepResult::Jammed {
+                       opcode: opcode,
+                       addr: addr,
+                   };
+        }
+
+        let addr = self.registers.pc;
         let opcode = self.read_pc();
-        self::opcodes::execute(self, opcode);
-
-        let pending_interrupt = self.pending_interrupt;
-        match pending_interrupt {
-            Interrupt::None => (),
-            Interrupt::Nmi => {
-                self.pending_interrupt = Interrupt::None;
-                self.nmi();
-            }
-            Interrupt::Irq => {
-                self.pending_interrupt = Interrupt::None;
-                self.irq();
+        if !self::opcodes::execute(self, opcode) {
+            match self.illegal_opcode_policy {
+                IllegalOpcodePolicy::Halt => {
+                    self.registers.pc = addr;
+                    self.jammed = Some((opcode, addr));
+                    return StepResult::Jammed {
+                               opcode: opcode,
+                               addr: addr,
+                           };
+                }
+                IllegalOpcodePolicy::Nop => self.tick(),
+                IllegalOpcodePolicy::Error => {
+                    self.registers.pc = addr;
+                    return StepResult::IllegalOpcode {
+                               opcode: opcode,
+                               addr: addr,
+                           };
+                }
             }
         }
-        pending_interrupt
+
+        let interrupt = if self.prev_poll != Interrupt::None {
+            self.interrupt()
+        } else {
+            Interrupt::None
+        };
+        StepResult::Executed(interrupt)
     }
 
     pub fn reset(&mut self) {
+        self.jammed = None;
         let pc_low = self.read_memory(RESET_VECTOR);
         let pc_high = self.read_memory(RESET_VECTOR + 1);
         self.registers.pc = from_lo_hi(pc_low, pc_high);
     }
 
-    fn nmi(&mut self) {
-        self.push_pc_and_status();
-        let pc = self.read_memory16(NMI_VECTOR);
-        self.registers.set_interrupt_disable_flag(true);
-        self.registers.pc = pc;
-    }
-
-    fn irq(&mut self) {
-        if !self.registers.interrupt_disable_flag() {
-            self.push_pc_and_status();
-            let pc = self.read_memory16(BREAK_VECTOR);
-            self.registers.set_interrupt_disable_flag(true);
-            self.registers.pc = pc;
-        }
-    }
+    /// Runs the seven cycle interrupt sequence, returning the interrupt that was serviced.
+    ///
+    /// Like BRK, an IRQ is hijacked by an NMI that arrives before the status register is pushed.
+    fn interrupt(&mut self) -> Interrupt {
+        // Two dummy reads of the next instruction
+        self.tick();
+        self.tick();
 
-    fn push_pc_and_status(&mut self) {
-        let stat = self.registers.status_sans_break();
         let pc = self.registers.pc;
         self.push_stack16(pc);
-        self.push_stack(stat)
+
+        let (interrupt, vector) = if self.nmi_pending {
+            self.nmi_pending = false;
+            (Interrupt::Nmi, NMI_VECTOR)
+        } else {
+            (Interrupt::Irq, BREAK_VECTOR)
+        };
+
+        let stat = self.registers.status_sans_break();
+        self.push_stack(stat);
+        self.registers.set_interrupt_disable_flag(true);
+        self.registers.pc = self.read_memory16(vector);
+        interrupt
     }
 
     fn read_memory(&mut self, addr: u16) -> u8 {
@@ -153,8 +228,20 @@ impl<S: Screen, I: Input, A: Audio, M: Memory<I, S, A>> Cpu<S, I, A, M> {
     fn tick(&mut self) {
         self.cycles += 1;
         if self.memory.tick() == Interrupt::Nmi {
-            self.pending_interrupt = Interrupt::Nmi;
+            self.nmi_pending = true;
         }
+        self.cycles += self.memory.take_stall_cycles();
+
+        // Interrupts are polled at the end of every cycle. NMI is latched on its edge, while IRQ is
+        // a level that is only seen for as long as some device holds the line.
+        self.prev_poll = self.poll;
+        self.poll = if self.nmi_pending {
+            Interrupt::Nmi
+        } else if self.memory.irq_line() && !self.registers.interrupt_disable_flag() {
+            Interrupt::Irq
+        } else {
+            Interrupt::None
+        };
     }
 
     fn read_pc(&mut self) -> u8 {
@@ -28,9 +28,12 @@ mod inc_dec_spec_tests;
 #[cfg(test)]
 mod jump_and_returns_instr_spec_tests;
 
+#[cfg(test)]
+mod unofficial_instr_spec_tests;
+
 use audio::Audio;
 use byte_utils::*;
-use cpu::Cpu;
+use cpu::{Cpu, Interrupt, NMI_VECTOR};
 use input::Input;
 use memory::Memory;
 use screen::Screen;
@@ -47,7 +50,8 @@ trait OpCode {
               AM: AddressingMode<S, I, A, M, Output = Self::Input>;
 }
 
-pub fn execute<S, I, A, M>(cpu: &mut Cpu<S, I, A, M>, opcode: u8)
+/// Executes a single instruction, returning `false` if the opcode locks up the CPU instead.
+pub fn execute<S, I, A, M>(cpu: &mut Cpu<S, I, A, M>, opcode: u8) -> bool
     where S: Screen,
           I: Input,
           A: Audio,
@@ -79,6 +83,12 @@ pub fn execute<S, I, A, M>(cpu: &mut Cpu<S, I, A, M>, opcode: u8)
         0x08 => Php::execute(cpu, Implied),
         0x28 => Plp::execute(cpu, Implied),
         0xea => Nop::execute(cpu, Implied),
+        0x1a => Nop::execute(cpu, Implied),
+        0x3a => Nop::execute(cpu, Implied),
+        0x5a => Nop::execute(cpu, Implied),
+        0x7a => Nop::execute(cpu, Implied),
+        0xda => Nop::execute(cpu, Implied),
+        0xfa => Nop::execute(cpu, Implied),
         0x10 => {
             let am = Relative::init(cpu);
             Bpl::execute(cpu, am)
@@ -583,8 +593,358 @@ pub fn execute<S, I, A, M>(cpu: &mut Cpu<S, I, A, M>, opcode: u8)
             let am = AbsoluteAddress::init(cpu);
             Jsr::execute(cpu, am)
         }
-        _ => panic!("Unexpected opcode: {:0>2X}", opcode),
+        0x80 => {
+            let am = Immediate::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x82 => {
+            let am = Immediate::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x89 => {
+            let am = Immediate::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0xc2 => {
+            let am = Immediate::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0xe2 => {
+            let am = Immediate::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x04 => {
+            let am = ZeroPage::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x44 => {
+            let am = ZeroPage::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x64 => {
+            let am = ZeroPage::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x14 => {
+            let am = ZeroPageX::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x34 => {
+            let am = ZeroPageX::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x54 => {
+            let am = ZeroPageX::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x74 => {
+            let am = ZeroPageX::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0xd4 => {
+            let am = ZeroPageX::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0xf4 => {
+            let am = ZeroPageX::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x0c => {
+            let am = Absolute::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x1c => {
+            let am = AbsoluteX::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x3c => {
+            let am = AbsoluteX::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x5c => {
+            let am = AbsoluteX::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0x7c => {
+            let am = AbsoluteX::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0xdc => {
+            let am = AbsoluteX::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0xfc => {
+            let am = AbsoluteX::init(cpu);
+            Ign::execute(cpu, am)
+        }
+        0xa7 => {
+            let am = ZeroPage::init(cpu);
+            Lax::execute(cpu, am)
+        }
+        0xb7 => {
+            let am = ZeroPageY::init(cpu);
+            Lax::execute(cpu, am)
+        }
+        0xaf => {
+            let am = Absolute::init(cpu);
+            Lax::execute(cpu, am)
+        }
+        0xbf => {
+            let am = AbsoluteY::init(cpu);
+            Lax::execute(cpu, am)
+        }
+        0xa3 => {
+            let am = IndexedIndirect::init(cpu);
+            Lax::execute(cpu, am)
+        }
+        0xb3 => {
+            let am = IndirectIndexed::init(cpu);
+            Lax::execute(cpu, am)
+        }
+        0xab => {
+            let am = Immediate::init(cpu);
+            Lxa::execute(cpu, am)
+        }
+        0x87 => {
+            let am = ZeroPage::init_store(cpu);
+            Sax::execute(cpu, am)
+        }
+        0x97 => {
+            let am = ZeroPageY::init_store(cpu);
+            Sax::execute(cpu, am)
+        }
+        0x8f => {
+            let am = Absolute::init_store(cpu);
+            Sax::execute(cpu, am)
+        }
+        0x83 => {
+            let am = IndexedIndirect::init_store(cpu);
+            Sax::execute(cpu, am)
+        }
+        0x07 => {
+            let am = ZeroPage::init(cpu);
+            Slo::execute(cpu, am)
+        }
+        0x17 => {
+            let am = ZeroPageX::init(cpu);
+            Slo::execute(cpu, am)
+        }
+        0x0f => {
+            let am = Absolute::init(cpu);
+            Slo::execute(cpu, am)
+        }
+        0x1f => {
+            let am = AbsoluteX::init_rmw(cpu);
+            Slo::execute(cpu, am)
+        }
+        0x1b => {
+            let am = AbsoluteY::init_rmw(cpu);
+            Slo::execute(cpu, am)
+        }
+        0x03 => {
+            let am = IndexedIndirect::init(cpu);
+            Slo::execute(cpu, am)
+        }
+        0x13 => {
+            let am = IndirectIndexed::init_rmw(cpu);
+            Slo::execute(cpu, am)
+        }
+        0x27 => {
+            let am = ZeroPage::init(cpu);
+            Rla::execute(cpu, am)
+        }
+        0x37 => {
+            let am = ZeroPageX::init(cpu);
+            Rla::execute(cpu, am)
+        }
+        0x2f => {
+            let am = Absolute::init(cpu);
+            Rla::execute(cpu, am)
+        }
+        0x3f => {
+            let am = AbsoluteX::init_rmw(cpu);
+            Rla::execute(cpu, am)
+        }
+        0x3b => {
+            let am = AbsoluteY::init_rmw(cpu);
+            Rla::execute(cpu, am)
+        }
+        0x23 => {
+            let am = IndexedIndirect::init(cpu);
+            Rla::execute(cpu, am)
+        }
+        0x33 => {
+            let am = IndirectIndexed::init_rmw(cpu);
+            Rla::execute(cpu, am)
+        }
+        0x47 => {
+            let am = ZeroPage::init(cpu);
+            Sre::execute(cpu, am)
+        }
+        0x57 => {
+            let am = ZeroPageX::init(cpu);
+            Sre::execute(cpu, am)
+        }
+        0x4f => {
+            let am = Absolute::init(cpu);
+            Sre::execute(cpu, am)
+        }
+        0x5f => {
+            let am = AbsoluteX::init_rmw(cpu);
+            Sre::execute(cpu, am)
+        }
+        0x5b => {
+            let am = AbsoluteY::init_rmw(cpu);
+            Sre::execute(cpu, am)
+        }
+        0x43 => {
+            let am = IndexedIndirect::init(cpu);
+            Sre::execute(cpu, am)
+        }
+        0x53 => {
+            let am = IndirectIndexed::init_rmw(cpu);
+            Sre::execute(cpu, am)
+        }
+        0x67 => {
+            let am = ZeroPage::init(cpu);
+            Rra::execute(cpu, am)
+        }
+        0x77 => {
+            let am = ZeroPageX::init(cpu);
+            Rra::execute(cpu, am)
+        }
+        0x6f => {
+            let am = Absolute::init(cpu);
+            Rra::execute(cpu, am)
+        }
+        0x7f => {
+            let am = AbsoluteX::init_rmw(cpu);
+            Rra::execute(cpu, am)
+        }
+        0x7b => {
+            let am = AbsoluteY::init_rmw(cpu);
+            Rra::execute(cpu, am)
+        }
+        0x63 => {
+            let am = IndexedIndirect::init(cpu);
+            Rra::execute(cpu, am)
+        }
+        0x73 => {
+            let am = IndirectIndexed::init_rmw(cpu);
+            Rra::execute(cpu, am)
+        }
+        0xc7 => {
+            let am = ZeroPage::init(cpu);
+            Dcp::execute(cpu, am)
+        }
+        0xd7 => {
+            let am = ZeroPageX::init(cpu);
+            Dcp::execute(cpu, am)
+        }
+        0xcf => {
+            let am = Absolute::init(cpu);
+            Dcp::execute(cpu, am)
+        }
+        0xdf => {
+            let am = AbsoluteX::init_rmw(cpu);
+            Dcp::execute(cpu, am)
+        }
+        0xdb => {
+            let am = AbsoluteY::init_rmw(cpu);
+            Dcp::execute(cpu, am)
+        }
+        0xc3 => {
+            let am = IndexedIndirect::init(cpu);
+            Dcp::execute(cpu, am)
+        }
+        0xd3 => {
+            let am = IndirectIndexed::init_rmw(cpu);
+            Dcp::execute(cpu, am)
+        }
+        0xe7 => {
+            let am = ZeroPage::init(cpu);
+            Isc::execute(cpu, am)
+        }
+        0xf7 => {
+            let am = ZeroPageX::init(cpu);
+            Isc::execute(cpu, am)
+        }
+        0xef => {
+            let am = Absolute::init(cpu);
+            Isc::execute(cpu, am)
+        }
+        0xff => {
+            let am = AbsoluteX::init_rmw(cpu);
+            Isc::execute(cpu, am)
+        }
+        0xfb => {
+            let am = AbsoluteY::init_rmw(cpu);
+            Isc::execute(cpu, am)
+        }
+        0xe3 => {
+            let am = IndexedIndirect::init(cpu);
+            Isc::execute(cpu, am)
+        }
+        0xf3 => {
+            let am = IndirectIndexed::init_rmw(cpu);
+            Isc::execute(cpu, am)
+        }
+        0x0b => {
+            let am = Immediate::init(cpu);
+            Anc::execute(cpu, am)
+        }
+        0x2b => {
+            let am = Immediate::init(cpu);
+            Anc::execute(cpu, am)
+        }
+        0x4b => {
+            let am = Immediate::init(cpu);
+            Alr::execute(cpu, am)
+        }
+        0x6b => {
+            let am = Immediate::init(cpu);
+            Arr::execute(cpu, am)
+        }
+        0xcb => {
+            let am = Immediate::init(cpu);
+            Axs::execute(cpu, am)
+        }
+        0x8b => {
+            let am = Immediate::init(cpu);
+            Xaa::execute(cpu, am)
+        }
+        0xeb => {
+            let am = Immediate::init(cpu);
+            Sbc::execute(cpu, am)
+        }
+        0xbb => {
+            let am = AbsoluteY::init(cpu);
+            Las::execute(cpu, am)
+        }
+        0x9f => {
+            let am = AbsoluteY::init_store(cpu);
+            Sha::execute(cpu, am)
+        }
+        0x93 => {
+            let am = IndirectIndexed::init_store(cpu);
+            Sha::execute(cpu, am)
+        }
+        0x9e => {
+            let am = AbsoluteY::init_store(cpu);
+            Shx::execute(cpu, am)
+        }
+        0x9c => {
+            let am = AbsoluteX::init_store(cpu);
+            Shy::execute(cpu, am)
+        }
+        0x9b => {
+            let am = AbsoluteY::init_store(cpu);
+            Tas::execute(cpu, am)
+        }
+        // The only opcodes left are JAM (also known as KIL). How to respond is up to the caller.
+        _ => return false,
     }
+    true
 }
 
 pub trait AddressingMode<S: Screen, I: Input, A: Audio, M: Memory<I, S, A>> {
@@ -593,6 +953,12 @@ pub trait AddressingMode<S: Screen, I: Input, A: Audio, M: Memory<I, S, A>> {
     fn write(&self, _: &mut Cpu<S, I, A, M>, _: u8) {
         unimplemented!();
     }
+
+    /// The effective address, for the unstable store instructions whose written value and
+    /// target address both depend on it.
+    fn addr(&self) -> u16 {
+        unimplemented!();
+    }
 }
 
 pub struct Absolute {
@@ -772,11 +1138,16 @@ impl<S, I, A, M> AddressingMode<S, I, A, M> for AbsoluteX
         }
         cpu.write_memory(self.addr, value)
     }
+
+    fn addr(&self) -> u16 {
+        self.addr
+    }
 }
 
 pub struct AbsoluteY {
     addr: u16,
     value: u8,
+    is_store: bool,
 }
 
 impl AbsoluteY {
@@ -786,7 +1157,7 @@ impl AbsoluteY {
               A: Audio,
               M: Memory<I, S, A>
     {
-        Self::init_base(cpu, false)
+        Self::init_base(cpu, Variant::Standard)
     }
 
     pub fn init_store<S, I, A, M>(cpu: &mut Cpu<S, I, A, M>) -> Self
@@ -795,10 +1166,22 @@ impl AbsoluteY {
               A: Audio,
               M: Memory<I, S, A>
     {
-        Self::init_base(cpu, true)
+        Self::init_base(cpu, Variant::Store)
     }
 
-    fn init_base<S, I, A, M>(cpu: &mut Cpu<S, I, A, M>, is_store: bool) -> Self
+    /// Init using special rules for cycle counting specific to read-modify-write instructions
+    ///
+    /// Only unofficial instructions use this addressing mode for read-modify-write operations.
+    pub fn init_rmw<S, I, A, M>(cpu: &mut Cpu<S, I, A, M>) -> Self
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>
+    {
+        Self::init_base(cpu, Variant::ReadModifyWrite)
+    }
+
+    fn init_base<S, I, A, M>(cpu: &mut Cpu<S, I, A, M>, variant: Variant) -> Self
         where S: Screen,
               I: Input,
               A: Audio,
@@ -808,11 +1191,12 @@ impl AbsoluteY {
         let target_addr = base_addr + cpu.registers.y as u16;
 
         // Conditional cycle if memory page crossed
-        if !is_store && base_addr & 0xff00 != target_addr & 0xff00 {
+        if variant != Variant::Store &&
+           (variant == Variant::ReadModifyWrite || (base_addr & 0xff00 != target_addr & 0xff00)) {
             cpu.tick()
         }
 
-        let val = if !is_store {
+        let val = if variant != Variant::Store {
             cpu.read_memory(target_addr)
         } else {
             cpu.tick();
@@ -822,6 +1206,7 @@ impl AbsoluteY {
         AbsoluteY {
             addr: target_addr,
             value: val,
+            is_store: variant == Variant::Store,
         }
     }
 }
@@ -834,8 +1219,16 @@ impl<S: Screen, I: Input, A: Audio, M: Memory<I, S, A>> AddressingMode<S, I, A,
     }
 
     fn write(&self, cpu: &mut Cpu<S, I, A, M>, value: u8) {
+        if !self.is_store {
+            // Dummy write cycle
+            cpu.tick();
+        }
         cpu.write_memory(self.addr, value)
     }
+
+    fn addr(&self) -> u16 {
+        self.addr
+    }
 }
 
 pub struct Accumulator {
@@ -905,6 +1298,7 @@ impl<S, I, A, M> AddressingMode<S, I, A, M> for Implied
 pub struct IndexedIndirect {
     addr: u16,
     value: u8,
+    is_store: bool,
 }
 
 impl IndexedIndirect {
@@ -946,6 +1340,7 @@ impl IndexedIndirect {
         IndexedIndirect {
             addr: target_addr,
             value: value,
+            is_store: is_store,
         }
     }
 }
@@ -963,6 +1358,10 @@ impl<S, I, A, M> AddressingMode<S, I, A, M> for IndexedIndirect
     }
 
     fn write(&self, cpu: &mut Cpu<S, I, A, M>, value: u8) {
+        if !self.is_store {
+            // Dummy write cycle
+            cpu.tick();
+        }
         cpu.write_memory(self.addr, value)
     }
 }
@@ -1011,6 +1410,7 @@ impl<S, I, A, M> AddressingMode<S, I, A, M> for Indirect
 pub struct IndirectIndexed {
     addr: u16,
     value: u8,
+    is_store: bool,
 }
 
 impl IndirectIndexed {
@@ -1020,7 +1420,7 @@ impl IndirectIndexed {
               A: Audio,
               M: Memory<I, S, A>
     {
-        Self::init_base(cpu, false)
+        Self::init_base(cpu, Variant::Standard)
     }
 
     pub fn init_store<S, I, A, M>(cpu: &mut Cpu<S, I, A, M>) -> Self
@@ -1029,10 +1429,22 @@ impl IndirectIndexed {
               A: Audio,
               M: Memory<I, S, A>
     {
-        Self::init_base(cpu, true)
+        Self::init_base(cpu, Variant::Store)
     }
 
-    fn init_base<S, I, A, M>(cpu: &mut Cpu<S, I, A, M>, is_store: bool) -> Self
+    /// Init using special rules for cycle counting specific to read-modify-write instructions
+    ///
+    /// Only unofficial instructions use this addressing mode for read-modify-write operations.
+    pub fn init_rmw<S, I, A, M>(cpu: &mut Cpu<S, I, A, M>) -> Self
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>
+    {
+        Self::init_base(cpu, Variant::ReadModifyWrite)
+    }
+
+    fn init_base<S, I, A, M>(cpu: &mut Cpu<S, I, A, M>, variant: Variant) -> Self
         where S: Screen,
               I: Input,
               A: Audio,
@@ -1044,7 +1456,8 @@ impl IndirectIndexed {
         let target_addr = base_addr + y as u16;
 
         // Conditional cycle if memory page crossed
-        if !is_store && base_addr & 0xff00 != target_addr & 0xff00 {
+        if variant != Variant::Store &&
+           (variant == Variant::ReadModifyWrite || (base_addr & 0xff00 != target_addr & 0xff00)) {
             cpu.tick();
         }
 
@@ -1052,6 +1465,7 @@ impl IndirectIndexed {
         IndirectIndexed {
             addr: target_addr,
             value: val,
+            is_store: variant == Variant::Store,
         }
     }
 }
@@ -1065,8 +1479,16 @@ impl<S: Screen, I: Input, A: Audio, M: Memory<I, S, A>> AddressingMode<S, I, A,
     }
 
     fn write(&self, cpu: &mut Cpu<S, I, A, M>, value: u8) {
+        if !self.is_store {
+            // Dummy write cycle
+            cpu.tick();
+        }
         cpu.write_memory(self.addr, value)
     }
+
+    fn addr(&self) -> u16 {
+        self.addr
+    }
 }
 
 pub struct Relative {
@@ -1353,7 +1775,7 @@ impl OpCode for And {
     }
 }
 
-fn shift_left<S, I, M, AM, A>(cpu: &mut Cpu<S, I, A, M>, am: AM, lsb: bool)
+fn shift_left<S, I, M, AM, A>(cpu: &mut Cpu<S, I, A, M>, am: AM, lsb: bool) -> u8
     where S: Screen,
           I: Input,
           A: Audio,
@@ -1365,10 +1787,11 @@ fn shift_left<S, I, M, AM, A>(cpu: &mut Cpu<S, I, A, M>, am: AM, lsb: bool)
     let res = if lsb { (val << 1) | 0x1 } else { val << 1 };
     cpu.registers.set_carry_flag(carry);
     cpu.registers.set_sign_and_zero_flag(res);
-    am.write(cpu, res)
+    am.write(cpu, res);
+    res
 }
 
-fn shift_right<S, I, M, AM, A>(cpu: &mut Cpu<S, I, A, M>, am: AM, msb: bool)
+fn shift_right<S, I, M, AM, A>(cpu: &mut Cpu<S, I, A, M>, am: AM, msb: bool) -> u8
     where S: Screen,
           I: Input,
           A: Audio,
@@ -1380,7 +1803,8 @@ fn shift_right<S, I, M, AM, A>(cpu: &mut Cpu<S, I, A, M>, am: AM, msb: bool)
     let res = if msb { (val >> 1) | 0x80 } else { val >> 1 };
     cpu.registers.set_carry_flag(carry);
     cpu.registers.set_sign_and_zero_flag(res);
-    am.write(cpu, res)
+    am.write(cpu, res);
+    res
 }
 
 struct Asl;
@@ -1395,7 +1819,7 @@ impl OpCode for Asl {
               M: Memory<I, S, A>,
               AM: AddressingMode<S, I, A, M, Output = Self::Input>
     {
-        shift_left(cpu, am, false)
+        shift_left(cpu, am, false);
     }
 }
 
@@ -1412,7 +1836,7 @@ impl OpCode for Rol {
               AM: AddressingMode<S, I, A, M, Output = Self::Input>
     {
         let carry_set = cpu.registers.carry_flag();
-        shift_left(cpu, am, carry_set)
+        shift_left(cpu, am, carry_set);
     }
 }
 
@@ -1429,7 +1853,7 @@ impl OpCode for Ror {
               AM: AddressingMode<S, I, A, M, Output = Self::Input>
     {
         let carry_set = cpu.registers.carry_flag();
-        shift_right(cpu, am, carry_set)
+        shift_right(cpu, am, carry_set);
     }
 }
 
@@ -1445,7 +1869,7 @@ impl OpCode for Lsr {
               M: Memory<I, S, A>,
               AM: AddressingMode<S, I, A, M, Output = Self::Input>
     {
-        shift_right(cpu, am, false)
+        shift_right(cpu, am, false);
     }
 }
 
@@ -1457,6 +1881,12 @@ fn branch<S, I, M, AM, A>(cpu: &mut Cpu<S, I, A, M>, am: AM, condition: bool)
           AM: AddressingMode<S, I, A, M, Output = i8>
 {
     if condition {
+        // Branches poll for interrupts before the operand fetch rather than on their penultimate
+        // cycle, so an interrupt that arrives later in a taken branch waits until after the next
+        // instruction. Branches that cross a page poll again before fixing up the high byte. The
+        // operand has already been fetched, so the poll before it is the previous one.
+        let poll = cpu.prev_poll;
+
         let rel_addr = am.read();
         let old_pc = cpu.registers.pc;
         cpu.registers.pc = (cpu.registers.pc as i32 + rel_addr as i32) as u16;
@@ -1465,6 +1895,8 @@ fn branch<S, I, M, AM, A>(cpu: &mut Cpu<S, I, A, M>, am: AM, condition: bool)
         // Conditional cycle if pc crosses page boundary
         if old_pc & 0xFF00 != cpu.registers.pc & 0xFF00 {
             cpu.tick();
+        } else {
+            cpu.prev_poll = poll;
         }
     }
 }
@@ -1613,6 +2045,12 @@ fn compare<S, I, M, AM, A>(cpu: &mut Cpu<S, I, A, M>, am: AM, lhs: u8)
           AM: AddressingMode<S, I, A, M, Output = u8>
 {
     let rhs = am.read();
+    compare_base(cpu, lhs, rhs)
+}
+
+fn compare_base<S: Screen, I: Input, A: Audio, M: Memory<I, S, A>>(cpu: &mut Cpu<S, I, A, M>,
+                                                                   lhs: u8,
+                                                                   rhs: u8) {
     let res = lhs as i32 - rhs as i32;
     cpu.registers.set_carry_flag(res & 0x100 == 0);
     cpu.registers.set_sign_and_zero_flag(res as u8);
@@ -1703,14 +2141,33 @@ impl OpCode for Brk {
               M: Memory<I, S, A>,
               AM: AddressingMode<S, I, A, M, Output = Self::Input>
     {
-        cpu.registers.pc += 1;
+        // An NMI that was already pending is serviced after the BRK, like any other interrupt
+        let nmi_was_pending = cpu.nmi_pending;
+
+        // Dummy read of the padding byte
+        cpu.read_pc();
+
         let pc = cpu.registers.pc;
-        let status = cpu.registers.status;
         cpu.push_stack16(pc);
+
+        // An NMI that arrives before the status register is pushed hijacks the BRK
+        let hijacked = cpu.nmi_pending && !nmi_was_pending;
+        let vector = if hijacked {
+            cpu.nmi_pending = false;
+            NMI_VECTOR
+        } else {
+            BRK_VECTOR
+        };
+
+        let status = cpu.registers.status;
         cpu.push_stack(status);
-        let irq_handler = cpu.read_memory16(BRK_VECTOR);
-        cpu.registers.pc = irq_handler;
         cpu.registers.set_interrupt_disable_flag(true);
+        cpu.registers.pc = cpu.read_memory16(vector);
+
+        // Don't service the hijacking NMI again right after the BRK
+        if hijacked {
+            cpu.prev_poll = Interrupt::None;
+        }
     }
 }
 
@@ -2142,9 +2599,10 @@ impl OpCode for Rti {
         // Increment stack pointer cycle
         cpu.tick();
 
+        // Unlike CLI, SEI and PLP, the restored interrupt flag takes effect immediately
         let stat = cpu.pop_stack();
-        let pc = cpu.pop_stack16();
         cpu.registers.set_status_from_stack(stat);
+        let pc = cpu.pop_stack16();
         cpu.registers.pc = pc;
 
     }
@@ -2395,3 +2853,406 @@ impl OpCode for Tya {
         cpu.tick()
     }
 }
+
+// Unofficial opcodes
+//
+// These are the undocumented instructions that fall out of the NMOS 6502's decoding logic. Most
+// combine an official read-modify-write instruction with an ALU operation on the same operand.
+// See http://www.oxyron.de/html/opcodes02.html
+
+/// Magic constant used by the unstable XAA and LXA instructions. The real value varies between
+/// chips and with temperature; 0xee is what most NES CPUs exhibit.
+const UNSTABLE_MAGIC: u8 = 0xee;
+
+/// Unofficial NOPs that read their operand and discard it
+struct Ign;
+
+impl OpCode for Ign {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(_: &mut Cpu<S, I, A, M>, _: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+    }
+}
+
+struct Lax;
+
+impl OpCode for Lax {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let val = am.read();
+        cpu.registers.x = val;
+        cpu.registers.set_acc(val);
+    }
+}
+
+/// Immediate LAX, which mixes in the unstable magic constant like XAA
+struct Lxa;
+
+impl OpCode for Lxa {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let val = (cpu.registers.acc | UNSTABLE_MAGIC) & am.read();
+        cpu.registers.x = val;
+        cpu.registers.set_acc(val);
+    }
+}
+
+struct Sax;
+
+impl OpCode for Sax {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let val = cpu.registers.acc & cpu.registers.x;
+        am.write(cpu, val)
+    }
+}
+
+struct Dcp;
+
+impl OpCode for Dcp {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let val = wrapping_dec(am.read());
+        am.write(cpu, val);
+        let acc = cpu.registers.acc;
+        compare_base(cpu, acc, val)
+    }
+}
+
+struct Isc;
+
+impl OpCode for Isc {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let val = wrapping_inc(am.read());
+        am.write(cpu, val);
+        let acc = cpu.registers.acc;
+        adc_base(cpu, acc, !val)
+    }
+}
+
+struct Slo;
+
+impl OpCode for Slo {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let val = shift_left(cpu, am, false);
+        let res = cpu.registers.acc | val;
+        cpu.registers.set_acc(res);
+    }
+}
+
+struct Rla;
+
+impl OpCode for Rla {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let carry_set = cpu.registers.carry_flag();
+        let val = shift_left(cpu, am, carry_set);
+        let res = cpu.registers.acc & val;
+        cpu.registers.set_acc(res);
+    }
+}
+
+struct Sre;
+
+impl OpCode for Sre {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let val = shift_right(cpu, am, false);
+        let res = cpu.registers.acc ^ val;
+        cpu.registers.set_acc(res);
+    }
+}
+
+struct Rra;
+
+impl OpCode for Rra {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let carry_set = cpu.registers.carry_flag();
+        let val = shift_right(cpu, am, carry_set);
+        let acc = cpu.registers.acc;
+        adc_base(cpu, acc, val)
+    }
+}
+
+struct Anc;
+
+impl OpCode for Anc {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let res = cpu.registers.acc & am.read();
+        cpu.registers.set_acc(res);
+        cpu.registers.set_carry_flag(res & 0x80 != 0);
+    }
+}
+
+struct Alr;
+
+impl OpCode for Alr {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let val = cpu.registers.acc & am.read();
+        cpu.registers.set_carry_flag(val & 0x1 != 0);
+        cpu.registers.set_acc(val >> 1);
+    }
+}
+
+struct Arr;
+
+impl OpCode for Arr {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let val = cpu.registers.acc & am.read();
+        let carry = if cpu.registers.carry_flag() { 0x80 } else { 0 };
+        let res = (val >> 1) | carry;
+        cpu.registers.set_acc(res);
+
+        // Carry and overflow come from bits 6 and 5 of the result, as if an ADC had taken place
+        cpu.registers.set_carry_flag(res & 0x40 != 0);
+        cpu.registers.set_overflow_flag(((res >> 6) ^ (res >> 5)) & 0x1 != 0);
+    }
+}
+
+struct Axs;
+
+impl OpCode for Axs {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let lhs = cpu.registers.acc & cpu.registers.x;
+        let rhs = am.read();
+        compare_base(cpu, lhs, rhs);
+        cpu.registers.x = lhs.wrapping_sub(rhs);
+    }
+}
+
+struct Xaa;
+
+impl OpCode for Xaa {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let res = (cpu.registers.acc | UNSTABLE_MAGIC) & cpu.registers.x & am.read();
+        cpu.registers.set_acc(res);
+    }
+}
+
+struct Las;
+
+impl OpCode for Las {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let val = am.read() & cpu.registers.sp;
+        cpu.registers.sp = val;
+        cpu.registers.x = val;
+        cpu.registers.set_acc(val);
+    }
+}
+
+/// Shared behavior of the unstable SHA, SHX, SHY and TAS stores.
+///
+/// The stored value is ANDed with the high byte of the base address plus one. When indexing
+/// crosses a page, the high byte of the target address is replaced with the stored value.
+fn unstable_store<S, I, M, AM, A>(cpu: &mut Cpu<S, I, A, M>, am: AM, value: u8, index: u8)
+    where S: Screen,
+          I: Input,
+          A: Audio,
+          M: Memory<I, S, A>,
+          AM: AddressingMode<S, I, A, M, Output = u8>
+{
+    let target_addr = am.addr();
+    let base_addr = target_addr.wrapping_sub(index as u16);
+    let value = value & wrapping_inc((base_addr >> 8) as u8);
+
+    let addr = if base_addr & 0xff00 != target_addr & 0xff00 {
+        (value as u16) << 8 | target_addr & 0xff
+    } else {
+        target_addr
+    };
+
+    cpu.write_memory(addr, value)
+}
+
+struct Sha;
+
+impl OpCode for Sha {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let value = cpu.registers.acc & cpu.registers.x;
+        let index = cpu.registers.y;
+        unstable_store(cpu, am, value, index)
+    }
+}
+
+struct Shx;
+
+impl OpCode for Shx {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let value = cpu.registers.x;
+        let index = cpu.registers.y;
+        unstable_store(cpu, am, value, index)
+    }
+}
+
+struct Shy;
+
+impl OpCode for Shy {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let value = cpu.registers.y;
+        let index = cpu.registers.x;
+        unstable_store(cpu, am, value, index)
+    }
+}
+
+struct Tas;
+
+impl OpCode for Tas {
+    type Input = u8;
+
+    fn execute<S, I, A, M, AM>(cpu: &mut Cpu<S, I, A, M>, am: AM)
+        where S: Screen,
+              I: Input,
+              A: Audio,
+              M: Memory<I, S, A>,
+              AM: AddressingMode<S, I, A, M, Output = Self::Input>
+    {
+        let value = cpu.registers.acc & cpu.registers.x;
+        cpu.registers.sp = value;
+        let index = cpu.registers.y;
+        unstable_store(cpu, am, value, index)
+    }
+}
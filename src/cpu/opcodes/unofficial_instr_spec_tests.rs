@@ -0,0 +1,219 @@
+use cpu::*;
+use cpu::opcodes::*;
+use cpu::opcodes::am_test_utils::*;
+use memory::Memory;
+
+#[test]
+fn lax_loads_acc_and_x() {
+    let mut cpu = TestCpu::new_test();
+    Lax::execute(&mut cpu, 0x80_u8);
+    assert_eq!(0x80, cpu.registers.acc);
+    assert_eq!(0x80, cpu.registers.x);
+    assert_eq!(true, cpu.registers.sign_flag());
+    assert_eq!(false, cpu.registers.zero_flag());
+}
+
+#[test]
+fn sax_stores_acc_and_x() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.acc = 0xf0;
+    cpu.registers.x = 0x3c;
+    let am = WriterAddressingMode::new();
+    let write_ref = am.write_ref();
+    Sax::execute(&mut cpu, am);
+    assert_eq!(0x30, write_ref.get());
+}
+
+#[test]
+fn dcp_decrements_then_compares() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.acc = 0x10;
+    let am = WriterAddressingMode::with_read_value(0x11);
+    let write_ref = am.write_ref();
+    Dcp::execute(&mut cpu, am);
+    assert_eq!(0x10, write_ref.get());
+    assert_eq!(true, cpu.registers.zero_flag());
+    assert_eq!(true, cpu.registers.carry_flag());
+}
+
+#[test]
+fn isc_increments_then_subtracts() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.acc = 0x10;
+    cpu.registers.set_carry_flag(true);
+    let am = WriterAddressingMode::with_read_value(0x04);
+    let write_ref = am.write_ref();
+    Isc::execute(&mut cpu, am);
+    assert_eq!(0x05, write_ref.get());
+    assert_eq!(0x0b, cpu.registers.acc);
+    assert_eq!(true, cpu.registers.carry_flag());
+}
+
+#[test]
+fn slo_shifts_left_then_ors() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.acc = 0x01;
+    let am = WriterAddressingMode::with_read_value(0x81);
+    let write_ref = am.write_ref();
+    Slo::execute(&mut cpu, am);
+    assert_eq!(0x02, write_ref.get());
+    assert_eq!(0x03, cpu.registers.acc);
+    assert_eq!(true, cpu.registers.carry_flag());
+}
+
+#[test]
+fn rla_rotates_left_then_ands() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.acc = 0x0f;
+    cpu.registers.set_carry_flag(true);
+    let am = WriterAddressingMode::with_read_value(0x04);
+    let write_ref = am.write_ref();
+    Rla::execute(&mut cpu, am);
+    assert_eq!(0x09, write_ref.get());
+    assert_eq!(0x09, cpu.registers.acc);
+    assert_eq!(false, cpu.registers.carry_flag());
+}
+
+#[test]
+fn sre_shifts_right_then_eors() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.acc = 0xff;
+    let am = WriterAddressingMode::with_read_value(0x03);
+    let write_ref = am.write_ref();
+    Sre::execute(&mut cpu, am);
+    assert_eq!(0x01, write_ref.get());
+    assert_eq!(0xfe, cpu.registers.acc);
+    assert_eq!(true, cpu.registers.carry_flag());
+    assert_eq!(true, cpu.registers.sign_flag());
+}
+
+#[test]
+fn rra_rotates_right_then_adds_with_carry() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.acc = 0x10;
+    let am = WriterAddressingMode::with_read_value(0x03);
+    let write_ref = am.write_ref();
+    Rra::execute(&mut cpu, am);
+    assert_eq!(0x01, write_ref.get());
+
+    // The carry shifted out of the operand feeds the addition
+    assert_eq!(0x12, cpu.registers.acc);
+    assert_eq!(false, cpu.registers.carry_flag());
+}
+
+#[test]
+fn anc_copies_sign_to_carry() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.acc = 0xff;
+    Anc::execute(&mut cpu, 0x80_u8);
+    assert_eq!(0x80, cpu.registers.acc);
+    assert_eq!(true, cpu.registers.carry_flag());
+    assert_eq!(true, cpu.registers.sign_flag());
+}
+
+#[test]
+fn alr_ands_then_shifts_right() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.acc = 0xff;
+    Alr::execute(&mut cpu, 0x03_u8);
+    assert_eq!(0x01, cpu.registers.acc);
+    assert_eq!(true, cpu.registers.carry_flag());
+}
+
+#[test]
+fn arr_sets_carry_and_overflow_from_result() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.acc = 0xff;
+    cpu.registers.set_carry_flag(true);
+    Arr::execute(&mut cpu, 0x80_u8);
+    assert_eq!(0xc0, cpu.registers.acc);
+    assert_eq!(true, cpu.registers.carry_flag());
+    assert_eq!(true, cpu.registers.overflow_flag());
+
+    cpu.registers.acc = 0xff;
+    cpu.registers.set_carry_flag(false);
+    Arr::execute(&mut cpu, 0x60_u8);
+    assert_eq!(0x30, cpu.registers.acc);
+    assert_eq!(false, cpu.registers.carry_flag());
+    assert_eq!(true, cpu.registers.overflow_flag());
+}
+
+#[test]
+fn axs_subtracts_from_acc_and_x() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.acc = 0x0f;
+    cpu.registers.x = 0xfc;
+    Axs::execute(&mut cpu, 0x0d_u8);
+    assert_eq!(0xff, cpu.registers.x);
+    assert_eq!(0x0f, cpu.registers.acc);
+    assert_eq!(false, cpu.registers.carry_flag());
+    assert_eq!(true, cpu.registers.sign_flag());
+}
+
+#[test]
+fn las_ands_with_stack_pointer() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.sp = 0xf3;
+    Las::execute(&mut cpu, 0x3f_u8);
+    assert_eq!(0x33, cpu.registers.sp);
+    assert_eq!(0x33, cpu.registers.x);
+    assert_eq!(0x33, cpu.registers.acc);
+}
+
+#[test]
+fn shx_ands_with_high_byte_plus_one() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.x = 0xff;
+    cpu.registers.y = 0x01;
+    cpu.memory.store_many(0x200, &[0x9e, 0x00, 0x04]);
+    cpu.step();
+    assert_eq!(0x05, cpu.memory.read(0x0401));
+}
+
+#[test]
+fn shy_page_cross_corrupts_target_address() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.x = 0x01;
+    cpu.registers.y = 0x03;
+    cpu.memory.store_many(0x200, &[0x9c, 0xff, 0x04]);
+    cpu.step();
+
+    // The value is 0x03 & 0x05, which also replaces the high byte of $0500
+    assert_eq!(0x01, cpu.memory.read(0x0100));
+    assert_eq!(0x00, cpu.memory.read(0x0500));
+}
+
+#[test]
+fn tas_sets_stack_pointer() {
+    let mut cpu = TestCpu::new_test();
+    cpu.registers.acc = 0xf0;
+    cpu.registers.x = 0x3f;
+    cpu.registers.y = 0x00;
+    cpu.memory.store_many(0x200, &[0x9b, 0x00, 0x7f]);
+    cpu.step();
+    assert_eq!(0x30, cpu.registers.sp);
+    assert_eq!(0x00, cpu.memory.read(0x7f00));
+
+    cpu.registers.acc = 0xff;
+    cpu.registers.x = 0xff;
+    cpu.memory.store_many(0x203, &[0x9b, 0x00, 0x02]);
+    cpu.step();
+    assert_eq!(0xff, cpu.registers.sp);
+    assert_eq!(0x03, cpu.memory.read(0x0200));
+}
+
+#[test]
+fn multi_byte_nops_skip_operands() {
+    let mut cpu = TestCpu::new_test();
+    cpu.memory.store_many(0x200, &[0x1a, 0x80, 0x00, 0x04, 0x00, 0x14, 0x00, 0x0c, 0x00, 0x00]);
+    cpu.step();
+    assert_eq!(0x201, cpu.registers.pc);
+    cpu.step();
+    assert_eq!(0x203, cpu.registers.pc);
+    cpu.step();
+    assert_eq!(0x205, cpu.registers.pc);
+    cpu.step();
+    assert_eq!(0x207, cpu.registers.pc);
+    cpu.step();
+    assert_eq!(0x20a, cpu.registers.pc);
+}
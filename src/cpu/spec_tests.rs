@@ -18,7 +18,8 @@ fn nmi() {
     let (addr_low, addr_high) = lo_hi(0xdead);
     cpu.memory.write(NMI_VECTOR, addr_low, 0);
     cpu.memory.write(NMI_VECTOR + 1, addr_high, 0);
-    cpu.nmi();
+    cpu.nmi_pending = true;
+    assert_eq!(Interrupt::Nmi, cpu.interrupt());
     assert_eq!(cpu.registers.pc, 0xdead);
 }
 
@@ -67,3 +68,194 @@ fn pop_stack16() {
     assert_eq!(0xff, cpu.registers.sp);
     assert_eq!(0xf00d, val);
 }
+
+#[test]
+fn jam_halts_until_reset() {
+    let mut cpu = TestCpu::new_test();
+    cpu.memory.store_many(0x200, &[0x02, 0xea]);
+    let jammed = StepResult::Jammed {
+        opcode: 0x02,
+        addr: 0x200,
+    };
+    assert_eq!(jammed, cpu.step());
+    assert_eq!(jammed, cpu.step());
+    assert_eq!(0x200, cpu.registers.pc);
+
+    // The rest of the system keeps being clocked while the CPU is halted
+    assert_eq!(2, cpu.cycles);
+
+    let (addr_low, addr_high) = lo_hi(0x201);
+    cpu.memory.write(RESET_VECTOR, addr_low, 0);
+    cpu.memory.write(RESET_VECTOR + 1, addr_high, 0);
+    cpu.reset();
+    assert_eq!(StepResult::Executed(Interrupt::None), cpu.step());
+    assert_eq!(0x202, cpu.registers.pc);
+}
+
+#[test]
+fn jam_as_nop() {
+    let mut cpu = TestCpu::new_test();
+    cpu.illegal_opcode_policy = IllegalOpcodePolicy::Nop;
+    cpu.memory.store_many(0x200, &[0x12]);
+    assert_eq!(StepResult::Executed(Interrupt::None), cpu.step());
+    assert_eq!(0x201, cpu.registers.pc);
+    assert_eq!(2, cpu.cycles);
+}
+
+#[test]
+fn jam_as_error() {
+    let mut cpu = TestCpu::new_test();
+    cpu.illegal_opcode_policy = IllegalOpcodePolicy::Error;
+    cpu.memory.store_many(0x200, &[0xf2]);
+    let error = StepResult::IllegalOpcode {
+        opcode: 0xf2,
+        addr: 0x200,
+    };
+    assert_eq!(error, cpu.step());
+    assert_eq!(error, cpu.step());
+    assert_eq!(0x200, cpu.registers.pc);
+}
+
+fn interrupt_fixture() -> TestCpu {
+    let mut cpu = TestCpu::new_test();
+    cpu.memory.store_many(NMI_VECTOR, &[0x00, 0x03, 0x00, 0x00, 0x00, 0x04]);
+    cpu.memory.store_many(0x300, &[0xea]);
+    cpu.memory.store_many(0x400, &[0xea]);
+    cpu
+}
+
+#[test]
+fn irq_is_level_triggered() {
+    let mut cpu = interrupt_fixture();
+    cpu.registers.set_interrupt_disable_flag(false);
+    cpu.memory.store_many(0x200, &[0xea]);
+    cpu.memory.set_irq_line(true);
+    assert_eq!(StepResult::Executed(Interrupt::Irq), cpu.step());
+    assert_eq!(0x400, cpu.registers.pc);
+    assert_eq!(2 + 7, cpu.cycles);
+
+    // The handler runs with interrupts disabled, even though the line is still held
+    assert_eq!(StepResult::Executed(Interrupt::None), cpu.step());
+    assert_eq!(0x401, cpu.registers.pc);
+}
+
+#[test]
+fn cli_delays_irq_by_one_instruction() {
+    let mut cpu = interrupt_fixture();
+    cpu.memory.store_many(0x200, &[0x58, 0xea]);
+    cpu.memory.set_irq_line(true);
+    assert_eq!(StepResult::Executed(Interrupt::None), cpu.step());
+    assert_eq!(StepResult::Executed(Interrupt::Irq), cpu.step());
+    assert_eq!(0x202, cpu.read_memory16(STACK_LOC + cpu.registers.sp as u16 + 2));
+}
+
+#[test]
+fn irq_is_taken_after_sei() {
+    let mut cpu = interrupt_fixture();
+    cpu.registers.set_interrupt_disable_flag(false);
+    cpu.memory.store_many(0x200, &[0x78]);
+    cpu.memory.set_irq_line(true);
+    assert_eq!(StepResult::Executed(Interrupt::Irq), cpu.step());
+    assert_eq!(0x400, cpu.registers.pc);
+}
+
+#[test]
+fn rti_restores_interrupt_disable_immediately() {
+    let mut cpu = interrupt_fixture();
+    cpu.memory.store_many(0x200, &[0x40]);
+    cpu.memory.store_many(0x210, &[0xea]);
+    cpu.push_stack16(0x210);
+    cpu.push_stack(0x00);
+    cpu.memory.set_irq_line(true);
+
+    // Unlike CLI and PLP, the IRQ is taken right after the RTI that clears the flag
+    assert_eq!(StepResult::Executed(Interrupt::Irq), cpu.step());
+    assert_eq!(0x400, cpu.registers.pc);
+    assert_eq!(0x210, cpu.read_memory16(STACK_LOC + cpu.registers.sp as u16 + 2));
+}
+
+#[test]
+fn taken_branch_delays_irq() {
+    let mut cpu = interrupt_fixture();
+    cpu.registers.set_interrupt_disable_flag(false);
+    cpu.memory.store_many(0x200, &[0xd0, 0x00, 0xea]);
+
+    // The IRQ arrives during the operand fetch, after the branch has polled for interrupts
+    cpu.memory.assert_irq_after(2);
+    assert_eq!(StepResult::Executed(Interrupt::None), cpu.step());
+    assert_eq!(0x202, cpu.registers.pc);
+    assert_eq!(StepResult::Executed(Interrupt::Irq), cpu.step());
+    assert_eq!(0x203, cpu.read_memory16(STACK_LOC + cpu.registers.sp as u16 + 2));
+}
+
+#[test]
+fn taken_branch_delays_nmi() {
+    let mut cpu = interrupt_fixture();
+    cpu.memory.store_many(0x200, &[0xd0, 0x00, 0xea]);
+    cpu.memory.trigger_nmi_after(2);
+    assert_eq!(StepResult::Executed(Interrupt::None), cpu.step());
+    assert_eq!(StepResult::Executed(Interrupt::Nmi), cpu.step());
+    assert_eq!(0x203, cpu.read_memory16(STACK_LOC + cpu.registers.sp as u16 + 2));
+}
+
+#[test]
+fn page_crossing_branch_doesnt_delay_irq() {
+    let mut cpu = interrupt_fixture();
+    cpu.registers.set_interrupt_disable_flag(false);
+    cpu.registers.pc = 0x2fc;
+    cpu.memory.store_many(0x2fc, &[0xd0, 0x10]);
+
+    // The branch polls again before its page fixup cycle, and sees the IRQ
+    cpu.memory.assert_irq_after(2);
+    assert_eq!(StepResult::Executed(Interrupt::Irq), cpu.step());
+    assert_eq!(0x30e, cpu.read_memory16(STACK_LOC + cpu.registers.sp as u16 + 2));
+}
+
+#[test]
+fn nmi_is_edge_triggered() {
+    let mut cpu = interrupt_fixture();
+    cpu.memory.store_many(0x200, &[0xea, 0xea]);
+    cpu.nmi_pending = true;
+    assert_eq!(StepResult::Executed(Interrupt::Nmi), cpu.step());
+    assert_eq!(0x300, cpu.registers.pc);
+    assert_eq!(StepResult::Executed(Interrupt::None), cpu.step());
+}
+
+#[test]
+fn nmi_hijacks_brk() {
+    let mut cpu = interrupt_fixture();
+    cpu.memory.store_many(0x200, &[0x00, 0x00]);
+
+    // The NMI arrives while the return address is being pushed
+    cpu.memory.trigger_nmi_after(3);
+
+    // The BRK completes, but jumps to the NMI handler, which doesn't run a second time
+    assert_eq!(StepResult::Executed(Interrupt::None), cpu.step());
+    assert_eq!(0x300, cpu.registers.pc);
+    assert_eq!(7, cpu.cycles);
+
+    // The break flag is still pushed
+    let status = cpu.read_memory(STACK_LOC + cpu.registers.sp as u16 + 1);
+    assert!(status & 0b_0001_0000 != 0);
+    assert_eq!(StepResult::Executed(Interrupt::None), cpu.step());
+}
+
+#[test]
+fn nmi_pending_before_brk_runs_after_it() {
+    let mut cpu = interrupt_fixture();
+    cpu.memory.store_many(0x200, &[0x00, 0x00]);
+    cpu.nmi_pending = true;
+
+    // The BRK goes to its own handler, then the NMI is serviced before the handler runs
+    assert_eq!(StepResult::Executed(Interrupt::Nmi), cpu.step());
+    assert_eq!(0x300, cpu.registers.pc);
+    assert_eq!(7 + 7, cpu.cycles);
+
+    // The BRK's return address and status are below the NMI's on the stack
+    let brk_status = cpu.read_memory(STACK_LOC + cpu.registers.sp as u16 + 4);
+    assert!(brk_status & 0b_0001_0000 != 0);
+    let brk_pc_high = cpu.read_memory(STACK_LOC + cpu.registers.sp as u16 + 6);
+    let nmi_pc_high = cpu.read_memory(STACK_LOC + cpu.registers.sp as u16 + 3);
+    assert_eq!(0x02, brk_pc_high);
+    assert_eq!(0x04, nmi_pc_high);
+}
@@ -5,8 +5,13 @@ extern crate log;
 #[macro_use]
 extern crate lazy_static;
 
+#[cfg(feature = "sdl2")]
 extern crate sdl2;
 
+extern crate crc;
+
+extern crate sha1;
+
 #[cfg(feature = "debugger")]
 extern crate env_logger;
 
@@ -51,12 +56,16 @@ extern crate asm6502;
 extern crate rand;
 
 pub mod rom;
+pub mod mapper;
 pub mod memory;
 pub mod cpu;
 pub mod ppu;
 pub mod screen;
 pub mod input;
 pub mod audio;
+#[cfg(feature = "sdl2")]
 pub mod audio_out;
 pub mod apu;
+pub mod cheat;
+pub mod nsf;
 mod byte_utils;
@@ -0,0 +1,63 @@
+//! AxROM (mapper 7). A switchable 32KB PRG bank and single-screen mirroring, with the nametable
+//! page selected by bit 4 of the bank register.
+//!
+//! ANROM and AMROM boards have bus conflicts but the more common AOROM does not, and some AOROM
+//! games write values that would conflict, so bus conflicts are not emulated.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::{Chr, Mapper};
+use rom::{Mirroring, NesRom};
+
+const PRG_BANK_SIZE: usize = 0x8000;
+
+pub struct Axrom {
+    prg: Vec<u8>,
+    chr: Chr,
+    bank: u8,
+}
+
+impl Axrom {
+    pub fn new(rom: NesRom) -> Self {
+        Axrom {
+            prg: rom.prg,
+            chr: Chr::new(rom.chr, rom.chr_ram_banks),
+            bank: 0,
+        }
+    }
+}
+
+impl Mapper for Axrom {
+    fn prg_read(&self, addr: u16) -> u8 {
+        if addr < 0x8000 {
+            return 0;
+        }
+
+        let bank_count = self.prg.len() / PRG_BANK_SIZE;
+        let bank = (self.bank as usize & 0b111) % bank_count;
+        self.prg[bank * PRG_BANK_SIZE + (addr as usize & 0x7fff)]
+    }
+
+    fn prg_write(&mut self, addr: u16, val: u8) {
+        if addr >= 0x8000 {
+            self.bank = val;
+        }
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr.read(addr as usize)
+    }
+
+    fn chr_write(&mut self, addr: u16, val: u8) {
+        self.chr.write(addr as usize, val)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        if self.bank & 0x10 == 0 {
+            Mirroring::SingleScreenLower
+        } else {
+            Mirroring::SingleScreenUpper
+        }
+    }
+}
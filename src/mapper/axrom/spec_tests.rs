@@ -0,0 +1,29 @@
+use super::*;
+use rom::NesRom;
+
+#[test]
+fn prg_bank_switch() {
+    let mut axrom = axrom_fixture();
+    assert_eq!(0, axrom.prg_read(0x8000));
+    axrom.prg_write(0x8000, 3);
+    assert_eq!(3, axrom.prg_read(0x8000));
+    assert_eq!(3, axrom.prg_read(0xffff));
+}
+
+#[test]
+fn single_screen_mirroring() {
+    let mut axrom = axrom_fixture();
+    assert_eq!(Mirroring::SingleScreenLower, axrom.mirroring());
+    axrom.prg_write(0x8000, 0x10);
+    assert_eq!(Mirroring::SingleScreenUpper, axrom.mirroring());
+    assert_eq!(0, axrom.prg_read(0x8000));
+}
+
+// 8 32KB PRG banks, each filled with its bank number
+fn axrom_fixture() -> Axrom {
+    let mut rom = NesRom::default();
+    rom.mapper = 7;
+    rom.prg = (0..0x40000).map(|i| (i / PRG_BANK_SIZE) as u8).collect();
+    rom.chr = vec![0; 0x2000];
+    Axrom::new(rom)
+}
@@ -0,0 +1,109 @@
+//! Mapper 34, which covers two unrelated boards:
+//!
+//! - BNROM: A switchable 32KB PRG bank selected by a latch over $8000-$FFFF, which is subject to
+//!   bus conflicts, and unbanked CHR.
+//! - NINA-001: A switchable 32KB PRG bank and two switchable 4KB CHR banks selected by registers
+//!   at $7FFD-$7FFF, which overlap 8KB of PRG-RAM at $6000-$7FFF.
+//!
+//! The boards are told apart by CHR size, since only NINA-001 has more than 8KB of CHR.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::{Chr, Mapper, prg_ram};
+use rom::{Mirroring, NesRom};
+
+const PRG_BANK_SIZE: usize = 0x8000;
+const NINA_CHR_BANK_SIZE: usize = 0x1000;
+
+pub struct Bnrom {
+    prg: Vec<u8>,
+    chr: Chr,
+    prg_ram: Vec<u8>,
+    mirroring: Mirroring,
+    is_nina_001: bool,
+    prg_bank: u8,
+    chr_banks: [u8; 2],
+}
+
+impl Bnrom {
+    pub fn new(rom: NesRom) -> Self {
+        let is_nina_001 = rom.chr.len() > 0x2000;
+        let mut prg_ram = prg_ram(&rom);
+        if is_nina_001 && prg_ram.is_empty() {
+            prg_ram = vec![0; 0x2000];
+        }
+
+        Bnrom {
+            prg_ram: prg_ram,
+            prg: rom.prg,
+            chr: Chr::new(rom.chr, rom.chr_ram_banks),
+            mirroring: rom.mirroring,
+            is_nina_001: is_nina_001,
+            prg_bank: 0,
+            chr_banks: [0, 1],
+        }
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        if self.is_nina_001 {
+            let bank = self.chr_banks[addr as usize >> 12] as usize;
+            (bank * NINA_CHR_BANK_SIZE + (addr as usize & 0x0fff)) % self.chr.len()
+        } else {
+            addr as usize
+        }
+    }
+}
+
+impl Mapper for Bnrom {
+    fn prg_read(&self, addr: u16) -> u8 {
+        if addr >= 0x8000 {
+            let bank_count = self.prg.len() / PRG_BANK_SIZE;
+            let bank = self.prg_bank as usize % bank_count;
+            self.prg[bank * PRG_BANK_SIZE + (addr as usize & 0x7fff)]
+        } else if addr >= 0x6000 && !self.prg_ram.is_empty() {
+            self.prg_ram[addr as usize & 0x1fff]
+        } else {
+            0
+        }
+    }
+
+    fn prg_write(&mut self, addr: u16, val: u8) {
+        if addr >= 0x8000 {
+            if !self.is_nina_001 {
+                self.prg_bank = val & self.prg_read(addr);
+            }
+        } else if addr >= 0x6000 && !self.prg_ram.is_empty() {
+            self.prg_ram[addr as usize & 0x1fff] = val;
+            if self.is_nina_001 {
+                match addr {
+                    0x7ffd => self.prg_bank = val & 1,
+                    0x7ffe => self.chr_banks[0] = val & 0x0f,
+                    0x7fff => self.chr_banks[1] = val & 0x0f,
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr.read(self.chr_offset(addr))
+    }
+
+    fn chr_write(&mut self, addr: u16, val: u8) {
+        let offset = self.chr_offset(addr);
+        self.chr.write(offset, val)
+    }
+
+    fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram
+    }
+
+    fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.prg_ram
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
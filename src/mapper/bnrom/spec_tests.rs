@@ -0,0 +1,56 @@
+use super::*;
+use rom::NesRom;
+
+#[test]
+fn bnrom_bank_switch() {
+    let mut bnrom = bnrom_fixture(0x2000);
+    assert_eq!(0, bnrom.prg_read(0x8000));
+    bnrom.prg_write(0xffff, 3);
+    assert_eq!(3, bnrom.prg_read(0x8000));
+}
+
+#[test]
+fn bnrom_bus_conflicts() {
+    let mut bnrom = bnrom_fixture(0x2000);
+    bnrom.prg_write(0x8000, 3);
+    assert_eq!(0, bnrom.prg_read(0x8000));
+}
+
+#[test]
+fn nina_001_bank_switch() {
+    let mut nina = bnrom_fixture(0x10000);
+    assert_eq!(true, nina.is_nina_001);
+
+    nina.prg_write(0x7ffd, 1);
+    nina.prg_write(0x7ffe, 5);
+    nina.prg_write(0x7fff, 9);
+    assert_eq!(1, nina.prg_read(0x8000));
+    assert_eq!(5, nina.chr_read(0x0000));
+    assert_eq!(9, nina.chr_read(0x1000));
+
+    // Register writes also land in PRG-RAM
+    assert_eq!(9, nina.prg_read(0x7fff));
+}
+
+#[test]
+fn nina_001_ignores_rom_writes() {
+    let mut nina = bnrom_fixture(0x10000);
+    nina.prg_write(0xffff, 3);
+    assert_eq!(0, nina.prg_read(0x8000));
+}
+
+// 4 32KB PRG banks, each starting with its bank number and otherwise filled with $FF, and CHR
+// filled with its 4KB bank number
+fn bnrom_fixture(chr_size: usize) -> Bnrom {
+    let mut rom = NesRom::default();
+    rom.mapper = 34;
+    rom.prg = (0..0x20000)
+        .map(|i| if i % PRG_BANK_SIZE == 0 {
+                 (i / PRG_BANK_SIZE) as u8
+             } else {
+                 0xff
+             })
+        .collect();
+    rom.chr = (0..chr_size).map(|i| (i / NINA_CHR_BANK_SIZE) as u8).collect();
+    Bnrom::new(rom)
+}
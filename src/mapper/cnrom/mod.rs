@@ -0,0 +1,63 @@
+//! CNROM (mapper 3). Fixed PRG like NROM and a switchable 8KB CHR bank selected by a latch over
+//! $8000-$FFFF, which is subject to bus conflicts.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::{Chr, Mapper};
+use rom::{Mirroring, NesRom};
+
+const CHR_BANK_SIZE: usize = 0x2000;
+
+pub struct Cnrom {
+    prg: Vec<u8>,
+    chr: Chr,
+    mirroring: Mirroring,
+    chr_bank: u8,
+}
+
+impl Cnrom {
+    pub fn new(rom: NesRom) -> Self {
+        Cnrom {
+            prg: rom.prg,
+            chr: Chr::new(rom.chr, rom.chr_ram_banks),
+            mirroring: rom.mirroring,
+            chr_bank: 0,
+        }
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        let bank_count = self.chr.len() / CHR_BANK_SIZE;
+        let bank = self.chr_bank as usize % bank_count;
+        bank * CHR_BANK_SIZE + addr as usize
+    }
+}
+
+impl Mapper for Cnrom {
+    fn prg_read(&self, addr: u16) -> u8 {
+        if addr >= 0x8000 {
+            self.prg[(addr as usize - 0x8000) % self.prg.len()]
+        } else {
+            0
+        }
+    }
+
+    fn prg_write(&mut self, addr: u16, val: u8) {
+        if addr >= 0x8000 {
+            self.chr_bank = val & self.prg_read(addr);
+        }
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr.read(self.chr_offset(addr))
+    }
+
+    fn chr_write(&mut self, addr: u16, val: u8) {
+        let offset = self.chr_offset(addr);
+        self.chr.write(offset, val)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
@@ -0,0 +1,27 @@
+use super::*;
+use rom::NesRom;
+
+#[test]
+fn chr_bank_switch() {
+    let mut cnrom = cnrom_fixture(0xff);
+    assert_eq!(0, cnrom.chr_read(0x0000));
+    cnrom.prg_write(0x8000, 3);
+    assert_eq!(3, cnrom.chr_read(0x0000));
+    assert_eq!(3, cnrom.chr_read(0x1fff));
+}
+
+#[test]
+fn bus_conflicts() {
+    let mut cnrom = cnrom_fixture(0x01);
+    cnrom.prg_write(0x8000, 3);
+    assert_eq!(1, cnrom.chr_read(0x0000));
+}
+
+// 4 8KB CHR banks, each filled with its bank number
+fn cnrom_fixture(prg_fill: u8) -> Cnrom {
+    let mut rom = NesRom::default();
+    rom.mapper = 3;
+    rom.prg = vec![prg_fill; 0x8000];
+    rom.chr = (0..0x8000).map(|i| (i / CHR_BANK_SIZE) as u8).collect();
+    Cnrom::new(rom)
+}
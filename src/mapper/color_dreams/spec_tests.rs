@@ -0,0 +1,34 @@
+use super::*;
+use rom::NesRom;
+
+#[test]
+fn bank_switch() {
+    let mut color_dreams = color_dreams_fixture();
+    color_dreams.prg_write(0xffff, 0x52);
+    assert_eq!(2, color_dreams.prg_read(0x8000));
+    assert_eq!(5, color_dreams.chr_read(0x0000));
+}
+
+#[test]
+fn bus_conflicts() {
+    let mut color_dreams = color_dreams_fixture();
+    color_dreams.prg_write(0x8000, 0x52);
+    assert_eq!(0, color_dreams.prg_read(0x8000));
+    assert_eq!(0, color_dreams.chr_read(0x0000));
+}
+
+// 4 32KB PRG banks, each starting with its bank number and otherwise filled with $FF, and 16 8KB
+// CHR banks filled with their bank number
+fn color_dreams_fixture() -> ColorDreams {
+    let mut rom = NesRom::default();
+    rom.mapper = 11;
+    rom.prg = (0..0x20000)
+        .map(|i| if i % PRG_BANK_SIZE == 0 {
+                 (i / PRG_BANK_SIZE) as u8
+             } else {
+                 0xff
+             })
+        .collect();
+    rom.chr = (0..0x20000).map(|i| (i / CHR_BANK_SIZE) as u8).collect();
+    ColorDreams::new(rom)
+}
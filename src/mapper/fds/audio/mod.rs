@@ -0,0 +1,260 @@
+//! The FDS expansion sound channel: a single 64 step wavetable voice with a volume envelope and a
+//! second wavetable that modulates its pitch.
+
+#[cfg(test)]
+mod spec_tests;
+
+const WAVE_TABLE_SIZE: usize = 64;
+const MOD_TABLE_SIZE: usize = 64;
+const MAX_GAIN: u8 = 32;
+
+// The largest value the channel can produce, a full wavetable step at full gain
+const MAX_OUTPUT: f32 = (63 * MAX_GAIN as u32) as f32;
+
+// At full volume the channel is about 2.4 times as loud as a full volume pulse channel, which the
+// APU's mixer outputs as about 0.15
+const MIX_LEVEL: f32 = 0.36;
+
+// Output is scaled by 2/2, 2/3, 2/4 or 2/5 depending on $4089 bits 0-1
+const MASTER_VOLUME_TABLE: [f32; 4] = [1.0, 2.0 / 3.0, 2.0 / 4.0, 2.0 / 5.0];
+
+// Change applied to the modulation counter for each modulation table value. `None` resets it.
+const MOD_ADJUSTMENT_TABLE: [Option<i8>; 8] =
+    [Some(0), Some(1), Some(2), Some(4), None, Some(-4), Some(-2), Some(-1)];
+
+#[derive(Default)]
+struct Envelope {
+    // $4080/$4084: direct gain or speed (bits 0-5), increase (bit 6), disable (bit 7)
+    speed: u8,
+    increase: bool,
+    disabled: bool,
+    gain: u8,
+    timer: u32,
+}
+
+impl Envelope {
+    fn write(&mut self, val: u8) {
+        self.speed = val & 0x3f;
+        self.increase = val & 0x40 != 0;
+        self.disabled = val & 0x80 != 0;
+        self.timer = 0;
+        if self.disabled {
+            self.gain = self.speed;
+        }
+    }
+
+    fn clock(&mut self, master_speed: u8) {
+        if self.disabled {
+            return;
+        }
+
+        self.timer += 1;
+        if self.timer >= 8 * (self.speed as u32 + 1) * master_speed as u32 {
+            self.timer = 0;
+            if self.increase && self.gain < MAX_GAIN {
+                self.gain += 1;
+            } else if !self.increase && self.gain > 0 {
+                self.gain -= 1;
+            }
+        }
+    }
+}
+
+pub struct FdsAudio {
+    wave_table: [u8; WAVE_TABLE_SIZE],
+    mod_table: [u8; MOD_TABLE_SIZE],
+    volume_envelope: Envelope,
+    mod_envelope: Envelope,
+
+    // $4082/$4083
+    frequency: u16,
+    wave_halted: bool,
+    envelopes_halted: bool,
+    wave_accumulator: u32,
+
+    // $4085-$4087
+    mod_counter: i8,
+    mod_frequency: u16,
+    mod_halted: bool,
+    mod_accumulator: u32,
+    mod_position: usize,
+
+    // $4089/$408A
+    wave_write_enabled: bool,
+    master_volume: usize,
+    envelope_speed: u8,
+
+    output: u8,
+}
+
+impl Default for FdsAudio {
+    fn default() -> Self {
+        FdsAudio {
+            wave_table: [0; WAVE_TABLE_SIZE],
+            mod_table: [0; MOD_TABLE_SIZE],
+            volume_envelope: Envelope::default(),
+            mod_envelope: Envelope::default(),
+            frequency: 0,
+            wave_halted: true,
+            envelopes_halted: true,
+            wave_accumulator: 0,
+            mod_counter: 0,
+            mod_frequency: 0,
+            mod_halted: true,
+            mod_accumulator: 0,
+            mod_position: 0,
+            wave_write_enabled: false,
+            master_volume: 0,
+            envelope_speed: 0xe8,
+            output: 0,
+        }
+    }
+}
+
+impl FdsAudio {
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0x4040...0x407f => self.wave_table[addr as usize & 0x3f] | 0x40,
+            0x4090 => self.volume_envelope.gain | 0x40,
+            0x4092 => self.mod_envelope.gain | 0x40,
+            _ => 0,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x4040...0x407f => {
+                if self.wave_write_enabled {
+                    self.wave_table[addr as usize & 0x3f] = val & 0x3f;
+                }
+            }
+            0x4080 => self.volume_envelope.write(val),
+            0x4082 => self.frequency = (self.frequency & 0x0f00) | val as u16,
+            0x4083 => {
+                self.frequency = (self.frequency & 0x00ff) | ((val as u16 & 0x0f) << 8);
+                self.wave_halted = val & 0x80 != 0;
+                self.envelopes_halted = val & 0x40 != 0;
+                if self.wave_halted {
+                    self.wave_accumulator = 0;
+                }
+                if self.envelopes_halted {
+                    self.volume_envelope.timer = 0;
+                    self.mod_envelope.timer = 0;
+                }
+            }
+            0x4084 => self.mod_envelope.write(val),
+            0x4085 => self.mod_counter = sign_extend_7_bit(val),
+            0x4086 => self.mod_frequency = (self.mod_frequency & 0x0f00) | val as u16,
+            0x4087 => {
+                self.mod_frequency = (self.mod_frequency & 0x00ff) | ((val as u16 & 0x0f) << 8);
+                self.mod_halted = val & 0x80 != 0;
+                if self.mod_halted {
+                    self.mod_accumulator = 0;
+                }
+            }
+            0x4088 => {
+                // Each write fills two consecutive entries, and is only possible while halted. The
+                // table can be halted at an odd position, so the second entry may wrap around.
+                if self.mod_halted {
+                    self.mod_table[self.mod_position] = val & 0x07;
+                    self.mod_table[(self.mod_position + 1) % MOD_TABLE_SIZE] = val & 0x07;
+                    self.mod_position = (self.mod_position + 2) % MOD_TABLE_SIZE;
+                }
+            }
+            0x4089 => {
+                self.wave_write_enabled = val & 0x80 != 0;
+                self.master_volume = val as usize & 0x03;
+            }
+            0x408a => self.envelope_speed = val,
+            _ => (),
+        }
+    }
+
+    /// Called once per CPU cycle
+    pub fn clock(&mut self) {
+        if !self.envelopes_halted && !self.wave_halted && self.envelope_speed != 0 {
+            self.volume_envelope.clock(self.envelope_speed);
+            self.mod_envelope.clock(self.envelope_speed);
+        }
+
+        if !self.mod_halted && self.mod_frequency != 0 {
+            self.mod_accumulator += self.mod_frequency as u32;
+            if self.mod_accumulator >= 0x10000 {
+                self.mod_accumulator &= 0xffff;
+                self.clock_mod_table();
+            }
+        }
+
+        if self.wave_write_enabled {
+            // The channel holds its last output while the wavetable is being written
+            return;
+        }
+
+        if !self.wave_halted {
+            let pitch = self.modulated_pitch();
+            if pitch > 0 {
+                self.wave_accumulator = (self.wave_accumulator + pitch as u32) & 0x3f_ffff;
+            }
+        }
+
+        let position = (self.wave_accumulator >> 16) as usize;
+        self.output = self.wave_table[position];
+    }
+
+    /// The current output level, on the same scale as the APU's mixer output
+    pub fn output(&self) -> f32 {
+        let gain = self.volume_envelope.gain.min(MAX_GAIN) as f32;
+        let master_volume = MASTER_VOLUME_TABLE[self.master_volume];
+        self.output as f32 * gain * master_volume / MAX_OUTPUT * MIX_LEVEL
+    }
+
+    fn clock_mod_table(&mut self) {
+        match MOD_ADJUSTMENT_TABLE[self.mod_table[self.mod_position] as usize] {
+            Some(adjustment) => {
+                let mod_counter = self.mod_counter.wrapping_add(adjustment);
+                self.mod_counter = sign_extend_7_bit(mod_counter as u8)
+            }
+            None => self.mod_counter = 0,
+        }
+        self.mod_position = (self.mod_position + 1) % MOD_TABLE_SIZE;
+    }
+
+    // The pitch after modulation is applied, which follows the hardware's integer arithmetic,
+    // rounding quirks included.
+    fn modulated_pitch(&self) -> i32 {
+        let pitch = self.frequency as i32;
+        if self.mod_halted {
+            return pitch;
+        }
+
+        let mut temp = self.mod_counter as i32 * self.mod_envelope.gain as i32;
+        let remainder = temp & 0x0f;
+        temp >>= 4;
+        if remainder > 0 && temp & 0x80 == 0 {
+            if self.mod_counter < 0 {
+                temp -= 1;
+            } else {
+                temp += 2;
+            }
+        }
+
+        if temp >= 192 {
+            temp -= 256;
+        } else if temp < -64 {
+            temp += 256;
+        }
+
+        temp *= pitch;
+        let remainder = temp & 0x3f;
+        temp >>= 6;
+        if remainder >= 32 {
+            temp += 1;
+        }
+
+        pitch + temp
+    }
+}
+
+fn sign_extend_7_bit(val: u8) -> i8 {
+    ((val << 1) as i8) >> 1
+}
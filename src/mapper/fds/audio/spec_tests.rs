@@ -0,0 +1,103 @@
+use super::*;
+
+#[test]
+fn wave_table_is_only_writable_when_enabled() {
+    let mut audio = FdsAudio::default();
+    audio.write(0x4040, 0x3f);
+    assert_eq!(0x40, audio.read(0x4040));
+
+    audio.write(0x4089, 0x80);
+    audio.write(0x4040, 0xff);
+    assert_eq!(0x7f, audio.read(0x4040));
+}
+
+#[test]
+fn direct_gain() {
+    let mut audio = FdsAudio::default();
+    audio.write(0x4080, 0x80 | 0x12);
+    assert_eq!(0x52, audio.read(0x4090));
+}
+
+#[test]
+fn volume_envelope_increases() {
+    let mut audio = FdsAudio::default();
+    audio.write(0x408a, 1);
+    audio.write(0x4080, 0x40);
+    audio.write(0x4083, 0x00);
+
+    // Speed 0 with a master speed of 1 clocks the envelope every 8 cycles
+    for _ in 0..16 {
+        audio.clock();
+    }
+    assert_eq!(0x42, audio.read(0x4090));
+}
+
+#[test]
+fn output_follows_wave_table_and_volume() {
+    let mut audio = FdsAudio::default();
+    audio.write(0x4089, 0x80);
+    for addr in 0x4040..0x4080 {
+        audio.write(addr, 63);
+    }
+    audio.write(0x4089, 0x00);
+    audio.write(0x4080, 0x80 | 32);
+    audio.write(0x4082, 0xff);
+    audio.write(0x4083, 0x0f);
+    audio.clock();
+    assert!((audio.output() - MIX_LEVEL).abs() < 0.000001);
+
+    audio.write(0x4089, 0x03);
+    audio.clock();
+    assert!((audio.output() - 0.4 * MIX_LEVEL).abs() < 0.000001);
+}
+
+#[test]
+fn silent_while_halted() {
+    let mut audio = FdsAudio::default();
+    audio.write(0x4080, 0x80 | 32);
+    audio.clock();
+    assert_eq!(0.0, audio.output());
+}
+
+#[test]
+fn mod_table_writes_fill_two_entries() {
+    let mut audio = FdsAudio::default();
+    audio.write(0x4087, 0x80);
+    audio.write(0x4088, 0x01);
+    audio.write(0x4088, 0x07);
+    assert_eq!(&[1, 1, 7, 7], &audio.mod_table[0..4]);
+}
+
+#[test]
+fn mod_table_write_wraps_around() {
+    let mut audio = FdsAudio::default();
+    for _ in 0..63 {
+        audio.clock_mod_table();
+    }
+    audio.write(0x4087, 0x80);
+    audio.write(0x4088, 0x03);
+    assert_eq!(3, audio.mod_table[63]);
+    assert_eq!(3, audio.mod_table[0]);
+    assert_eq!(1, audio.mod_position);
+}
+
+#[test]
+fn mod_counter_adjustments() {
+    let mut audio = FdsAudio::default();
+    audio.write(0x4085, 0x3f);
+    audio.mod_table[0] = 1;
+    audio.mod_table[1] = 4;
+    audio.clock_mod_table();
+    assert_eq!(-64, audio.mod_counter);
+    audio.clock_mod_table();
+    assert_eq!(0, audio.mod_counter);
+}
+
+#[test]
+fn unmodulated_pitch() {
+    let mut audio = FdsAudio::default();
+    audio.write(0x4082, 0x34);
+    audio.write(0x4083, 0x02);
+    audio.write(0x4087, 0x00);
+    assert_eq!(0x234, audio.modulated_pitch());
+}
@@ -0,0 +1,374 @@
+//! Famicom Disk System. The RAM adapter provides the `disksys.rom` BIOS at $E000-$FFFF, 32KB of RAM
+//! at $6000-$DFFF, 8KB of CHR-RAM, a timer IRQ, the disk drive interface and an extra sound
+//! channel.
+//!
+//! The drive is emulated a byte at a time from the raw side data built by `rom::fds`. CRCs are
+//! never checked, so the drive always reports a good CRC. Writes made by games go to the in-memory
+//! copy of the disk and are not saved back to the image.
+
+#[cfg(test)]
+mod spec_tests;
+
+pub mod audio;
+
+use self::audio::FdsAudio;
+use super::{Chr, Mapper};
+use rom::Mirroring;
+use rom::fds::FdsImage;
+use std::cell::Cell;
+
+pub const BIOS_SIZE: usize = 0x2000;
+const RAM_SIZE: usize = 0x8000;
+
+// CPU cycles from the start of the head's travel to the first byte, and between bytes
+const HEAD_START_DELAY: u32 = 50000;
+const BYTE_DELAY: u32 = 150;
+
+// Roughly a second, which is long enough for the BIOS to notice the disk has been ejected
+const DISK_SWAP_DELAY: u32 = 1_800_000;
+
+pub struct Fds {
+    bios: Vec<u8>,
+    ram: Vec<u8>,
+    chr: Chr,
+    sides: Vec<Vec<u8>>,
+    audio: FdsAudio,
+    mirroring: Mirroring,
+
+    // $4023
+    disk_registers_enabled: bool,
+    sound_registers_enabled: bool,
+
+    // $4020-$4022
+    irq_reload_value: u16,
+    irq_counter: u16,
+    irq_repeat: bool,
+    irq_enabled: bool,
+    timer_irq: Cell<bool>,
+
+    // $4025
+    motor_on: bool,
+    transfer_reset: bool,
+    read_mode: bool,
+    crc_control: bool,
+    disk_ready: bool,
+    disk_irq_enabled: bool,
+    disk_irq: Cell<bool>,
+
+    read_data: u8,
+    write_data: u8,
+    transfer_complete: Cell<bool>,
+
+    // Drive state
+    inserted_side: Option<usize>,
+    pending_side: Option<usize>,
+    swap_delay: u32,
+    position: usize,
+    delay: u32,
+    end_of_head: bool,
+    scanning: bool,
+    gap_ended: bool,
+}
+
+impl Fds {
+    pub fn new(bios: Vec<u8>, image: FdsImage) -> Self {
+        Fds {
+            bios: bios,
+            ram: vec![0; RAM_SIZE],
+            chr: Chr::new(Vec::new(), 1),
+            sides: image.sides,
+            audio: FdsAudio::default(),
+            mirroring: Mirroring::Vertical,
+            disk_registers_enabled: true,
+            sound_registers_enabled: true,
+            irq_reload_value: 0,
+            irq_counter: 0,
+            irq_repeat: false,
+            irq_enabled: false,
+            timer_irq: Cell::new(false),
+            motor_on: false,
+            transfer_reset: false,
+            read_mode: true,
+            crc_control: false,
+            disk_ready: false,
+            disk_irq_enabled: false,
+            disk_irq: Cell::new(false),
+            read_data: 0,
+            write_data: 0,
+            transfer_complete: Cell::new(false),
+            inserted_side: Some(0),
+            pending_side: None,
+            swap_delay: 0,
+            position: 0,
+            delay: 0,
+            end_of_head: true,
+            scanning: false,
+            gap_ended: false,
+        }
+    }
+
+    fn read_status(&self) -> u8 {
+        // -E-- --TI
+        // End of head (E), byte transferred (T), timer IRQ (I)
+        let mut status = 0;
+        if self.timer_irq.get() {
+            status |= 0x01;
+        }
+        if self.transfer_complete.get() {
+            status |= 0x02;
+        }
+        if self.end_of_head {
+            status |= 0x40;
+        }
+
+        self.transfer_complete.set(false);
+        self.timer_irq.set(false);
+        self.disk_irq.set(false);
+        status
+    }
+
+    fn read_drive_status(&self) -> u8 {
+        // ---- -WRS
+        // Write protected (W), not ready (R), disk not inserted (S)
+        let inserted = self.inserted_side.is_some();
+        let mut status = 0;
+        if !inserted {
+            status |= 0x05;
+        }
+        if !inserted || !self.scanning {
+            status |= 0x02;
+        }
+        status
+    }
+
+    fn write_control(&mut self, val: u8) {
+        // IS1C MRTD
+        // IRQ on transfer (I), ready (S), CRC control (C), mirroring (M), read mode (R),
+        // transfer reset (T), motor on (D)
+        self.motor_on = val & 0x01 != 0;
+        self.transfer_reset = val & 0x02 != 0;
+        self.read_mode = val & 0x04 != 0;
+        self.mirroring = if val & 0x08 != 0 {
+            Mirroring::Horizontal
+        } else {
+            Mirroring::Vertical
+        };
+        self.crc_control = val & 0x10 != 0;
+        self.disk_ready = val & 0x40 != 0;
+        self.disk_irq_enabled = val & 0x80 != 0;
+        self.disk_irq.set(false);
+    }
+
+    fn write_register(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x4020 => self.irq_reload_value = (self.irq_reload_value & 0xff00) | val as u16,
+            0x4021 => self.irq_reload_value = (self.irq_reload_value & 0x00ff) | (val as u16) << 8,
+            0x4022 => {
+                self.irq_repeat = val & 0x01 != 0;
+                self.irq_enabled = val & 0x02 != 0 && self.disk_registers_enabled;
+                if self.irq_enabled {
+                    self.irq_counter = self.irq_reload_value;
+                } else {
+                    self.timer_irq.set(false);
+                }
+            }
+            0x4023 => {
+                self.disk_registers_enabled = val & 0x01 != 0;
+                self.sound_registers_enabled = val & 0x02 != 0;
+                if !self.disk_registers_enabled {
+                    self.irq_enabled = false;
+                    self.timer_irq.set(false);
+                    self.disk_irq.set(false);
+                }
+            }
+            0x4024 => {
+                self.write_data = val;
+                self.transfer_complete.set(false);
+                self.disk_irq.set(false);
+            }
+            0x4025 => self.write_control(val),
+            _ => (),
+        }
+    }
+
+    fn clock_timer(&mut self) {
+        if !self.irq_enabled {
+            return;
+        }
+
+        if self.irq_counter == 0 {
+            self.timer_irq.set(true);
+            self.irq_counter = self.irq_reload_value;
+            if !self.irq_repeat {
+                self.irq_enabled = false;
+            }
+        } else {
+            self.irq_counter -= 1;
+        }
+    }
+
+    fn clock_drive(&mut self) {
+        if self.swap_delay > 0 {
+            self.swap_delay -= 1;
+            if self.swap_delay == 0 {
+                self.inserted_side = self.pending_side.take();
+            }
+        }
+
+        let side = match self.inserted_side {
+            Some(side) if self.motor_on => side,
+            _ => {
+                self.end_of_head = true;
+                self.scanning = false;
+                return;
+            }
+        };
+
+        if self.transfer_reset && !self.scanning {
+            return;
+        }
+
+        if self.end_of_head {
+            self.delay = HEAD_START_DELAY;
+            self.end_of_head = false;
+            self.position = 0;
+            self.gap_ended = false;
+            return;
+        }
+
+        if self.delay > 0 {
+            self.delay -= 1;
+            return;
+        }
+
+        self.scanning = true;
+        if self.read_mode {
+            let data = self.sides[side][self.position];
+            let mut need_irq = self.disk_irq_enabled;
+            if !self.disk_ready {
+                self.gap_ended = false;
+            } else if data != 0 && !self.gap_ended {
+                // The block start mark ends the gap, but isn't itself transferred
+                self.gap_ended = true;
+                need_irq = false;
+            }
+
+            if self.gap_ended {
+                self.transfer_complete.set(true);
+                self.read_data = data;
+                if need_irq {
+                    self.disk_irq.set(true);
+                }
+            }
+        } else {
+            let mut data = 0;
+            if !self.crc_control {
+                self.transfer_complete.set(true);
+                data = self.write_data;
+                if self.disk_irq_enabled {
+                    self.disk_irq.set(true);
+                }
+            }
+            if !self.disk_ready {
+                data = 0;
+            }
+            self.sides[side][self.position] = data;
+            self.gap_ended = false;
+        }
+
+        self.position += 1;
+        if self.position >= self.sides[side].len() {
+            self.motor_on = false;
+        } else {
+            self.delay = BYTE_DELAY;
+        }
+    }
+}
+
+impl Mapper for Fds {
+    fn prg_read(&self, addr: u16) -> u8 {
+        if addr >= 0xe000 {
+            self.bios[addr as usize & 0x1fff]
+        } else if addr >= 0x6000 {
+            self.ram[addr as usize - 0x6000]
+        } else if addr >= 0x4040 && addr < 0x4098 {
+            if self.sound_registers_enabled {
+                self.audio.read(addr)
+            } else {
+                0
+            }
+        } else if !self.disk_registers_enabled {
+            0
+        } else {
+            match addr {
+                0x4030 => self.read_status(),
+                0x4031 => {
+                    self.transfer_complete.set(false);
+                    self.disk_irq.set(false);
+                    self.read_data
+                }
+                0x4032 => self.read_drive_status(),
+                // Battery is good
+                0x4033 => 0x80,
+                _ => 0,
+            }
+        }
+    }
+
+    fn prg_write(&mut self, addr: u16, val: u8) {
+        if addr >= 0xe000 {
+            return;
+        }
+
+        if addr >= 0x6000 {
+            self.ram[addr as usize - 0x6000] = val;
+        } else if addr >= 0x4040 && addr < 0x4098 {
+            if self.sound_registers_enabled {
+                self.audio.write(addr, val);
+            }
+        } else if addr == 0x4023 || self.disk_registers_enabled {
+            self.write_register(addr, val);
+        }
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr.read(addr as usize)
+    }
+
+    fn chr_write(&mut self, addr: u16, val: u8) {
+        self.chr.write(addr as usize, val)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.timer_irq.get() || self.disk_irq.get()
+    }
+
+    fn tick(&mut self) {
+        self.clock_timer();
+        self.clock_drive();
+        self.audio.clock();
+    }
+
+    fn expansion_audio(&self) -> f32 {
+        self.audio.output()
+    }
+
+    fn disk_sides(&self) -> usize {
+        self.sides.len()
+    }
+
+    fn insert_disk(&mut self, side: Option<usize>) {
+        // Eject whatever is in the drive first, so the BIOS sees the disk change
+        self.inserted_side = None;
+        self.pending_side = side.filter(|side| *side < self.sides.len());
+        self.swap_delay = if self.pending_side.is_some() {
+            DISK_SWAP_DELAY
+        } else {
+            0
+        };
+    }
+}
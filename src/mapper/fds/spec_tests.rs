@@ -0,0 +1,148 @@
+use super::*;
+
+#[test]
+fn bios_and_ram() {
+    let mut fds = fds_fixture();
+    assert_eq!(0xe0, fds.prg_read(0xe000));
+    assert_eq!(0xff, fds.prg_read(0xffff));
+    fds.prg_write(0xe000, 0x00);
+    assert_eq!(0xe0, fds.prg_read(0xe000));
+
+    fds.prg_write(0x6000, 0x11);
+    fds.prg_write(0xdfff, 0x22);
+    assert_eq!(0x11, fds.prg_read(0x6000));
+    assert_eq!(0x22, fds.prg_read(0xdfff));
+}
+
+#[test]
+fn chr_ram() {
+    let mut fds = fds_fixture();
+    fds.chr_write(0x1fff, 0x33);
+    assert_eq!(0x33, fds.chr_read(0x1fff));
+}
+
+#[test]
+fn mirroring() {
+    let mut fds = fds_fixture();
+    fds.prg_write(0x4025, 0x08);
+    assert_eq!(Mirroring::Horizontal, fds.mirroring());
+    fds.prg_write(0x4025, 0x00);
+    assert_eq!(Mirroring::Vertical, fds.mirroring());
+}
+
+#[test]
+fn timer_irq() {
+    let mut fds = fds_fixture();
+    fds.prg_write(0x4020, 0x02);
+    fds.prg_write(0x4021, 0x00);
+    fds.prg_write(0x4022, 0x02);
+
+    fds.tick();
+    fds.tick();
+    assert_eq!(false, fds.irq_pending());
+    fds.tick();
+    assert_eq!(true, fds.irq_pending());
+    assert_eq!(0x01, fds.prg_read(0x4030) & 0x01);
+    assert_eq!(false, fds.irq_pending());
+
+    // Without repeat, the timer only fires once
+    for _ in 0..10 {
+        fds.tick();
+    }
+    assert_eq!(false, fds.irq_pending());
+}
+
+#[test]
+fn timer_irq_repeats() {
+    let mut fds = fds_fixture();
+    fds.prg_write(0x4020, 0x01);
+    fds.prg_write(0x4022, 0x03);
+    fds.tick();
+    fds.tick();
+    assert_eq!(true, fds.irq_pending());
+    fds.prg_read(0x4030);
+    assert_eq!(false, fds.irq_pending());
+    fds.tick();
+    fds.tick();
+    assert_eq!(true, fds.irq_pending());
+}
+
+#[test]
+fn disabling_disk_registers_stops_timer() {
+    let mut fds = fds_fixture();
+    fds.prg_write(0x4022, 0x02);
+    fds.prg_write(0x4023, 0x00);
+    fds.tick();
+    assert_eq!(false, fds.irq_pending());
+
+    // Registers other than $4023 are ignored while disabled
+    fds.prg_write(0x4022, 0x02);
+    fds.tick();
+    assert_eq!(false, fds.irq_pending());
+}
+
+#[test]
+fn sound_registers() {
+    let mut fds = fds_fixture();
+    fds.prg_write(0x4080, 0x80 | 0x10);
+    assert_eq!(0x50, fds.prg_read(0x4090));
+
+    fds.prg_write(0x4023, 0x01);
+    assert_eq!(0x00, fds.prg_read(0x4090));
+}
+
+#[test]
+fn drive_status() {
+    let mut fds = fds_fixture();
+    assert_eq!(0x80, fds.prg_read(0x4033));
+    assert_eq!(0x02, fds.prg_read(0x4032));
+
+    fds.insert_disk(None);
+    assert_eq!(0x07, fds.prg_read(0x4032));
+}
+
+#[test]
+fn reads_disk() {
+    let mut fds = fds_fixture();
+    fds.prg_write(0x4025, 0x80 | 0x40 | 0x20 | 0x04 | 0x01);
+
+    // The gap ends at the block start mark, and the first byte after it is the block type
+    let mut bytes = Vec::new();
+    for _ in 0..(HEAD_START_DELAY + 20 * (BYTE_DELAY + 1)) {
+        fds.tick();
+        if fds.irq_pending() {
+            bytes.push(fds.prg_read(0x4031));
+        }
+    }
+    assert_eq!(&[0x01, b'*', b'N', b'I'], &bytes[0..4]);
+    assert_eq!(0x00, fds.prg_read(0x4032) & 0x02);
+}
+
+#[test]
+fn disk_swap() {
+    let mut fds = fds_fixture();
+    assert_eq!(2, fds.disk_sides());
+    fds.insert_disk(Some(1));
+    assert_eq!(None, fds.inserted_side);
+    for _ in 0..DISK_SWAP_DELAY {
+        fds.tick();
+    }
+    assert_eq!(Some(1), fds.inserted_side);
+
+    fds.insert_disk(Some(2));
+    assert_eq!(None, fds.pending_side);
+}
+
+fn fds_fixture() -> Fds {
+    let mut bios = vec![0; BIOS_SIZE];
+    bios[0] = 0xe0;
+    bios[BIOS_SIZE - 1] = 0xff;
+
+    let mut side = vec![0; 10];
+    side.push(0x80);
+    side.push(0x01);
+    side.extend_from_slice(b"*NINTENDO-HVC*");
+    side.resize(1000, 0);
+    let image = FdsImage { sides: vec![side.clone(), side] };
+    Fds::new(bios, image)
+}
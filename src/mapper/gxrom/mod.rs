@@ -0,0 +1,68 @@
+//! GxROM (mapper 66). A switchable 32KB PRG bank and 8KB CHR bank selected by a single latch over
+//! $8000-$FFFF, which is subject to bus conflicts.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::{Chr, Mapper};
+use rom::{Mirroring, NesRom};
+
+const PRG_BANK_SIZE: usize = 0x8000;
+const CHR_BANK_SIZE: usize = 0x2000;
+
+pub struct Gxrom {
+    prg: Vec<u8>,
+    chr: Chr,
+    mirroring: Mirroring,
+    bank: u8,
+}
+
+impl Gxrom {
+    pub fn new(rom: NesRom) -> Self {
+        Gxrom {
+            prg: rom.prg,
+            chr: Chr::new(rom.chr, rom.chr_ram_banks),
+            mirroring: rom.mirroring,
+            bank: 0,
+        }
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        // ---- --CC
+        let bank_count = self.chr.len() / CHR_BANK_SIZE;
+        let bank = (self.bank as usize & 0b11) % bank_count;
+        bank * CHR_BANK_SIZE + addr as usize
+    }
+}
+
+impl Mapper for Gxrom {
+    fn prg_read(&self, addr: u16) -> u8 {
+        if addr < 0x8000 {
+            return 0;
+        }
+
+        // --PP ----
+        let bank_count = self.prg.len() / PRG_BANK_SIZE;
+        let bank = ((self.bank as usize >> 4) & 0b11) % bank_count;
+        self.prg[bank * PRG_BANK_SIZE + (addr as usize & 0x7fff)]
+    }
+
+    fn prg_write(&mut self, addr: u16, val: u8) {
+        if addr >= 0x8000 {
+            self.bank = val & self.prg_read(addr);
+        }
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr.read(self.chr_offset(addr))
+    }
+
+    fn chr_write(&mut self, addr: u16, val: u8) {
+        let offset = self.chr_offset(addr);
+        self.chr.write(offset, val)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
@@ -0,0 +1,38 @@
+use super::*;
+use rom::NesRom;
+
+#[test]
+fn bank_switch() {
+    let mut gxrom = gxrom_fixture();
+    assert_eq!(0, gxrom.prg_read(0x8000));
+    assert_eq!(0, gxrom.chr_read(0x0000));
+
+    gxrom.prg_write(0xffff, 0x12);
+    assert_eq!(1, gxrom.prg_read(0x8000));
+    assert_eq!(2, gxrom.chr_read(0x0000));
+}
+
+#[test]
+fn bus_conflicts() {
+    let mut gxrom = gxrom_fixture();
+    // The ROM drives $00 at $8000 in bank 0
+    gxrom.prg_write(0x8000, 0x12);
+    assert_eq!(0, gxrom.prg_read(0x8000));
+    assert_eq!(0, gxrom.chr_read(0x0000));
+}
+
+// 4 32KB PRG banks, each starting with its bank number and otherwise filled with $FF, and 4 8KB
+// CHR banks filled with their bank number
+fn gxrom_fixture() -> Gxrom {
+    let mut rom = NesRom::default();
+    rom.mapper = 66;
+    rom.prg = (0..0x20000)
+        .map(|i| if i % PRG_BANK_SIZE == 0 {
+                 (i / PRG_BANK_SIZE) as u8
+             } else {
+                 0xff
+             })
+        .collect();
+    rom.chr = (0..0x8000).map(|i| (i / CHR_BANK_SIZE) as u8).collect();
+    Gxrom::new(rom)
+}
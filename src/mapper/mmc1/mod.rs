@@ -0,0 +1,170 @@
+//! MMC1 (mapper 1). Registers are loaded serially, one bit per write, through a 5-bit shift
+//! register. The fifth write copies the shift register into the internal register selected by bits
+//! 13 and 14 of the address.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::{Chr, Mapper, prg_ram};
+use rom::{Mirroring, NesRom};
+
+const PRG_BANK_SIZE: usize = 0x4000;
+const CHR_BANK_SIZE: usize = 0x1000;
+
+pub struct Mmc1 {
+    prg: Vec<u8>,
+    chr: Chr,
+    prg_ram: Vec<u8>,
+    shift_register: u8,
+    shift_count: u8,
+    control: u8,
+    chr_bank_0: u8,
+    chr_bank_1: u8,
+    prg_bank: u8,
+    // Whether the shift register was written on the current and previous CPU cycles
+    written_this_cycle: bool,
+    written_last_cycle: bool,
+}
+
+impl Mmc1 {
+    pub fn new(rom: NesRom) -> Self {
+        // MMC1 boards always have an 8KB PRG-RAM window, even if the header says otherwise
+        let mut prg_ram = prg_ram(&rom);
+        if prg_ram.is_empty() {
+            prg_ram = vec![0; 0x2000];
+        }
+
+        Mmc1 {
+            prg: rom.prg,
+            chr: Chr::new(rom.chr, rom.chr_ram_banks),
+            prg_ram: prg_ram,
+            shift_register: 0,
+            shift_count: 0,
+            // Power-on state is PRG mode 3, with the last bank fixed at $C000
+            control: 0x0c,
+            chr_bank_0: 0,
+            chr_bank_1: 0,
+            prg_bank: 0,
+            written_this_cycle: false,
+            written_last_cycle: false,
+        }
+    }
+
+    fn write_shift_register(&mut self, addr: u16, val: u8) {
+        // Read-modify-write instructions write twice on consecutive cycles, and only the first of
+        // those writes reaches the shift register
+        let consecutive = self.written_last_cycle;
+        self.written_this_cycle = true;
+        if consecutive {
+            return;
+        }
+
+        if val & 0x80 != 0 {
+            // Writing a value with bit 7 set clears the shift register to its initial state and
+            // sets PRG mode 3
+            self.shift_register = 0;
+            self.shift_count = 0;
+            self.control |= 0x0c;
+            return;
+        }
+
+        self.shift_register |= (val & 1) << self.shift_count;
+        self.shift_count += 1;
+
+        if self.shift_count == 5 {
+            let register_val = self.shift_register;
+            match (addr >> 13) & 0b11 {
+                0 => self.control = register_val,
+                1 => self.chr_bank_0 = register_val,
+                2 => self.chr_bank_1 = register_val,
+                3 => self.prg_bank = register_val,
+                _ => unreachable!(),
+            }
+            self.shift_register = 0;
+            self.shift_count = 0;
+        }
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        self.prg_bank & 0x10 == 0
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        let bank_count = self.prg.len() / PRG_BANK_SIZE;
+        let bank = self.prg_bank as usize & 0x0f;
+        let bank = match (self.control >> 2) & 0b11 {
+            // Switch 32KB at $8000, ignoring the low bit of the bank number
+            0 | 1 => (bank & !1) | ((addr as usize >> 14) & 1),
+            // Fix first bank at $8000 and switch 16KB bank at $C000
+            2 => if addr < 0xc000 { 0 } else { bank },
+            // Fix last bank at $C000 and switch 16KB bank at $8000
+            3 => if addr < 0xc000 { bank } else { bank_count - 1 },
+            _ => unreachable!(),
+        };
+        (bank % bank_count) * PRG_BANK_SIZE + (addr as usize & 0x3fff)
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        let bank = if self.control & 0x10 == 0 {
+            // Switch 8KB at a time, ignoring the low bit of the bank number
+            (self.chr_bank_0 as usize & !1) | (addr as usize >> 12)
+        } else if addr < 0x1000 {
+            self.chr_bank_0 as usize
+        } else {
+            self.chr_bank_1 as usize
+        };
+        (bank * CHR_BANK_SIZE + (addr as usize & 0x0fff)) % self.chr.len()
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn prg_read(&self, addr: u16) -> u8 {
+        if addr >= 0x8000 {
+            self.prg[self.prg_offset(addr)]
+        } else if addr >= 0x6000 && self.prg_ram_enabled() {
+            self.prg_ram[addr as usize & 0x1fff]
+        } else {
+            0
+        }
+    }
+
+    fn prg_write(&mut self, addr: u16, val: u8) {
+        if addr >= 0x8000 {
+            self.write_shift_register(addr, val)
+        } else if addr >= 0x6000 && self.prg_ram_enabled() {
+            self.prg_ram[addr as usize & 0x1fff] = val
+        }
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr.read(self.chr_offset(addr))
+    }
+
+    fn chr_write(&mut self, addr: u16, val: u8) {
+        let offset = self.chr_offset(addr);
+        self.chr.write(offset, val)
+    }
+
+    fn tick(&mut self) {
+        self.written_last_cycle = self.written_this_cycle;
+        self.written_this_cycle = false;
+    }
+
+    fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram
+    }
+
+    fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.prg_ram
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0b11 {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            3 => Mirroring::Horizontal,
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use super::*;
+use rom::NesRom;
+
+#[test]
+fn shift_register_load() {
+    let mut mmc1 = mmc1_fixture();
+    // Bits are shifted in least significant bit first
+    for bit in &[1, 0, 1, 1, 0] {
+        mmc1.prg_write(0xa000, *bit);
+    }
+    assert_eq!(0b01101, mmc1.chr_bank_0);
+    assert_eq!(0, mmc1.shift_count);
+}
+
+#[test]
+fn only_fifth_write_address_matters() {
+    let mut mmc1 = mmc1_fixture();
+    mmc1.prg_write(0x8000, 1);
+    mmc1.prg_write(0xa000, 1);
+    mmc1.prg_write(0xe000, 1);
+    mmc1.prg_write(0x8000, 1);
+    mmc1.prg_write(0xc000, 1);
+    assert_eq!(0b11111, mmc1.chr_bank_1);
+    assert_eq!(0x0c, mmc1.control);
+}
+
+#[test]
+fn reset_shift_register() {
+    let mut mmc1 = mmc1_fixture();
+    write_register(&mut mmc1, 0x8000, 0b00000);
+    mmc1.prg_write(0x8000, 1);
+    mmc1.prg_write(0x8000, 1);
+    mmc1.prg_write(0x8000, 0x80);
+    assert_eq!(0, mmc1.shift_register);
+    assert_eq!(0, mmc1.shift_count);
+    assert_eq!(0x0c, mmc1.control);
+}
+
+#[test]
+fn consecutive_writes_are_ignored() {
+    let mut mmc1 = mmc1_fixture();
+    write_register(&mut mmc1, 0xa000, 0b00000);
+
+    // Like INC $A000 on a value of $FF: the dummy write resets the shift register, and the
+    // modified value written on the next cycle is ignored
+    mmc1.prg_write(0xa000, 0xff);
+    mmc1.tick();
+    mmc1.prg_write(0xa000, 0x00);
+    mmc1.tick();
+    assert_eq!(0, mmc1.shift_count);
+
+    // Writes that aren't on consecutive cycles all count
+    mmc1.tick();
+    mmc1.prg_write(0xa000, 1);
+    mmc1.tick();
+    mmc1.tick();
+    mmc1.prg_write(0xa000, 1);
+    assert_eq!(2, mmc1.shift_count);
+}
+
+#[test]
+fn power_on_fixes_last_bank() {
+    let mmc1 = mmc1_fixture();
+    assert_eq!(0, mmc1.prg_read(0x8000));
+    assert_eq!(7, mmc1.prg_read(0xc000));
+}
+
+#[test]
+fn prg_mode_32k() {
+    let mut mmc1 = mmc1_fixture();
+    write_register(&mut mmc1, 0x8000, 0b00000);
+    write_register(&mut mmc1, 0xe000, 5);
+    // Low bit of the bank number is ignored
+    assert_eq!(4, mmc1.prg_read(0x8000));
+    assert_eq!(5, mmc1.prg_read(0xc000));
+}
+
+#[test]
+fn prg_mode_fix_first_bank() {
+    let mut mmc1 = mmc1_fixture();
+    write_register(&mut mmc1, 0x8000, 0b01000);
+    write_register(&mut mmc1, 0xe000, 5);
+    assert_eq!(0, mmc1.prg_read(0x8000));
+    assert_eq!(5, mmc1.prg_read(0xc000));
+}
+
+#[test]
+fn prg_mode_fix_last_bank() {
+    let mut mmc1 = mmc1_fixture();
+    write_register(&mut mmc1, 0x8000, 0b01100);
+    write_register(&mut mmc1, 0xe000, 5);
+    assert_eq!(5, mmc1.prg_read(0x8000));
+    assert_eq!(7, mmc1.prg_read(0xffff));
+}
+
+#[test]
+fn chr_mode_8k() {
+    let mut mmc1 = mmc1_fixture();
+    write_register(&mut mmc1, 0x8000, 0b00000);
+    write_register(&mut mmc1, 0xa000, 3);
+    write_register(&mut mmc1, 0xc000, 7);
+    // Low bit of the bank number is ignored and the second bank register is unused
+    assert_eq!(2, mmc1.chr_read(0x0000));
+    assert_eq!(3, mmc1.chr_read(0x1000));
+}
+
+#[test]
+fn chr_mode_4k() {
+    let mut mmc1 = mmc1_fixture();
+    write_register(&mut mmc1, 0x8000, 0b10000);
+    write_register(&mut mmc1, 0xa000, 3);
+    write_register(&mut mmc1, 0xc000, 6);
+    assert_eq!(3, mmc1.chr_read(0x0000));
+    assert_eq!(6, mmc1.chr_read(0x1fff));
+}
+
+#[test]
+fn mirroring() {
+    let mut mmc1 = mmc1_fixture();
+    write_register(&mut mmc1, 0x8000, 0b00);
+    assert_eq!(Mirroring::SingleScreenLower, mmc1.mirroring());
+    write_register(&mut mmc1, 0x8000, 0b01);
+    assert_eq!(Mirroring::SingleScreenUpper, mmc1.mirroring());
+    write_register(&mut mmc1, 0x8000, 0b10);
+    assert_eq!(Mirroring::Vertical, mmc1.mirroring());
+    write_register(&mut mmc1, 0x8000, 0b11);
+    assert_eq!(Mirroring::Horizontal, mmc1.mirroring());
+}
+
+#[test]
+fn prg_ram() {
+    let mut mmc1 = mmc1_fixture();
+    mmc1.prg_write(0x6000, 0xaa);
+    mmc1.prg_write(0x7fff, 0xbb);
+    assert_eq!(0xaa, mmc1.prg_read(0x6000));
+    assert_eq!(0xbb, mmc1.prg_read(0x7fff));
+}
+
+#[test]
+fn prg_ram_disable() {
+    let mut mmc1 = mmc1_fixture();
+    mmc1.prg_write(0x6000, 0xaa);
+    write_register(&mut mmc1, 0xe000, 0x10);
+    assert_eq!(0, mmc1.prg_read(0x6000));
+    mmc1.prg_write(0x6000, 0xbb);
+    write_register(&mut mmc1, 0xe000, 0x00);
+    assert_eq!(0xaa, mmc1.prg_read(0x6000));
+}
+
+fn write_register(mmc1: &mut Mmc1, addr: u16, val: u8) {
+    for i in 0..5 {
+        mmc1.prg_write(addr, (val >> i) & 1);
+    }
+}
+
+// 8 16KB PRG banks and 8 4KB CHR banks, each filled with its bank number
+fn mmc1_fixture() -> Mmc1 {
+    let mut rom = NesRom::default();
+    rom.mapper = 1;
+    rom.prg = (0..0x20000).map(|i| (i / PRG_BANK_SIZE) as u8).collect();
+    rom.chr = (0..0x8000).map(|i| (i / CHR_BANK_SIZE) as u8).collect();
+    Mmc1::new(rom)
+}
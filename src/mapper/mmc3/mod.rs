@@ -0,0 +1,203 @@
+//! MMC3 (mapper 4). 8KB PRG banks, 1KB/2KB CHR banks and a scanline counter that is clocked by
+//! rising edges of PPU address line A12.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::{Chr, Mapper, prg_ram};
+use rom::{Mirroring, NesRom};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x400;
+
+// The MMC3 ignores A12 rising edges unless A12 has been low for a few CPU cycles. This filters out
+// the rapid toggling that occurs between the background and sprite fetches of a single scanline.
+const A12_LOW_CYCLES: u8 = 3;
+
+pub struct Mmc3 {
+    prg: Vec<u8>,
+    chr: Chr,
+    prg_ram: Vec<u8>,
+    bank_select: u8,
+    bank_registers: [u8; 8],
+    mirroring: Mirroring,
+    prg_ram_enabled: bool,
+    prg_ram_write_protect: bool,
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq_pending: bool,
+    a12: bool,
+    a12_low_cycles: u8,
+}
+
+impl Mmc3 {
+    pub fn new(rom: NesRom) -> Self {
+        let mut prg_ram = prg_ram(&rom);
+        if prg_ram.is_empty() {
+            prg_ram = vec![0; 0x2000];
+        }
+
+        Mmc3 {
+            prg_ram: prg_ram,
+            prg: rom.prg,
+            chr: Chr::new(rom.chr, rom.chr_ram_banks),
+            bank_select: 0,
+            bank_registers: [0, 2, 4, 5, 6, 7, 0, 1],
+            mirroring: rom.mirroring,
+            prg_ram_enabled: true,
+            prg_ram_write_protect: false,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq_pending: false,
+            a12: false,
+            a12_low_cycles: 0,
+        }
+    }
+
+    fn write_register(&mut self, addr: u16, val: u8) {
+        match addr & 0xe001 {
+            0x8000 => self.bank_select = val,
+            0x8001 => self.bank_registers[self.bank_select as usize & 0b111] = val,
+            0xa000 => {
+                // Boards wired for four-screen VRAM ignore the mirroring register
+                if self.mirroring != Mirroring::FourScreen {
+                    self.mirroring = if val & 1 == 0 {
+                        Mirroring::Vertical
+                    } else {
+                        Mirroring::Horizontal
+                    };
+                }
+            }
+            0xa001 => {
+                self.prg_ram_enabled = val & 0x80 != 0;
+                self.prg_ram_write_protect = val & 0x40 != 0;
+            }
+            0xc000 => self.irq_latch = val,
+            0xc001 => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            0xe000 => {
+                self.irq_enabled = false;
+                self.irq_pending = false;
+            }
+            0xe001 => self.irq_enabled = true,
+            _ => unreachable!(),
+        }
+    }
+
+    fn clock_irq_counter(&mut self) {
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+
+        if self.irq_counter == 0 && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        let bank_count = self.prg.len() / PRG_BANK_SIZE;
+        // An 8KB PRG ROM only has one bank to map into every slot
+        let second_last = bank_count.saturating_sub(2);
+        let swap_prg_banks = self.bank_select & 0x40 != 0;
+        let bank = match (addr as usize - 0x8000) / PRG_BANK_SIZE {
+            0 if swap_prg_banks => second_last,
+            0 => self.bank_registers[6] as usize,
+            1 => self.bank_registers[7] as usize,
+            2 if swap_prg_banks => self.bank_registers[6] as usize,
+            2 => second_last,
+            3 => bank_count - 1,
+            _ => unreachable!(),
+        };
+        (bank % bank_count) * PRG_BANK_SIZE + (addr as usize & 0x1fff)
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        // With CHR A12 inversion, the two 2KB banks are at $1000-$1FFF and the four 1KB banks are
+        // at $0000-$0FFF
+        let addr = if self.bank_select & 0x80 != 0 {
+            addr ^ 0x1000
+        } else {
+            addr
+        } as usize;
+
+        let bank = match addr / CHR_BANK_SIZE {
+            0 => self.bank_registers[0] & 0xfe,
+            1 => self.bank_registers[0] | 1,
+            2 => self.bank_registers[1] & 0xfe,
+            3 => self.bank_registers[1] | 1,
+            n => self.bank_registers[n - 2],
+        } as usize;
+        (bank * CHR_BANK_SIZE + (addr & 0x3ff)) % self.chr.len()
+    }
+}
+
+impl Mapper for Mmc3 {
+    fn prg_read(&self, addr: u16) -> u8 {
+        if addr >= 0x8000 {
+            self.prg[self.prg_offset(addr)]
+        } else if addr >= 0x6000 && self.prg_ram_enabled {
+            self.prg_ram[addr as usize & 0x1fff]
+        } else {
+            0
+        }
+    }
+
+    fn prg_write(&mut self, addr: u16, val: u8) {
+        if addr >= 0x8000 {
+            self.write_register(addr, val)
+        } else if addr >= 0x6000 && self.prg_ram_enabled && !self.prg_ram_write_protect {
+            self.prg_ram[addr as usize & 0x1fff] = val
+        }
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr.read(self.chr_offset(addr))
+    }
+
+    fn chr_write(&mut self, addr: u16, val: u8) {
+        let offset = self.chr_offset(addr);
+        self.chr.write(offset, val)
+    }
+
+    fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram
+    }
+
+    fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.prg_ram
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn ppu_address(&mut self, addr: u16) {
+        let a12 = addr & 0x1000 != 0;
+        if a12 {
+            if !self.a12 && self.a12_low_cycles >= A12_LOW_CYCLES {
+                self.clock_irq_counter();
+            }
+            self.a12_low_cycles = 0;
+        }
+        self.a12 = a12;
+    }
+
+    fn tick(&mut self) {
+        if !self.a12 && self.a12_low_cycles < A12_LOW_CYCLES {
+            self.a12_low_cycles += 1;
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use super::*;
+use rom::NesRom;
+
+#[test]
+fn prg_mode_0() {
+    let mut mmc3 = mmc3_fixture();
+    select_bank(&mut mmc3, 6, 3);
+    select_bank(&mut mmc3, 7, 5);
+    assert_eq!(3, mmc3.prg_read(0x8000));
+    assert_eq!(5, mmc3.prg_read(0xa000));
+    assert_eq!(14, mmc3.prg_read(0xc000));
+    assert_eq!(15, mmc3.prg_read(0xe000));
+}
+
+#[test]
+fn prg_mode_1() {
+    let mut mmc3 = mmc3_fixture();
+    select_bank(&mut mmc3, 0x46, 3);
+    select_bank(&mut mmc3, 0x47, 5);
+    assert_eq!(14, mmc3.prg_read(0x8000));
+    assert_eq!(5, mmc3.prg_read(0xa000));
+    assert_eq!(3, mmc3.prg_read(0xc000));
+    assert_eq!(15, mmc3.prg_read(0xe000));
+}
+
+#[test]
+fn chr_mode_0() {
+    let mut mmc3 = mmc3_fixture();
+    for (register, bank) in [9, 12, 1, 2, 3, 4].iter().enumerate() {
+        select_bank(&mut mmc3, register as u8, *bank);
+    }
+    // The low bit of the 2KB bank registers is ignored
+    assert_eq!(8, mmc3.chr_read(0x0000));
+    assert_eq!(9, mmc3.chr_read(0x0400));
+    assert_eq!(12, mmc3.chr_read(0x0800));
+    assert_eq!(13, mmc3.chr_read(0x0c00));
+    assert_eq!(1, mmc3.chr_read(0x1000));
+    assert_eq!(2, mmc3.chr_read(0x1400));
+    assert_eq!(3, mmc3.chr_read(0x1800));
+    assert_eq!(4, mmc3.chr_read(0x1c00));
+}
+
+#[test]
+fn chr_mode_1() {
+    let mut mmc3 = mmc3_fixture();
+    for (register, bank) in [8, 12, 1, 2, 3, 4].iter().enumerate() {
+        select_bank(&mut mmc3, register as u8 | 0x80, *bank);
+    }
+    assert_eq!(1, mmc3.chr_read(0x0000));
+    assert_eq!(2, mmc3.chr_read(0x0400));
+    assert_eq!(3, mmc3.chr_read(0x0800));
+    assert_eq!(4, mmc3.chr_read(0x0c00));
+    assert_eq!(8, mmc3.chr_read(0x1000));
+    assert_eq!(9, mmc3.chr_read(0x1400));
+    assert_eq!(12, mmc3.chr_read(0x1800));
+    assert_eq!(13, mmc3.chr_read(0x1c00));
+}
+
+#[test]
+fn mirroring() {
+    let mut mmc3 = mmc3_fixture();
+    mmc3.prg_write(0xa000, 0);
+    assert_eq!(Mirroring::Vertical, mmc3.mirroring());
+    mmc3.prg_write(0xa000, 1);
+    assert_eq!(Mirroring::Horizontal, mmc3.mirroring());
+}
+
+#[test]
+fn four_screen_ignores_mirroring_register() {
+    let mut rom = NesRom::default();
+    rom.prg = vec![0; 0x8000];
+    rom.chr = vec![0; 0x2000];
+    rom.mirroring = Mirroring::FourScreen;
+    let mut mmc3 = Mmc3::new(rom);
+    mmc3.prg_write(0xa000, 1);
+    assert_eq!(Mirroring::FourScreen, mmc3.mirroring());
+}
+
+#[test]
+fn prg_ram_protect() {
+    let mut mmc3 = mmc3_fixture();
+    mmc3.prg_write(0xa001, 0x80);
+    mmc3.prg_write(0x6000, 0xaa);
+    assert_eq!(0xaa, mmc3.prg_read(0x6000));
+
+    mmc3.prg_write(0xa001, 0xc0);
+    mmc3.prg_write(0x6000, 0xbb);
+    assert_eq!(0xaa, mmc3.prg_read(0x6000));
+
+    mmc3.prg_write(0xa001, 0x00);
+    assert_eq!(0, mmc3.prg_read(0x6000));
+}
+
+#[test]
+fn irq_after_latch_scanlines() {
+    let mut mmc3 = mmc3_fixture();
+    mmc3.prg_write(0xc000, 3);
+    mmc3.prg_write(0xc001, 0);
+    mmc3.prg_write(0xe001, 0);
+
+    // First clock reloads the counter with the latch value
+    scanline(&mut mmc3);
+    assert_eq!(3, mmc3.irq_counter);
+    scanline(&mut mmc3);
+    scanline(&mut mmc3);
+    assert_eq!(false, mmc3.irq_pending());
+    scanline(&mut mmc3);
+    assert_eq!(true, mmc3.irq_pending());
+}
+
+#[test]
+fn irq_acknowledge() {
+    let mut mmc3 = mmc3_fixture();
+    mmc3.prg_write(0xc000, 0);
+    mmc3.prg_write(0xe001, 0);
+    scanline(&mut mmc3);
+    assert_eq!(true, mmc3.irq_pending());
+    mmc3.prg_write(0xe000, 0);
+    assert_eq!(false, mmc3.irq_pending());
+}
+
+#[test]
+fn irq_disabled() {
+    let mut mmc3 = mmc3_fixture();
+    mmc3.prg_write(0xc000, 0);
+    scanline(&mut mmc3);
+    assert_eq!(false, mmc3.irq_pending());
+}
+
+#[test]
+fn a12_rising_edges_are_filtered() {
+    let mut mmc3 = mmc3_fixture();
+    mmc3.prg_write(0xc000, 5);
+    scanline(&mut mmc3);
+    assert_eq!(5, mmc3.irq_counter);
+
+    // A12 toggling without being low for several CPU cycles does not clock the counter
+    for _ in 0..8 {
+        mmc3.ppu_address(0x0000);
+        mmc3.ppu_address(0x1000);
+    }
+    assert_eq!(5, mmc3.irq_counter);
+}
+
+#[test]
+fn single_prg_bank() {
+    let mut rom = NesRom::default();
+    rom.mapper = 4;
+    rom.prg = (0..PRG_BANK_SIZE).map(|i| i as u8).collect();
+    let mut mmc3 = Mmc3::new(rom);
+    select_bank(&mut mmc3, 0x46, 3);
+    for &addr in &[0x8000, 0xa000, 0xc000, 0xe000] {
+        assert_eq!(0, mmc3.prg_read(addr));
+        assert_eq!(1, mmc3.prg_read(addr + 1));
+    }
+}
+
+fn scanline(mmc3: &mut Mmc3) {
+    mmc3.ppu_address(0x0000);
+    for _ in 0..A12_LOW_CYCLES {
+        mmc3.tick();
+    }
+    mmc3.ppu_address(0x1000);
+}
+
+fn select_bank(mmc3: &mut Mmc3, bank_select: u8, bank: u8) {
+    mmc3.prg_write(0x8000, bank_select);
+    mmc3.prg_write(0x8001, bank);
+}
+
+// 16 8KB PRG banks and 16 1KB CHR banks, each filled with its bank number
+fn mmc3_fixture() -> Mmc3 {
+    let mut rom = NesRom::default();
+    rom.mapper = 4;
+    rom.prg = (0..0x20000).map(|i| (i / PRG_BANK_SIZE) as u8).collect();
+    rom.chr = (0..0x4000).map(|i| (i / CHR_BANK_SIZE) as u8).collect();
+    Mmc3::new(rom)
+}
@@ -0,0 +1,319 @@
+//! Cartridge boards. The mapper owns PRG-ROM, CHR and PRG-RAM and decides how the CPU and PPU
+//! address spaces map onto them.
+
+mod nrom;
+mod mmc1;
+mod mmc3;
+mod uxrom;
+mod cnrom;
+mod axrom;
+mod gxrom;
+mod bnrom;
+mod color_dreams;
+mod fds;
+pub mod nsf;
+
+#[cfg(test)]
+mod spec_tests;
+
+use self::axrom::Axrom;
+use self::bnrom::Bnrom;
+use self::cnrom::Cnrom;
+use self::color_dreams::ColorDreams;
+use self::fds::Fds;
+use self::gxrom::Gxrom;
+use self::mmc1::Mmc1;
+use self::mmc3::Mmc3;
+use self::nrom::Nrom;
+use self::uxrom::Uxrom;
+use rom::{Mirroring, NesRom, RomError};
+use rom::fds::FdsImage;
+use std::cell::RefCell;
+use std::cmp;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+const PRG_RAM_BANK_SIZE: usize = 0x2000;
+const CHR_RAM_BANK_SIZE: usize = 0x2000;
+
+// Both pattern tables, $0000-$1FFF
+const PATTERN_TABLES_SIZE: usize = 0x2000;
+
+// Offset of $7000 within PRG-RAM, which is where trainers are loaded
+const TRAINER_OFFSET: usize = 0x1000;
+
+pub trait Mapper {
+    /// Read from the cartridge's CPU address space ($4020-$FFFF)
+    fn prg_read(&self, addr: u16) -> u8;
+
+    /// Write to the cartridge's CPU address space ($4020-$FFFF)
+    fn prg_write(&mut self, addr: u16, val: u8);
+
+    /// Read from the pattern tables ($0000-$1FFF)
+    fn chr_read(&self, addr: u16) -> u8;
+
+    /// Write to the pattern tables ($0000-$1FFF)
+    fn chr_write(&mut self, addr: u16, val: u8);
+
+    /// The current nametable arrangement, which some boards can change at runtime
+    fn mirroring(&self) -> Mirroring;
+
+    /// PRG-RAM mapped at $6000-$7FFF, which is persisted on boards with a battery
+    fn prg_ram(&self) -> &[u8] {
+        &[]
+    }
+
+    fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut []
+    }
+
+    /// Whether the cartridge is currently asserting the CPU's IRQ line
+    fn irq_pending(&self) -> bool {
+        false
+    }
+
+    /// Called with every address the PPU places on its address bus, for boards that watch the PPU
+    /// address lines
+    fn ppu_address(&mut self, _: u16) {}
+
+    /// Called once per CPU cycle
+    fn tick(&mut self) {}
+
+    /// The output of the board's expansion sound hardware, scaled to its level relative to the
+    /// APU's channels, whose mixed output is from 0.0 to 1.0
+    fn expansion_audio(&self) -> f32 {
+        0.0
+    }
+
+    /// The number of disk sides available to insert, for boards with a disk drive
+    fn disk_sides(&self) -> usize {
+        0
+    }
+
+    /// Ejects the current disk and inserts the given side, or leaves the drive empty if `None`
+    fn insert_disk(&mut self, _: Option<usize>) {}
+}
+
+/// A handle to the cartridge that is shared between the CPU and PPU buses.
+#[derive(Clone)]
+pub struct Cartridge {
+    mapper: Rc<RefCell<Box<Mapper>>>,
+    has_battery: bool,
+}
+
+impl Cartridge {
+    /// Selects the mapper implementation based on the mapper number in the ROM header.
+    pub fn new(mut rom: NesRom) -> Result<Self, RomError> {
+        // Trainers were loaded into PRG-RAM by copiers, so boards that have one always get some
+        if !rom.trainer.is_empty() && rom.prg_ram_banks == 0 {
+            rom.prg_ram_banks = 1;
+            rom.prg_ram_size = PRG_RAM_BANK_SIZE;
+        }
+
+        let has_battery = rom.has_sram;
+        let trainer = rom.trainer.clone();
+        let prg_len = rom.prg.len();
+        let chr_len = rom.chr.len();
+        let (mapper, prg_bank_size): (Box<Mapper>, usize) = match rom.mapper {
+            0 => (Box::new(Nrom::new(rom)), 0x4000),
+            1 => (Box::new(Mmc1::new(rom)), 0x4000),
+            2 => (Box::new(Uxrom::new(rom)), 0x4000),
+            3 => (Box::new(Cnrom::new(rom)), 0x4000),
+            4 => (Box::new(Mmc3::new(rom)), 0x2000),
+            7 => (Box::new(Axrom::new(rom)), 0x8000),
+            11 => (Box::new(ColorDreams::new(rom)), 0x8000),
+            34 => (Box::new(Bnrom::new(rom)), 0x8000),
+            66 => (Box::new(Gxrom::new(rom)), 0x8000),
+            mapper => return Err(RomError::UnsupportedMapper(mapper)),
+        };
+
+        // Boards address PRG-ROM in whole banks and CHR in up to a full 8KB pattern table, so
+        // anything smaller would be indexed out of bounds. Boards without CHR-ROM get CHR-RAM.
+        if prg_len < prg_bank_size {
+            return Err(RomError::TooSmall("PRG-ROM is smaller than one bank."));
+        }
+        if chr_len > 0 && chr_len < PATTERN_TABLES_SIZE {
+            return Err(RomError::TooSmall("CHR-ROM is smaller than 8KB."));
+        }
+        let mut cart = Self::from_mapper(mapper);
+        cart.has_battery = has_battery;
+        cart.load_trainer(&trainer)?;
+        Ok(cart)
+    }
+
+    /// Builds a Famicom Disk System from the `disksys.rom` BIOS and a disk image, with the first
+    /// side inserted.
+    pub fn from_fds(bios: Vec<u8>, image: FdsImage) -> Result<Self, RomError> {
+        if bios.len() != fds::BIOS_SIZE {
+            return Err(RomError::BadBios);
+        }
+        Ok(Self::from_mapper(Box::new(Fds::new(bios, image))))
+    }
+
+    pub fn from_mapper(mapper: Box<Mapper>) -> Self {
+        Cartridge {
+            mapper: Rc::new(RefCell::new(mapper)),
+            has_battery: false,
+        }
+    }
+
+    /// Copies a trainer into $7000-$71FF of PRG-RAM, as the copier hardware that trainers were
+    /// written for did before reset. It's an error for boards that don't map PRG-RAM there.
+    fn load_trainer(&self, trainer: &[u8]) -> Result<(), RomError> {
+        if trainer.is_empty() {
+            return Ok(());
+        }
+
+        let mut mapper = self.mapper.borrow_mut();
+        let prg_ram = mapper.prg_ram_mut();
+        let end = TRAINER_OFFSET + trainer.len();
+        if prg_ram.len() < end {
+            return Err(RomError::UnsupportedTrainer);
+        }
+        prg_ram[TRAINER_OFFSET..end].copy_from_slice(trainer);
+        Ok(())
+    }
+
+    pub fn has_battery(&self) -> bool {
+        self.has_battery
+    }
+
+    /// Loads battery-backed PRG-RAM from a save file. A missing save file is not an error, since
+    /// it just means the game hasn't been saved yet. Does nothing for boards without a battery.
+    pub fn load_save<P: AsRef<Path>>(&self, path: P) -> Result<(), &'static str> {
+        if !self.has_battery {
+            return Ok(());
+        }
+
+        let mut f = match File::open(path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(_) => return Err("Unable to open save file."),
+        };
+
+        let mut save = Vec::new();
+        if f.read_to_end(&mut save).is_err() {
+            return Err("An error occurred reading the save file.");
+        }
+
+        let mut mapper = self.mapper.borrow_mut();
+        let prg_ram = mapper.prg_ram_mut();
+        let len = cmp::min(prg_ram.len(), save.len());
+        prg_ram[..len].copy_from_slice(&save[..len]);
+        Ok(())
+    }
+
+    /// Writes battery-backed PRG-RAM to a save file. Does nothing for boards without a battery.
+    pub fn flush_save<P: AsRef<Path>>(&self, path: P) -> Result<(), &'static str> {
+        if !self.has_battery {
+            return Ok(());
+        }
+
+        let mut f = match File::create(path) {
+            Ok(file) => file,
+            Err(_) => return Err("Unable to create save file."),
+        };
+
+        match f.write_all(self.mapper.borrow().prg_ram()) {
+            Ok(_) => Ok(()),
+            Err(_) => Err("An error occurred writing the save file."),
+        }
+    }
+
+    pub fn prg_read(&self, addr: u16) -> u8 {
+        self.mapper.borrow().prg_read(addr)
+    }
+
+    pub fn prg_write(&self, addr: u16, val: u8) {
+        self.mapper.borrow_mut().prg_write(addr, val)
+    }
+
+    pub fn chr_read(&self, addr: u16) -> u8 {
+        self.mapper.borrow().chr_read(addr)
+    }
+
+    pub fn chr_write(&self, addr: u16, val: u8) {
+        self.mapper.borrow_mut().chr_write(addr, val)
+    }
+
+    pub fn mirroring(&self) -> Mirroring {
+        self.mapper.borrow().mirroring()
+    }
+
+    pub fn irq_pending(&self) -> bool {
+        self.mapper.borrow().irq_pending()
+    }
+
+    pub fn ppu_address(&self, addr: u16) {
+        self.mapper.borrow_mut().ppu_address(addr)
+    }
+
+    pub fn tick(&self) {
+        self.mapper.borrow_mut().tick()
+    }
+
+    pub fn expansion_audio(&self) -> f32 {
+        self.mapper.borrow().expansion_audio()
+    }
+
+    pub fn disk_sides(&self) -> usize {
+        self.mapper.borrow().disk_sides()
+    }
+
+    pub fn insert_disk(&self, side: Option<usize>) {
+        self.mapper.borrow_mut().insert_disk(side)
+    }
+}
+
+/// The save file for a ROM lives next to it, with a `.sav` extension.
+pub fn save_path<P: AsRef<Path>>(rom_path: P) -> PathBuf {
+    rom_path.as_ref().with_extension("sav")
+}
+
+/// The Famicom Disk System BIOS is expected next to the disk image, as `disksys.rom`.
+pub fn fds_bios_path<P: AsRef<Path>>(image_path: P) -> PathBuf {
+    image_path.as_ref().with_file_name("disksys.rom")
+}
+
+fn prg_ram(rom: &NesRom) -> Vec<u8> {
+    vec![0; rom.prg_ram_banks as usize * PRG_RAM_BANK_SIZE]
+}
+
+/// Pattern table memory. Boards without CHR-ROM have CHR-RAM instead, which the PPU can write to.
+struct Chr {
+    mem: Vec<u8>,
+    is_ram: bool,
+}
+
+impl Chr {
+    fn new(chr_rom: Vec<u8>, chr_ram_banks: u16) -> Self {
+        if chr_rom.is_empty() {
+            let banks = cmp::max(chr_ram_banks, 1) as usize;
+            Chr {
+                mem: vec![0; banks * CHR_RAM_BANK_SIZE],
+                is_ram: true,
+            }
+        } else {
+            Chr {
+                mem: chr_rom,
+                is_ram: false,
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.mem.len()
+    }
+
+    fn read(&self, offset: usize) -> u8 {
+        self.mem[offset]
+    }
+
+    fn write(&mut self, offset: usize, val: u8) {
+        if self.is_ram {
+            self.mem[offset] = val;
+        }
+    }
+}
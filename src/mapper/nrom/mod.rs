@@ -0,0 +1,65 @@
+//! NROM (mapper 0). No bank switching: 16KB or 32KB of PRG-ROM at $8000 (16KB images are
+//! mirrored into $C000) and 8KB of CHR.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::{Chr, Mapper, prg_ram};
+use rom::{Mirroring, NesRom};
+
+pub struct Nrom {
+    prg: Vec<u8>,
+    chr: Chr,
+    prg_ram: Vec<u8>,
+    mirroring: Mirroring,
+}
+
+impl Nrom {
+    pub fn new(rom: NesRom) -> Self {
+        Nrom {
+            prg_ram: prg_ram(&rom),
+            prg: rom.prg,
+            chr: Chr::new(rom.chr, rom.chr_ram_banks),
+            mirroring: rom.mirroring,
+        }
+    }
+}
+
+impl Mapper for Nrom {
+    fn prg_read(&self, addr: u16) -> u8 {
+        if addr >= 0x8000 {
+            self.prg[(addr as usize - 0x8000) % self.prg.len()]
+        } else if addr >= 0x6000 && !self.prg_ram.is_empty() {
+            self.prg_ram[(addr as usize - 0x6000) % self.prg_ram.len()]
+        } else {
+            0
+        }
+    }
+
+    fn prg_write(&mut self, addr: u16, val: u8) {
+        if addr >= 0x6000 && addr < 0x8000 && !self.prg_ram.is_empty() {
+            let len = self.prg_ram.len();
+            self.prg_ram[(addr as usize - 0x6000) % len] = val;
+        }
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr.read(addr as usize)
+    }
+
+    fn chr_write(&mut self, addr: u16, val: u8) {
+        self.chr.write(addr as usize, val)
+    }
+
+    fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram
+    }
+
+    fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.prg_ram
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
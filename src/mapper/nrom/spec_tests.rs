@@ -0,0 +1,79 @@
+use super::*;
+use rom::NesRom;
+
+#[test]
+fn prg_16k_is_mirrored() {
+    let nrom = nrom_fixture(0x4000, 0);
+    for addr in 0x8000..0xc000_u32 {
+        let addr = addr as u16;
+        assert_eq!(nrom.prg_read(addr), nrom.prg_read(addr + 0x4000));
+    }
+    assert_eq!(0x00, nrom.prg_read(0x8000));
+    assert_eq!(0x3f, nrom.prg_read(0xbfff));
+    assert_eq!(0x3f, nrom.prg_read(0xffff));
+}
+
+#[test]
+fn prg_32k_is_not_mirrored() {
+    let nrom = nrom_fixture(0x8000, 0);
+    assert_eq!(0x00, nrom.prg_read(0x8000));
+    assert_eq!(0x40, nrom.prg_read(0xc000));
+}
+
+#[test]
+fn prg_rom_is_read_only() {
+    let mut nrom = nrom_fixture(0x8000, 0);
+    nrom.prg_write(0x8000, 0xaa);
+    assert_eq!(0x00, nrom.prg_read(0x8000));
+}
+
+#[test]
+fn prg_ram() {
+    let mut nrom = nrom_fixture(0x4000, 1);
+    nrom.prg_write(0x6000, 0x11);
+    nrom.prg_write(0x7fff, 0x22);
+    assert_eq!(0x11, nrom.prg_read(0x6000));
+    assert_eq!(0x22, nrom.prg_read(0x7fff));
+}
+
+#[test]
+fn no_prg_ram() {
+    let mut nrom = nrom_fixture(0x4000, 0);
+    nrom.prg_write(0x6000, 0x11);
+    assert_eq!(0, nrom.prg_read(0x6000));
+}
+
+#[test]
+fn chr_rom_is_read_only() {
+    let mut nrom = nrom_fixture(0x4000, 0);
+    nrom.chr_write(0x0000, 0xaa);
+    assert_eq!(0, nrom.chr_read(0x0000));
+}
+
+#[test]
+fn chr_ram() {
+    let mut rom = NesRom::default();
+    rom.prg = vec![0; 0x4000];
+    rom.chr_ram_banks = 1;
+    let mut nrom = Nrom::new(rom);
+    nrom.chr_write(0x0000, 0x11);
+    nrom.chr_write(0x1fff, 0x22);
+    assert_eq!(0x11, nrom.chr_read(0x0000));
+    assert_eq!(0x22, nrom.chr_read(0x1fff));
+}
+
+#[test]
+fn mirroring_from_header() {
+    let mut rom = NesRom::default();
+    rom.mirroring = Mirroring::Vertical;
+    let nrom = Nrom::new(rom);
+    assert_eq!(Mirroring::Vertical, nrom.mirroring());
+}
+
+fn nrom_fixture(prg_size: usize, prg_ram_banks: u16) -> Nrom {
+    let mut rom = NesRom::default();
+    rom.prg = (0..prg_size).map(|i| (i >> 8) as u8).collect();
+    rom.chr = vec![0; 0x2000];
+    rom.prg_ram_banks = prg_ram_banks;
+    Nrom::new(rom)
+}
@@ -0,0 +1,213 @@
+//! The memory map seen by an NSF tune: 8KB of RAM at $6000-$7FFF and the tune's data in eight 4KB
+//! banks at $8000-$FFFF, switched by writing to $5FF8-$5FFF. Tunes that don't bank switch are
+//! loaded as a flat image at their load address instead.
+//!
+//! Expansion chips change the map:
+//!
+//! - FDS: $6000-$DFFF is RAM that is filled from the banks selected by $5FF6-$5FFD, and the FDS
+//!   sound registers are mapped at $4040-$4092.
+//! - MMC5: 1KB of ExRAM at $5C00-$5FF5, and the multiplier at $5205/$5206.
+//!
+//! Sound from the other expansion chips (VRC6, VRC7, Namco 163 and Sunsoft 5B) is not emulated.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::Mapper;
+use super::fds::audio::FdsAudio;
+use rom::Mirroring;
+use rom::nsf::{self, NsfFile};
+
+const BANK_SIZE: usize = 0x1000;
+
+// $6000-$FFFF is split into ten 4KB slots, the first two of which are only banked for FDS tunes
+const SLOT_COUNT: usize = 10;
+const FIRST_ROM_SLOT: usize = 2;
+
+pub struct Nsf {
+    data: Vec<u8>,
+    initial_banks: [u8; SLOT_COUNT],
+    banks: [u8; SLOT_COUNT],
+    ram: Vec<u8>,
+    fds_audio: Option<FdsAudio>,
+    exram: Option<Vec<u8>>,
+    multiplicand: u8,
+    multiplier: u8,
+}
+
+impl Nsf {
+    pub fn new(nsf: &NsfFile) -> Self {
+        let mut initial_banks = [0; SLOT_COUNT];
+        let data = if nsf.is_bankswitched() {
+            // Bank data starts at the 4KB boundary below the load address
+            let padding = nsf.load_address as usize & 0x0fff;
+            initial_banks[FIRST_ROM_SLOT..].copy_from_slice(&nsf.bankswitch_init);
+            initial_banks[0] = nsf.bankswitch_init[6];
+            initial_banks[1] = nsf.bankswitch_init[7];
+            let mut data = vec![0; padding];
+            data.extend_from_slice(&nsf.data);
+            data
+        } else {
+            // A flat image of $6000-$FFFF, with each slot mapped to its own bank
+            for (slot, bank) in initial_banks.iter_mut().enumerate() {
+                *bank = slot as u8;
+            }
+            let mut data = vec![0; SLOT_COUNT * BANK_SIZE];
+            let start = nsf.load_address as usize - 0x6000;
+            let len = nsf.data.len().min(data.len() - start);
+            data[start..start + len].copy_from_slice(&nsf.data[..len]);
+            data
+        };
+
+        let is_fds = nsf.has_expansion_chip(nsf::FDS);
+        let mut mapper = Nsf {
+            data: pad_to_bank(data),
+            initial_banks: initial_banks,
+            banks: initial_banks,
+            ram: vec![0; if is_fds { 0x8000 } else { 0x2000 }],
+            fds_audio: if is_fds { Some(FdsAudio::default()) } else { None },
+            exram: if nsf.has_expansion_chip(nsf::MMC5) {
+                Some(vec![0; 0x400])
+            } else {
+                None
+            },
+            multiplicand: 0xff,
+            multiplier: 0xff,
+        };
+        mapper.reset();
+        mapper
+    }
+
+    /// Restores the initial banks and clears RAM, which is done before each song is started
+    pub fn reset(&mut self) {
+        for byte in self.ram.iter_mut() {
+            *byte = 0;
+        }
+        if let Some(ref mut exram) = self.exram {
+            for byte in exram.iter_mut() {
+                *byte = 0;
+            }
+        }
+        if self.fds_audio.is_some() {
+            self.fds_audio = Some(FdsAudio::default());
+        }
+
+        self.banks = self.initial_banks;
+        if self.is_fds() {
+            for slot in 0..FIRST_ROM_SLOT + 6 {
+                self.load_fds_slot(slot);
+            }
+        }
+    }
+
+    fn is_fds(&self) -> bool {
+        self.fds_audio.is_some()
+    }
+
+    fn bank_read(&self, slot: usize, addr: u16) -> u8 {
+        let bank_count = self.data.len() / BANK_SIZE;
+        let bank = self.banks[slot] as usize % bank_count;
+        self.data[bank * BANK_SIZE + (addr as usize & 0x0fff)]
+    }
+
+    // FDS tunes run from RAM, so switching a bank into $6000-$DFFF copies it
+    fn load_fds_slot(&mut self, slot: usize) {
+        let bank_count = self.data.len() / BANK_SIZE;
+        let bank = self.banks[slot] as usize % bank_count;
+        let src = &self.data[bank * BANK_SIZE..(bank + 1) * BANK_SIZE];
+        self.ram[slot * BANK_SIZE..(slot + 1) * BANK_SIZE].copy_from_slice(src);
+    }
+
+    fn write_bank(&mut self, addr: u16, val: u8) {
+        let slot = addr as usize - 0x5ff6;
+        if slot < FIRST_ROM_SLOT && !self.is_fds() {
+            return;
+        }
+
+        self.banks[slot] = val;
+        if self.is_fds() && slot < FIRST_ROM_SLOT + 6 {
+            self.load_fds_slot(slot);
+        }
+    }
+}
+
+impl Mapper for Nsf {
+    fn prg_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x4040...0x4092 => {
+                match self.fds_audio {
+                    Some(ref audio) => audio.read(addr),
+                    None => 0,
+                }
+            }
+            0x5205 | 0x5206 if self.exram.is_some() => {
+                let product = self.multiplicand as u16 * self.multiplier as u16;
+                if addr == 0x5205 {
+                    product as u8
+                } else {
+                    (product >> 8) as u8
+                }
+            }
+            0x5c00...0x5ff5 => {
+                match self.exram {
+                    Some(ref exram) => exram[addr as usize - 0x5c00],
+                    None => 0,
+                }
+            }
+            0x6000...0xdfff if self.is_fds() => self.ram[addr as usize - 0x6000],
+            0x6000...0x7fff => self.ram[addr as usize - 0x6000],
+            0x8000...0xffff => self.bank_read((addr as usize - 0x6000) / BANK_SIZE, addr),
+            _ => 0,
+        }
+    }
+
+    fn prg_write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x4040...0x4092 => {
+                if let Some(ref mut audio) = self.fds_audio {
+                    audio.write(addr, val);
+                }
+            }
+            0x5205 => self.multiplicand = val,
+            0x5206 => self.multiplier = val,
+            0x5c00...0x5ff5 => {
+                if let Some(ref mut exram) = self.exram {
+                    exram[addr as usize - 0x5c00] = val;
+                }
+            }
+            0x5ff6...0x5fff => self.write_bank(addr, val),
+            0x6000...0xdfff if self.is_fds() => self.ram[addr as usize - 0x6000] = val,
+            0x6000...0x7fff => self.ram[addr as usize - 0x6000] = val,
+            _ => (),
+        }
+    }
+
+    fn chr_read(&self, _: u16) -> u8 {
+        0
+    }
+
+    fn chr_write(&mut self, _: u16, _: u8) {}
+
+    fn mirroring(&self) -> Mirroring {
+        Mirroring::Horizontal
+    }
+
+    fn tick(&mut self) {
+        if let Some(ref mut audio) = self.fds_audio {
+            audio.clock();
+        }
+    }
+
+    fn expansion_audio(&self) -> f32 {
+        match self.fds_audio {
+            Some(ref audio) => audio.output(),
+            None => 0.0,
+        }
+    }
+}
+
+fn pad_to_bank(mut data: Vec<u8>) -> Vec<u8> {
+    let len = (data.len() + BANK_SIZE - 1) / BANK_SIZE * BANK_SIZE;
+    data.resize(len.max(BANK_SIZE), 0);
+    data
+}
@@ -0,0 +1,129 @@
+use super::*;
+use rom::VideoStandard;
+
+#[test]
+fn flat_image_at_load_address() {
+    let nsf = nsf_fixture(0x8100, vec![0x11, 0x22], [0; 8], 0);
+    let mapper = Nsf::new(&nsf);
+    assert_eq!(0x00, mapper.prg_read(0x80ff));
+    assert_eq!(0x11, mapper.prg_read(0x8100));
+    assert_eq!(0x22, mapper.prg_read(0x8101));
+}
+
+#[test]
+fn prg_ram() {
+    let nsf = nsf_fixture(0x8000, vec![0; 0x1000], [0; 8], 0);
+    let mut mapper = Nsf::new(&nsf);
+    mapper.prg_write(0x6000, 0x11);
+    mapper.prg_write(0x7fff, 0x22);
+    assert_eq!(0x11, mapper.prg_read(0x6000));
+    assert_eq!(0x22, mapper.prg_read(0x7fff));
+
+    // ROM isn't writable without the FDS
+    mapper.prg_write(0x8000, 0x33);
+    assert_eq!(0x00, mapper.prg_read(0x8000));
+
+    mapper.reset();
+    assert_eq!(0x00, mapper.prg_read(0x6000));
+}
+
+#[test]
+fn bank_switching() {
+    let nsf = nsf_fixture(0x8000, banks_fixture(4), [0, 1, 2, 3, 0, 1, 2, 3], 0);
+    let mut mapper = Nsf::new(&nsf);
+    assert_eq!(0, mapper.prg_read(0x8000));
+    assert_eq!(1, mapper.prg_read(0x9000));
+    assert_eq!(3, mapper.prg_read(0xffff));
+
+    mapper.prg_write(0x5fff, 1);
+    assert_eq!(1, mapper.prg_read(0xf000));
+
+    // Banks wrap around the available data
+    mapper.prg_write(0x5ff8, 6);
+    assert_eq!(2, mapper.prg_read(0x8000));
+
+    mapper.reset();
+    assert_eq!(0, mapper.prg_read(0x8000));
+    assert_eq!(3, mapper.prg_read(0xf000));
+}
+
+#[test]
+fn bank_data_is_aligned_to_load_address() {
+    let nsf = nsf_fixture(0x8080, vec![0x11], [0, 0, 0, 0, 0, 0, 0, 1], 0);
+    let mapper = Nsf::new(&nsf);
+    assert_eq!(0x11, mapper.prg_read(0x8080));
+}
+
+#[test]
+fn fds_ram_is_loaded_from_banks() {
+    let nsf = nsf_fixture(0x8000, banks_fixture(4), [0, 1, 2, 3, 0, 1, 2, 3], nsf::FDS);
+    let mut mapper = Nsf::new(&nsf);
+    assert_eq!(2, mapper.prg_read(0x6000));
+    assert_eq!(3, mapper.prg_read(0x7000));
+    assert_eq!(0, mapper.prg_read(0x8000));
+
+    // $6000-$DFFF is writable RAM, which bank writes reload
+    mapper.prg_write(0x8000, 0x55);
+    assert_eq!(0x55, mapper.prg_read(0x8000));
+    mapper.prg_write(0x5ff8, 1);
+    assert_eq!(1, mapper.prg_read(0x8000));
+
+    // $5FF6 and $5FF7 switch $6000-$7FFF
+    mapper.prg_write(0x5ff6, 0);
+    assert_eq!(0, mapper.prg_read(0x6000));
+}
+
+#[test]
+fn fds_audio_registers() {
+    let nsf = nsf_fixture(0x8000, vec![0; 0x1000], [0; 8], nsf::FDS);
+    let mut mapper = Nsf::new(&nsf);
+    mapper.prg_write(0x4080, 0x80 | 0x10);
+    assert_eq!(0x50, mapper.prg_read(0x4090));
+
+    let nsf = nsf_fixture(0x8000, vec![0; 0x1000], [0; 8], 0);
+    let mut mapper = Nsf::new(&nsf);
+    mapper.prg_write(0x4080, 0x80 | 0x10);
+    assert_eq!(0x00, mapper.prg_read(0x4090));
+}
+
+#[test]
+fn mmc5_exram_and_multiplier() {
+    let nsf = nsf_fixture(0x8000, vec![0; 0x1000], [0; 8], nsf::MMC5);
+    let mut mapper = Nsf::new(&nsf);
+    mapper.prg_write(0x5c00, 0x11);
+    assert_eq!(0x11, mapper.prg_read(0x5c00));
+
+    mapper.prg_write(0x5205, 0x40);
+    mapper.prg_write(0x5206, 0x05);
+    assert_eq!(0x40, mapper.prg_read(0x5205));
+    assert_eq!(0x01, mapper.prg_read(0x5206));
+}
+
+// Each bank is filled with its own bank number
+fn banks_fixture(count: usize) -> Vec<u8> {
+    (0..count * BANK_SIZE).map(|i| (i / BANK_SIZE) as u8).collect()
+}
+
+fn nsf_fixture(load_address: u16,
+               data: Vec<u8>,
+               bankswitch_init: [u8; 8],
+               expansion_chips: u8)
+               -> NsfFile {
+    NsfFile {
+        version: 1,
+        total_songs: 1,
+        starting_song: 1,
+        load_address: load_address,
+        init_address: 0x8000,
+        play_address: 0x8000,
+        name: String::new(),
+        artist: String::new(),
+        copyright: String::new(),
+        ntsc_speed: 16639,
+        pal_speed: 19997,
+        bankswitch_init: bankswitch_init,
+        video_standard: VideoStandard::Ntsc,
+        expansion_chips: expansion_chips,
+        data: data,
+    }
+}
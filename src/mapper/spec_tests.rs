@@ -0,0 +1,183 @@
+use super::*;
+use rom::NesRom;
+use std::env;
+use std::fs;
+use std::process;
+
+#[test]
+fn save_path_is_next_to_rom() {
+    assert_eq!(PathBuf::from("roms/zelda.sav"), save_path("roms/zelda.nes"));
+}
+
+#[test]
+fn fds_bios_is_next_to_image() {
+    assert_eq!(PathBuf::from("roms/disksys.rom"),
+               fds_bios_path("roms/zelda.fds"));
+}
+
+#[test]
+fn unsupported_mapper() {
+    let mut rom = NesRom::default();
+    rom.mapper = 5;
+    match Cartridge::new(rom) {
+        Err(RomError::UnsupportedMapper(5)) => (),
+        _ => panic!("expected an unsupported mapper error"),
+    }
+}
+
+#[test]
+fn empty_prg_rom() {
+    let mut rom = NesRom::default();
+    rom.chr = vec![0; 0x2000];
+    match Cartridge::new(rom) {
+        Err(RomError::TooSmall(_)) => (),
+        _ => panic!("expected a too small error"),
+    }
+}
+
+#[test]
+fn prg_rom_smaller_than_bank() {
+    let mut rom = NesRom::default();
+    rom.prg = vec![0; 0x2000];
+    match Cartridge::new(rom) {
+        Err(RomError::TooSmall(_)) => (),
+        _ => panic!("expected a too small error"),
+    }
+}
+
+#[test]
+fn mmc1_prg_rom_smaller_than_bank() {
+    let mut rom = NesRom::default();
+    rom.mapper = 1;
+    rom.prg = vec![0; 0x2000];
+    match Cartridge::new(rom) {
+        Err(RomError::TooSmall(_)) => (),
+        _ => panic!("expected a too small error"),
+    }
+}
+
+#[test]
+fn discrete_mapper_prg_rom_smaller_than_bank() {
+    for &(mapper, bank_size) in &[(2, 0x4000), (3, 0x4000), (7, 0x8000), (11, 0x8000),
+                                  (34, 0x8000), (66, 0x8000)] {
+        let mut rom = NesRom::default();
+        rom.mapper = mapper;
+        rom.prg = vec![0; bank_size / 2];
+        match Cartridge::new(rom) {
+            Err(RomError::TooSmall(_)) => (),
+            _ => panic!("expected a too small error for mapper {}", mapper),
+        }
+    }
+}
+
+#[test]
+fn color_dreams_chr_rom_smaller_than_bank() {
+    let mut rom = NesRom::default();
+    rom.mapper = 11;
+    rom.prg = vec![0; 0x8000];
+    rom.chr = vec![0; 0x1000];
+    match Cartridge::new(rom) {
+        Err(RomError::TooSmall(_)) => (),
+        _ => panic!("expected a too small error"),
+    }
+}
+
+#[test]
+fn chr_rom_smaller_than_pattern_tables() {
+    let mut rom = NesRom::default();
+    rom.prg = vec![0; 0x4000];
+    rom.chr = vec![0; 0x1000];
+    match Cartridge::new(rom) {
+        Err(RomError::TooSmall(_)) => (),
+        _ => panic!("expected a too small error"),
+    }
+}
+
+#[test]
+fn trainer_is_loaded_at_7000() {
+    let mut rom = NesRom::default();
+    rom.prg = vec![0; 0x8000];
+    rom.chr = vec![0; 0x2000];
+    rom.prg_ram_banks = 1;
+    rom.trainer = (0..0x200).map(|i| i as u8).collect();
+    let cart = Cartridge::new(rom).unwrap();
+    assert_eq!(0x00, cart.prg_read(0x6fff));
+    assert_eq!(0x00, cart.prg_read(0x7000));
+    assert_eq!(0x01, cart.prg_read(0x7001));
+    assert_eq!(0xff, cart.prg_read(0x71ff));
+    assert_eq!(0x00, cart.prg_read(0x7200));
+}
+
+#[test]
+fn trainer_adds_prg_ram() {
+    let mut rom = NesRom::default();
+    rom.prg = vec![0; 0x8000];
+    rom.chr = vec![0; 0x2000];
+    rom.trainer = vec![0x5a; 0x200];
+    let cart = Cartridge::new(rom).unwrap();
+    assert_eq!(0x5a, cart.prg_read(0x7000));
+}
+
+#[test]
+fn trainer_on_board_without_prg_ram() {
+    let mut rom = NesRom::default();
+    rom.mapper = 2;
+    rom.prg = vec![0; 0x8000];
+    rom.trainer = vec![0x5a; 0x200];
+    match Cartridge::new(rom) {
+        Err(RomError::UnsupportedTrainer) => (),
+        _ => panic!("expected an unsupported trainer error"),
+    }
+}
+
+#[test]
+fn battery_backed_prg_ram_round_trip() {
+    let path = temp_save_path("round_trip");
+    let cart = cart_fixture(true);
+    cart.prg_write(0x6000, 0x11);
+    cart.prg_write(0x7fff, 0x22);
+    cart.flush_save(&path).unwrap();
+
+    let cart = cart_fixture(true);
+    cart.load_save(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(0x11, cart.prg_read(0x6000));
+    assert_eq!(0x22, cart.prg_read(0x7fff));
+}
+
+#[test]
+fn missing_save_file_is_not_an_error() {
+    let cart = cart_fixture(true);
+    assert_eq!(Ok(()), cart.load_save(temp_save_path("missing")));
+    assert_eq!(0, cart.prg_read(0x6000));
+}
+
+#[test]
+fn no_save_file_without_battery() {
+    let path = temp_save_path("no_battery");
+    let cart = cart_fixture(false);
+    cart.flush_save(&path).unwrap();
+    assert_eq!(false, path.exists());
+}
+
+fn temp_save_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("rs_nes_{}_{}.sav", name, process::id()))
+}
+
+fn cart_fixture(has_battery: bool) -> Cartridge {
+    let mut rom = NesRom::default();
+    rom.prg = vec![0; 0x8000];
+    rom.chr = vec![0; 0x2000];
+    rom.prg_ram_banks = 1;
+    rom.has_sram = has_battery;
+    Cartridge::new(rom).unwrap()
+}
+
+#[test]
+fn fds_bios_must_be_8kb() {
+    let image = FdsImage { sides: Vec::new() };
+    match Cartridge::from_fds(vec![0; 0x1000], image) {
+        Err(RomError::BadBios) => (),
+        _ => panic!("expected a bad BIOS error"),
+    }
+}
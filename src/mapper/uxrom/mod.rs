@@ -0,0 +1,62 @@
+//! UxROM (mapper 2). A switchable 16KB PRG bank at $8000 and the last bank fixed at $C000. The
+//! bank register is a latch over all of $8000-$FFFF and is subject to bus conflicts.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::{Chr, Mapper};
+use rom::{Mirroring, NesRom};
+
+const PRG_BANK_SIZE: usize = 0x4000;
+
+pub struct Uxrom {
+    prg: Vec<u8>,
+    chr: Chr,
+    mirroring: Mirroring,
+    prg_bank: u8,
+}
+
+impl Uxrom {
+    pub fn new(rom: NesRom) -> Self {
+        Uxrom {
+            prg: rom.prg,
+            chr: Chr::new(rom.chr, rom.chr_ram_banks),
+            mirroring: rom.mirroring,
+            prg_bank: 0,
+        }
+    }
+}
+
+impl Mapper for Uxrom {
+    fn prg_read(&self, addr: u16) -> u8 {
+        if addr < 0x8000 {
+            return 0;
+        }
+
+        let bank_count = self.prg.len() / PRG_BANK_SIZE;
+        let bank = if addr < 0xc000 {
+            self.prg_bank as usize % bank_count
+        } else {
+            bank_count - 1
+        };
+        self.prg[bank * PRG_BANK_SIZE + (addr as usize & 0x3fff)]
+    }
+
+    fn prg_write(&mut self, addr: u16, val: u8) {
+        if addr >= 0x8000 {
+            self.prg_bank = val & self.prg_read(addr);
+        }
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr.read(addr as usize)
+    }
+
+    fn chr_write(&mut self, addr: u16, val: u8) {
+        self.chr.write(addr as usize, val)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
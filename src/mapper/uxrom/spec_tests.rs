@@ -0,0 +1,45 @@
+use super::*;
+use rom::NesRom;
+
+#[test]
+fn switchable_bank() {
+    let mut uxrom = uxrom_fixture();
+    assert_eq!(0xf0, uxrom.prg_read(0x8000));
+    uxrom.prg_write(0xffff, 5);
+    assert_eq!(0xf5, uxrom.prg_read(0x8000));
+    assert_eq!(0xf5, uxrom.prg_read(0xbfff));
+}
+
+#[test]
+fn last_bank_fixed() {
+    let mut uxrom = uxrom_fixture();
+    uxrom.prg_write(0xffff, 5);
+    assert_eq!(0xf7, uxrom.prg_read(0xc000));
+    assert_eq!(0xf7, uxrom.prg_read(0xffff));
+}
+
+#[test]
+fn bus_conflicts() {
+    let mut uxrom = uxrom_fixture();
+    // The ROM drives $F0 at $8000 while bank 0 is selected, which is ANDed with the written value
+    uxrom.prg_write(0x8000, 5);
+    assert_eq!(0xf0, uxrom.prg_read(0x8000));
+}
+
+#[test]
+fn chr_ram() {
+    let mut rom = NesRom::default();
+    rom.prg = vec![0; 0x8000];
+    let mut uxrom = Uxrom::new(rom);
+    uxrom.chr_write(0x0800, 0x55);
+    assert_eq!(0x55, uxrom.chr_read(0x0800));
+}
+
+// 8 16KB PRG banks, each filled with $F0 OR'd with its bank number
+fn uxrom_fixture() -> Uxrom {
+    let mut rom = NesRom::default();
+    rom.mapper = 2;
+    rom.prg = (0..0x20000).map(|i| 0xf0 | (i / PRG_BANK_SIZE) as u8).collect();
+    rom.chr = vec![0; 0x2000];
+    Uxrom::new(rom)
+}
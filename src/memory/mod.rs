@@ -1,26 +1,47 @@
 pub mod nes_memory;
+pub mod nsf_memory;
 mod simple_memory;
 
 pub use self::simple_memory::SimpleMemory;
 use audio::Audio;
+use cheat::Cheats;
 use cpu::Interrupt;
 use input::Input;
 use screen::Screen;
 use std::io::Write;
 
 pub const ADDRESSABLE_MEMORY: usize = 65536;
+pub const PPU_ADDRESSABLE_MEMORY: usize = 16384;
 
 pub trait Memory<I: Input, S: Screen, A: Audio> {
+    /// Clocks everything on the bus for one CPU cycle, returning `Interrupt::Nmi` if an NMI edge
+    /// occurred. IRQs are level triggered, and are reported by `irq_line` instead.
     fn tick(&mut self) -> Interrupt {
         Interrupt::None
     }
+
+    /// Whether any device is currently holding the shared IRQ line
+    fn irq_line(&self) -> bool {
+        false
+    }
+
+    /// Takes the number of cycles the CPU has been halted for by DMC sample fetches since the
+    /// last call. The bus has already been clocked for them.
+    fn take_stall_cycles(&mut self) -> u64 {
+        0
+    }
+
     fn write(&mut self, u16, u8, u64) -> u64;
     fn read(&self, u16) -> u8;
     fn screen(&self) -> &S;
     fn input(&self) -> &I;
     fn audio(&self) -> &A;
     fn dump<T: Write>(&self, writer: &mut T);
+    fn dump_vram<T: Write>(&self, _: &mut T) {}
     fn hash(&self) -> u64 {
         0
     }
+    fn cheats_mut(&mut self) -> Option<&mut Cheats> {
+        None
+    }
 }
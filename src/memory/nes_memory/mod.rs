@@ -3,23 +3,23 @@ mod spec_tests;
 
 use super::Memory;
 use apu::{Apu, ApuContract};
+use cheat::Cheats;
 use cpu::Interrupt;
 use input::{Input, InputBase};
+use mapper::Cartridge;
 use ppu::{Ppu, PpuImpl};
-use rom::NesRom;
 use screen::NesScreen;
 
 #[cfg(feature = "debugger")]
 use seahash;
 use std::io::Write;
-use std::rc::Rc;
 
 macro_rules! dma_tick {
     ( $mem : expr ) => {
         {
-            let tick_action = $mem.tick();
-            if tick_action != Interrupt::None {
-                panic!("unimplemented: nmi during dma")
+            // The CPU is halted, so an NMI raised during DMA is held until it resumes
+            if $mem.tick() == Interrupt::Nmi {
+                $mem.dma_nmi = true;
             }
         }
     };
@@ -29,28 +29,41 @@ pub type NesMemoryImpl = NesMemoryBase<PpuImpl, Apu, InputBase>;
 
 pub struct NesMemoryBase<P: Ppu, A: ApuContract, I: Input> {
     ram: [u8; 0x800],
-    rom: Rc<Box<NesRom>>,
+    cart: Cartridge,
     ppu: P,
     apu: A,
     input: I,
+    cheats: Cheats,
+    dma_nmi: bool,
+    oam_dma_active: bool,
+    stall_cycles: u64,
 }
 
 impl<P: Ppu<Scr = NesScreen>, A: ApuContract, I: Input> NesMemoryBase<P, A, I> {
-    pub fn new(rom: Rc<Box<NesRom>>, ppu: P, input: I, apu: A) -> Self {
+    pub fn new(cart: Cartridge, ppu: P, input: I, apu: A) -> Self {
         NesMemoryBase {
             ram: [0_u8; 0x800],
-            rom: rom,
+            cart: cart,
             ppu: ppu,
             apu: apu,
             input: input,
+            cheats: Cheats::default(),
+            dma_nmi: false,
+            oam_dma_active: false,
+            stall_cycles: 0,
         }
     }
 
+    // The CPU halts for a cycle after the write, then DMA alternates between reading on get cycles
+    // and writing on put cycles. If the write was on a put cycle, the halt cycle lands on a get
+    // cycle and an extra cycle is needed to align the reads.
     fn dma_write(&mut self, value: u8, cycles: u64) -> u64 {
         let mut elapsed_cycles = 513;
+        let written_on_put_cycle = self.apu.is_put_cycle();
+        self.oam_dma_active = true;
         dma_tick!(self);
 
-        if cycles % 2 == 1 {
+        if written_on_put_cycle {
             dma_tick!(self);
             elapsed_cycles += 1;
         }
@@ -62,11 +75,35 @@ impl<P: Ppu<Scr = NesScreen>, A: ApuContract, I: Input> NesMemoryBase<P, A, I> {
             self.write(0x2004, val, cycles + 1);
             dma_tick!(self);
         }
+        self.oam_dma_active = false;
         elapsed_cycles
     }
+
+    // A DMC sample fetch halts the CPU for four cycles, reading on the last of them. During OAM
+    // DMA the CPU is already halted, so the fetch only steals an alignment cycle and its read.
+    fn dmc_fetch(&mut self, address: u16) {
+        let fetch_cycles = if self.oam_dma_active { 2 } else { 4 };
+        for _ in 1..fetch_cycles {
+            dma_tick!(self);
+        }
+        let val = self.read(address);
+        dma_tick!(self);
+        self.apu.dmc_fill(val);
+        self.stall_cycles += fetch_cycles;
+    }
+
+    // Pro Action Replay codes freeze RAM by rewriting it at every NMI
+    fn apply_freezes(&mut self) {
+        for (address, value) in self.cheats.freezes() {
+            if address < 0x2000 {
+                self.ram[address as usize & 0x7ff] = value;
+            } else {
+                self.cart.prg_write(address, value);
+            }
+        }
+    }
 }
 
-// Currently NROM only
 impl<P: Ppu<Scr = NesScreen>, A: ApuContract, I: Input> Memory<I, NesScreen, A>
     for NesMemoryBase<P, A, I> {
     fn tick(&mut self) -> Interrupt {
@@ -81,16 +118,34 @@ impl<P: Ppu<Scr = NesScreen>, A: ApuContract, I: Input> Memory<I, NesScreen, A>
             };
         }
 
-        let apu_action = self.apu.half_step();
+        if ppu_action == Interrupt::Nmi {
+            self.apply_freezes();
+        }
 
-        // TODO: What do we do if PPU and APU both generate an interrupt?
-        let tick_action = if ppu_action != Interrupt::None {
-            ppu_action
+        self.apu.half_step();
+        self.cart.tick();
+        self.apu.set_expansion_audio(self.cart.expansion_audio());
+
+        if let Some(address) = self.apu.dmc_fetch_request() {
+            self.dmc_fetch(address);
+        }
+
+        if self.dma_nmi {
+            self.dma_nmi = false;
+            Interrupt::Nmi
         } else {
-            apu_action
-        };
+            ppu_action
+        }
+    }
 
-        tick_action
+    fn irq_line(&self) -> bool {
+        self.apu.irq_pending() || self.cart.irq_pending()
+    }
+
+    fn take_stall_cycles(&mut self) -> u64 {
+        let stall_cycles = self.stall_cycles;
+        self.stall_cycles = 0;
+        stall_cycles
     }
 
     fn write(&mut self, address: u16, value: u8, cycles: u64) -> u64 {
@@ -105,8 +160,8 @@ impl<P: Ppu<Scr = NesScreen>, A: ApuContract, I: Input> Memory<I, NesScreen, A>
             self.input.write(address, value)
         } else if address < 0x4018 {
             self.apu.write(address, value)
-        } else {
-            panic!("Unimplemented write to 0x{:0>4X}", address);
+        } else if address >= 0x4020 {
+            self.cart.prg_write(address, value)
         }
         addl_cycles
     }
@@ -120,16 +175,12 @@ impl<P: Ppu<Scr = NesScreen>, A: ApuContract, I: Input> Memory<I, NesScreen, A>
             self.apu.read_status()
         } else if address == 0x4016 {
             self.input.read(address)
-        } else if address < 0x4018 {
+        } else if address < 0x4020 {
             0
-        } else if address < 0x8000 {
-            panic!("Read from 0x{:0>4X}", address);
+        } else if address >= 0x8000 {
+            self.cheats.substitute(address, self.cart.prg_read(address))
         } else {
-            if self.rom.prg.len() > 16384 {
-                self.rom.prg[address as usize & 0x7fff]
-            } else {
-                self.rom.prg[address as usize & 0x3fff]
-            }
+            self.cart.prg_read(address)
         };
         val
     }
@@ -149,23 +200,23 @@ impl<P: Ppu<Scr = NesScreen>, A: ApuContract, I: Input> Memory<I, NesScreen, A>
         // 0x4000 to 0x401f (APU and IO regs placeholder)
         writer.write_all(&[0_u8; 0x20]).unwrap();
 
-        // Not sure what goes here, but gotta pad it for now to have correct ROM size
-        writer.write_all(&[0_u8; 16352]).unwrap();
+        // 0x4020 to 0xFFFF (cartridge space)
+        let cart_space = (0x4020..0x10000_u32)
+            .map(|addr| self.cart.prg_read(addr as u16))
+            .collect::<Vec<u8>>();
+        writer.write_all(&cart_space).unwrap();
+    }
 
-        // 0x6000 to 0xFFFF
-        if self.rom.prg.len() > 0x4000 {
-            writer.write_all(&self.rom.prg).unwrap();
-        } else {
-            // PRG is mirrored if only one bank
-            writer.write_all(&self.rom.prg).unwrap();
-            writer.write_all(&self.rom.prg).unwrap();
-        }
+    fn dump_vram<T: Write>(&self, writer: &mut T) {
+        self.ppu.dump_vram(writer)
     }
 
     #[cfg(feature = "debugger")]
     fn hash(&self) -> u64 {
-        // Hashing just the ram will suffice for now...
-        seahash::hash(&self.ram)
+        // Include VRAM so that tiles uploaded to CHR-RAM show up as a memory change
+        let mut buf = self.ram.to_vec();
+        self.ppu.dump_vram(&mut buf);
+        seahash::hash(&buf)
     }
 
     fn screen(&self) -> &NesScreen {
@@ -178,4 +229,8 @@ impl<P: Ppu<Scr = NesScreen>, A: ApuContract, I: Input> Memory<I, NesScreen, A>
     fn audio(&self) -> &A {
         &self.apu
     }
+
+    fn cheats_mut(&mut self) -> Option<&mut Cheats> {
+        Some(&mut self.cheats)
+    }
 }
@@ -1,4 +1,5 @@
-use self::mocks::new_fixture;
+use self::mocks::{new_fixture, new_fixture_with_prg};
+use cpu::{Cpu, Interrupt, StepResult};
 use memory::Memory;
 
 #[test]
@@ -49,8 +50,8 @@ fn ppu_memory_mapped_write() {
 
 #[test]
 fn apu_memory_mapped_read() {
-    let fixture = new_fixture();
-    fixture.apu.write().unwrap().set_status(0xff);
+    let mut fixture = new_fixture();
+    fixture.apu.set_status(0xff);
     // Only a single APU address is readable
     let val = fixture.read(0x4015);
     assert_eq!(0xff, val);
@@ -62,21 +63,21 @@ fn apu_memory_mapped_write() {
 
     for addr in 0x4000..0x4014_u16 {
         fixture.write(addr, 0xff, 0);
-        assert_eq!(addr, fixture.apu.write().unwrap().write_addr());
-        assert_eq!(0xff, fixture.apu.write().unwrap().write_value());
+        assert_eq!(addr, fixture.apu.write_addr());
+        assert_eq!(0xff, fixture.apu.write_value());
     }
     // Skip 0x4014, since it's ppu DMA address
 
     fixture.write(0x4015, 0xff, 0);
-    assert_eq!(0x4015, fixture.apu.write().unwrap().write_addr());
-    assert_eq!(0xff, fixture.apu.write().unwrap().write_value());
+    assert_eq!(0x4015, fixture.apu.write_addr());
+    assert_eq!(0xff, fixture.apu.write_value());
 
     // Skip 0x4016 since it's input probe register
 
     for addr in 0x4017..0x4018_u16 {
         fixture.write(addr, 0xff, 0);
-        assert_eq!(addr, fixture.apu.write().unwrap().write_addr());
-        assert_eq!(0xff, fixture.apu.write().unwrap().write_value());
+        assert_eq!(addr, fixture.apu.write_addr());
+        assert_eq!(0xff, fixture.apu.write_value());
     }
 }
 
@@ -92,28 +93,124 @@ fn input_memory_mapped_write() {
     // TODO: reimplement
 }
 
+#[test]
+fn cart_memory_mapped_read() {
+    let fixture = new_fixture();
+    for addr in 0x8000..0xc000_u32 {
+        let addr = addr as u16;
+        assert_eq!(addr as u8, fixture.read(addr));
+        assert_eq!(addr as u8, fixture.read(addr + 0x4000));
+    }
+}
+
+#[test]
+fn cart_memory_mapped_write() {
+    let mut fixture = new_fixture();
+    fixture.write(0x6000, 0xff, 0);
+    assert_eq!(0xff, fixture.read(0x6000));
+}
+
 #[test]
 fn oam_dma_timing() {
     let mut fixture = new_fixture();
     let addl_cycles = fixture.write(0x4014, 0x02, 0);
     assert_eq!(513, addl_cycles);
 
-    let addl_cycles = fixture.write(0x4014, 0x02, 1);
+    // The first DMA took an odd number of cycles, so this one is written on a put cycle
+    let addl_cycles = fixture.write(0x4014, 0x02, 513);
     assert_eq!(514, addl_cycles);
 }
 
+#[test]
+fn oam_dma_aligns_to_get_cycle() {
+    // STA $4014 writes on cycle 3, so the halt cycle lands on a get cycle and needs alignment
+    let mut cpu = Cpu::new_init_pc(new_fixture_with_prg(&[0x8d, 0x14, 0x40]), 0x8000);
+    cpu.step();
+    assert_eq!(4 + 514, cpu.cycles);
+
+    // LDA $00; STA $4014 writes on cycle 6, so the halt cycle is already a put cycle
+    let mut cpu = Cpu::new_init_pc(new_fixture_with_prg(&[0xa5, 0x00, 0x8d, 0x14, 0x40]), 0x8000);
+    cpu.step();
+    cpu.step();
+    assert_eq!(3 + 4 + 513, cpu.cycles);
+}
+
+#[test]
+fn oam_dma_holds_nmi_until_cpu_resumes() {
+    let mut fixture = new_fixture();
+    fixture.ppu.set_nmi_after(300);
+    fixture.write(0x4014, 0x02, 0);
+    assert_eq!(Interrupt::Nmi, fixture.tick());
+    assert_eq!(Interrupt::None, fixture.tick());
+}
+
+#[test]
+fn oam_dma_nmi_is_serviced_after_next_instruction() {
+    let mut memory = new_fixture_with_prg(&[0x8d, 0x14, 0x40, 0xea]);
+    memory.ppu.set_nmi_after(300);
+    let mut cpu = Cpu::new_init_pc(memory, 0x8000);
+
+    // The NMI is raised while DMA has the CPU halted, after STA polled for interrupts
+    assert_eq!(StepResult::Executed(Interrupt::None), cpu.step());
+    assert_eq!(StepResult::Executed(Interrupt::Nmi), cpu.step());
+}
+
+#[test]
+fn dmc_fetch_stalls_cpu() {
+    let mut fixture = new_fixture();
+    fixture.apu.request_dmc_fetch(0xc001);
+    fixture.tick();
+    assert_eq!(Some(0x01), fixture.apu.dmc_byte());
+    assert_eq!(4, fixture.take_stall_cycles());
+    assert_eq!(0, fixture.take_stall_cycles());
+}
+
+#[test]
+fn dmc_fetch_during_oam_dma_steals_two_cycles() {
+    let mut fixture = new_fixture();
+    fixture.apu.request_dmc_fetch(0xc001);
+    assert_eq!(513, fixture.write(0x4014, 0x02, 0));
+    assert_eq!(Some(0x01), fixture.apu.dmc_byte());
+    assert_eq!(2, fixture.take_stall_cycles());
+}
+
+#[test]
+fn game_genie_substitutes_cart_reads() {
+    let mut fixture = new_fixture();
+    fixture.cheats_mut().unwrap().add("SXIOPO").unwrap();
+    assert_eq!(0xad, fixture.read(0x91d9));
+    assert_eq!(0xda, fixture.read(0x91da));
+
+    fixture.cheats_mut().unwrap().toggle(0);
+    assert_eq!(0xd9, fixture.read(0x91d9));
+}
+
+#[test]
+fn pro_action_replay_freezes_at_nmi() {
+    let mut fixture = new_fixture();
+    fixture.cheats_mut().unwrap().add("075A09").unwrap();
+    fixture.cheats_mut().unwrap().add("6000FF").unwrap();
+    fixture.tick();
+    assert_eq!(0x00, fixture.read(0x075a));
+
+    fixture.ppu.set_nmi(true);
+    fixture.tick();
+    assert_eq!(0x09, fixture.read(0x075a));
+    assert_eq!(0xff, fixture.read(0x6000));
+}
+
 mod mocks {
     use apu::ApuContract;
     use audio::Audio;
+    use cheat::Cheats;
     use cpu::Interrupt;
     use input::{Button, Input};
+    use mapper::Cartridge;
     use memory::nes_memory::NesMemoryBase;
     use ppu::Ppu;
     use rom::*;
     use screen::NesScreen;
     use std::io::Write;
-    use std::rc::Rc;
-    use std::sync::{Arc, RwLock};
 
     #[derive(Default)]
     pub struct InputMock;
@@ -139,6 +236,9 @@ mod mocks {
         write_addr: u16,
         write_value: u8,
         status: u8,
+        dmc_request: Option<u16>,
+        dmc_byte: Option<u8>,
+        put_cycle: bool,
     }
 
     impl ApuMock {
@@ -153,6 +253,14 @@ mod mocks {
         pub fn set_status(&mut self, val: u8) {
             self.status = val;
         }
+
+        pub fn request_dmc_fetch(&mut self, addr: u16) {
+            self.dmc_request = Some(addr);
+        }
+
+        pub fn dmc_byte(&self) -> Option<u8> {
+            self.dmc_byte
+        }
     }
 
     impl ApuContract for ApuMock {
@@ -165,22 +273,31 @@ mod mocks {
             self.status
         }
 
-        fn half_step(&mut self) -> Interrupt {
-            Interrupt::None
+        fn half_step(&mut self) {
+            self.put_cycle = !self.put_cycle;
         }
-    }
 
-    impl Audio for ApuMock {
-        fn sample(&self) -> f32 {
-            0.0
+        fn is_put_cycle(&self) -> bool {
+            self.put_cycle
+        }
+
+        fn dmc_fetch_request(&mut self) -> Option<u16> {
+            self.dmc_request.take()
+        }
+
+        fn dmc_fill(&mut self, val: u8) {
+            self.dmc_byte = Some(val);
         }
     }
 
+    impl Audio for ApuMock {}
+
     #[derive(Default)]
     pub struct PpuMock {
         addr: u16,
         value: u8,
         screen: NesScreen,
+        nmi_in: Option<u32>,
     }
 
     impl PpuMock {
@@ -195,6 +312,14 @@ mod mocks {
         pub fn set_value(&mut self, value: u8) {
             self.value = value;
         }
+
+        pub fn set_nmi(&mut self, nmi: bool) {
+            self.nmi_in = if nmi { Some(0) } else { None };
+        }
+
+        pub fn set_nmi_after(&mut self, steps: u32) {
+            self.nmi_in = Some(steps);
+        }
     }
 
     impl Ppu for PpuMock {
@@ -211,14 +336,28 @@ mod mocks {
         }
 
         fn step(&mut self) -> Interrupt {
-            Interrupt::None
+            match self.nmi_in {
+                Some(0) => {
+                    self.nmi_in = None;
+                    Interrupt::Nmi
+                }
+                Some(steps) => {
+                    self.nmi_in = Some(steps - 1);
+                    Interrupt::None
+                }
+                None => Interrupt::None,
+            }
         }
 
         fn dump_registers<T: Write>(&self, _: &mut T) {
             unimplemented!()
         }
 
-        fn new(_: Rc<Box<NesRom>>) -> Self {
+        fn dump_vram<T: Write>(&self, _: &mut T) {
+            unimplemented!()
+        }
+
+        fn new(_: Cartridge) -> Self {
             unimplemented!()
         }
 
@@ -230,29 +369,31 @@ mod mocks {
     pub type NesMemoryFixture = NesMemoryBase<PpuMock, ApuMock, InputMock>;
 
     pub fn new_fixture() -> NesMemoryFixture {
-        let rom = Rc::new(Box::new(NesRom {
-                                       format: RomFormat::INes,
-                                       video_standard: VideoStandard::Ntsc,
-                                       mapper: 0,
-                                       mirroring: Mirroring::Horizontal,
-                                       prg_rom_banks: 1,
-                                       prg_ram_banks: 1,
-                                       chr_rom_banks: 1,
-                                       has_sram: false,
-                                       has_trainer: false,
-                                       is_pc10: false,
-                                       is_vs_unisystem: false,
-                                       trainer: Vec::new(),
-                                       chr: Vec::new(),
-                                       prg: Vec::new(),
-                                   }));
+        new_fixture_with_prg(&[])
+    }
+
+    // Builds a fixture whose PRG starts with `program` at $8000, followed by a counting pattern
+    pub fn new_fixture_with_prg(program: &[u8]) -> NesMemoryFixture {
+        let mut prg: Vec<u8> = (0..0x4000).map(|i| i as u8).collect();
+        prg[..program.len()].copy_from_slice(program);
+        let rom = NesRom {
+            prg_rom_banks: 1,
+            prg_ram_banks: 1,
+            chr_rom_banks: 1,
+            prg: prg,
+            ..NesRom::default()
+        };
 
         NesMemoryBase {
             ram: [0_u8; 0x800],
-            rom: rom,
+            cart: Cartridge::new(rom).unwrap(),
             ppu: PpuMock::default(),
-            apu: Arc::new(RwLock::new(ApuMock::default())),
+            apu: ApuMock::default(),
             input: InputMock::default(),
+            cheats: Cheats::default(),
+            dma_nmi: false,
+            oam_dma_active: false,
+            stall_cycles: 0,
         }
     }
 }
@@ -0,0 +1,189 @@
+//! The CPU bus for playing NSF tunes. There is no PPU, so $2000-$3FFF is free to hold a small
+//! driver routine that calls the tune's INIT routine once, then idles. PLAY is called from the
+//! driver's NMI handler, and NMIs are generated at the tune's play rate instead of by the PPU.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::Memory;
+use apu::ApuContract;
+use cpu::Interrupt;
+use input::NoInput;
+use mapper::Mapper;
+use mapper::nsf::Nsf;
+use rom::nsf::NsfFile;
+use screen::NoScreen;
+use std::io::Write;
+
+const DRIVER_START: u16 = 0x3f00;
+const DRIVER_NMI: u16 = 0x3f12;
+
+// Written to by the driver when INIT or PLAY returns
+const DRIVER_IDLE_PORT: u16 = 0x3f80;
+
+// Offsets of the operands patched into the driver for each song
+const SONG_OPERAND: usize = 0x06;
+const REGION_OPERAND: usize = 0x08;
+
+const DRIVER: [u8; 0x19] = [0x78, // $3F00: SEI
+                            0xd8, // $3F01: CLD
+                            0xa2, 0xff, // $3F02: LDX #$FF
+                            0x9a, // $3F04: TXS
+                            0xa9, 0x00, // $3F05: LDA #song
+                            0xa2, 0x00, // $3F07: LDX #region
+                            0x20, 0x00, 0x00, // $3F09: JSR init
+                            0x8d, 0x80, 0x3f, // $3F0C: STA $3F80
+                            0x4c, 0x0f, 0x3f, // $3F0F: JMP $3F0F
+                            0x20, 0x00, 0x00, // $3F12: JSR play
+                            0x8d, 0x80, 0x3f, // $3F15: STA $3F80
+                            0x40 /* $3F18: RTI */];
+
+pub struct NsfMemory<A: ApuContract> {
+    ram: [u8; 0x800],
+    driver: [u8; 0x19],
+    mapper: Nsf,
+    apu: A,
+    screen: NoScreen,
+    input: NoInput,
+    play_period: u32,
+    play_timer: u32,
+    play_pending: bool,
+    idle: bool,
+}
+
+impl<A: ApuContract> NsfMemory<A> {
+    /// `play_period` is the number of CPU cycles between calls to PLAY
+    pub fn new(nsf: &NsfFile, apu: A, play_period: u32) -> Self {
+        let mut driver = DRIVER;
+        driver[0x0a] = nsf.init_address as u8;
+        driver[0x0b] = (nsf.init_address >> 8) as u8;
+        driver[0x13] = nsf.play_address as u8;
+        driver[0x14] = (nsf.play_address >> 8) as u8;
+
+        NsfMemory {
+            ram: [0; 0x800],
+            driver: driver,
+            mapper: Nsf::new(nsf),
+            apu: apu,
+            screen: NoScreen,
+            input: NoInput,
+            play_period: play_period,
+            play_timer: 0,
+            play_pending: false,
+            idle: false,
+        }
+    }
+
+    /// Prepares the machine to start a song, which begins on the next CPU reset. `song` starts at
+    /// 0, and `region` is 0 for NTSC or 1 for PAL.
+    pub fn start_song(&mut self, song: u8, region: u8) {
+        self.driver[SONG_OPERAND] = song;
+        self.driver[REGION_OPERAND] = region;
+        self.ram = [0; 0x800];
+        self.mapper.reset();
+
+        for addr in 0x4000..0x4014 {
+            self.apu.write(addr, 0);
+        }
+        self.apu.write(0x4015, 0x00);
+        self.apu.write(0x4015, 0x0f);
+        self.apu.write(0x4017, 0x40);
+
+        self.play_timer = 0;
+        self.play_pending = false;
+        self.idle = false;
+    }
+
+    pub fn apu_mut(&mut self) -> &mut A {
+        &mut self.apu
+    }
+}
+
+impl<A: ApuContract> Memory<NoInput, NoScreen, A> for NsfMemory<A> {
+    fn tick(&mut self) -> Interrupt {
+        self.apu.half_step();
+        self.mapper.tick();
+        self.apu.set_expansion_audio(self.mapper.expansion_audio());
+
+        // Tunes aren't timed against the CPU stall of a sample fetch, so the read is immediate
+        if let Some(address) = self.apu.dmc_fetch_request() {
+            let val = self.read(address);
+            self.apu.dmc_fill(val);
+        }
+
+        self.play_timer += 1;
+        if self.play_timer >= self.play_period {
+            self.play_timer = 0;
+            self.play_pending = true;
+        }
+
+        // A PLAY call that runs long delays the next one rather than interrupting itself
+        if self.play_pending && self.idle {
+            self.play_pending = false;
+            self.idle = false;
+            Interrupt::Nmi
+        } else {
+            Interrupt::None
+        }
+    }
+
+    fn irq_line(&self) -> bool {
+        self.apu.irq_pending() || self.mapper.irq_pending()
+    }
+
+    fn write(&mut self, address: u16, value: u8, _: u64) -> u64 {
+        if address < 0x2000 {
+            self.ram[address as usize & 0x7ff] = value
+        } else if address == DRIVER_IDLE_PORT {
+            self.idle = true
+        } else if address == 0x4014 || address == 0x4016 {
+            // No OAM or controllers to write to
+        } else if address >= 0x4000 && address < 0x4018 {
+            self.apu.write(address, value)
+        } else if address >= 0x4020 {
+            self.mapper.prg_write(address, value)
+        }
+        0
+    }
+
+    fn read(&self, address: u16) -> u8 {
+        let driver_end = DRIVER_START + self.driver.len() as u16;
+        if address < 0x2000 {
+            self.ram[address as usize & 0x7ff]
+        } else if address >= DRIVER_START && address < driver_end {
+            self.driver[(address - DRIVER_START) as usize]
+        } else if address == 0x4015 {
+            self.apu.read_status()
+        } else if address < 0x4020 {
+            0
+        } else {
+            // The NMI and reset vectors point at the driver regardless of the tune's data
+            match address {
+                0xfffa => DRIVER_NMI as u8,
+                0xfffb => (DRIVER_NMI >> 8) as u8,
+                0xfffc => DRIVER_START as u8,
+                0xfffd => (DRIVER_START >> 8) as u8,
+                _ => self.mapper.prg_read(address),
+            }
+        }
+    }
+
+    fn dump<T: Write>(&self, writer: &mut T) {
+        let mem = (0..0x10000_u32)
+            .map(|addr| self.read(addr as u16))
+            .collect::<Vec<u8>>();
+        writer.write_all(&mem).unwrap();
+    }
+
+    fn screen(&self) -> &NoScreen {
+        &self.screen
+    }
+
+    fn input(&self) -> &NoInput {
+        &self.input
+    }
+
+    fn audio(&self) -> &A {
+        &self.apu
+    }
+}
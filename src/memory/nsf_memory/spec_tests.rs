@@ -0,0 +1,69 @@
+use super::*;
+use apu::Apu;
+use rom::VideoStandard;
+
+#[test]
+fn vectors_point_at_driver() {
+    let mem = memory_fixture(100);
+    assert_eq!(0x12, mem.read(0xfffa));
+    assert_eq!(0x3f, mem.read(0xfffb));
+    assert_eq!(0x00, mem.read(0xfffc));
+    assert_eq!(0x3f, mem.read(0xfffd));
+}
+
+#[test]
+fn driver_calls_init_and_play() {
+    let mem = memory_fixture(100);
+    assert_eq!(&[0x20, 0x00, 0x80], &mem.driver[0x09..0x0c]);
+    assert_eq!(&[0x20, 0x10, 0x80], &mem.driver[0x12..0x15]);
+    assert_eq!(0x20, mem.read(DRIVER_NMI));
+}
+
+#[test]
+fn start_song_patches_driver_and_clears_ram() {
+    let mut mem = memory_fixture(100);
+    mem.write(0x0000, 0xff, 0);
+    mem.start_song(2, 1);
+    assert_eq!(0x00, mem.read(0x0000));
+    assert_eq!(&[0xa9, 0x02, 0xa2, 0x01], &mem.driver[0x05..0x09]);
+}
+
+#[test]
+fn play_nmi_waits_for_driver_to_idle() {
+    let mut mem = memory_fixture(10);
+    for _ in 0..20 {
+        assert_eq!(Interrupt::None, mem.tick());
+    }
+
+    // Idling once the period has elapsed triggers PLAY straight away
+    mem.write(DRIVER_IDLE_PORT, 0, 0);
+    assert_eq!(Interrupt::Nmi, mem.tick());
+
+    // Then no more until PLAY returns
+    for _ in 0..20 {
+        assert_eq!(Interrupt::None, mem.tick());
+    }
+    mem.write(DRIVER_IDLE_PORT, 0, 0);
+    assert_eq!(Interrupt::Nmi, mem.tick());
+}
+
+fn memory_fixture(play_period: u32) -> NsfMemory<Apu> {
+    let nsf = NsfFile {
+        version: 1,
+        total_songs: 1,
+        starting_song: 1,
+        load_address: 0x8000,
+        init_address: 0x8000,
+        play_address: 0x8010,
+        name: String::new(),
+        artist: String::new(),
+        copyright: String::new(),
+        ntsc_speed: 16639,
+        pal_speed: 19997,
+        bankswitch_init: [0; 8],
+        video_standard: VideoStandard::Ntsc,
+        expansion_chips: 0,
+        data: vec![0; 0x20],
+    };
+    NsfMemory::new(&nsf, Apu::default(), play_period)
+}
@@ -1,5 +1,6 @@
 use super::{ADDRESSABLE_MEMORY, Memory};
 use audio::NoAudio;
+use cpu::Interrupt;
 use input::NoInput;
 use screen::NoScreen;
 
@@ -12,6 +13,9 @@ pub struct SimpleMemory {
     input: NoInput,
     screen: NoScreen,
     audio: NoAudio,
+    irq_line: bool,
+    nmi_countdown: Option<u64>,
+    irq_countdown: Option<u64>,
 }
 
 impl SimpleMemory {
@@ -21,9 +25,27 @@ impl SimpleMemory {
             input: NoInput,
             screen: NoScreen,
             audio: NoAudio,
+            irq_line: false,
+            nmi_countdown: None,
+            irq_countdown: None,
         }
     }
 
+    /// Asserts or releases the IRQ line, since there are no devices here to do it
+    pub fn set_irq_line(&mut self, asserted: bool) {
+        self.irq_line = asserted;
+    }
+
+    /// Signals an NMI edge at the end of the given number of cycles from now
+    pub fn trigger_nmi_after(&mut self, cycles: u64) {
+        self.nmi_countdown = Some(cycles);
+    }
+
+    /// Asserts the IRQ line at the end of the given number of cycles from now
+    pub fn assert_irq_after(&mut self, cycles: u64) {
+        self.irq_countdown = Some(cycles);
+    }
+
     pub fn store_many(&mut self, addr: u16, data: &[u8]) {
         for (i, byte) in data.iter().enumerate() {
             self.write(addr + i as u16, *byte, 0);
@@ -38,6 +60,18 @@ impl Default for SimpleMemory {
 }
 
 impl Memory<NoInput, NoScreen, NoAudio> for SimpleMemory {
+    fn tick(&mut self) -> Interrupt {
+        if count_down(&mut self.irq_countdown) {
+            self.irq_line = true;
+        }
+
+        if count_down(&mut self.nmi_countdown) {
+            Interrupt::Nmi
+        } else {
+            Interrupt::None
+        }
+    }
+
     fn write(&mut self, addr: u16, data: u8, _: u64) -> u64 {
         let addr = addr as usize;
         self.addr[addr] = data;
@@ -49,6 +83,10 @@ impl Memory<NoInput, NoScreen, NoAudio> for SimpleMemory {
         self.addr[addr]
     }
 
+    fn irq_line(&self) -> bool {
+        self.irq_line
+    }
+
     fn dump<T: Write>(&self, writer: &mut T) {
         writer.write_all(&self.addr).unwrap();
     }
@@ -70,3 +108,18 @@ impl Memory<NoInput, NoScreen, NoAudio> for SimpleMemory {
         &self.audio
     }
 }
+
+// Counts down one cycle, returning true on the cycle that reaches zero
+fn count_down(countdown: &mut Option<u64>) -> bool {
+    match *countdown {
+        Some(1) => {
+            *countdown = None;
+            true
+        }
+        Some(cycles) => {
+            *countdown = Some(cycles - 1);
+            false
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,89 @@
+//! Plays NSF tunes through the APU, without the PPU.
+
+#[cfg(test)]
+mod spec_tests;
+
+use apu::ApuContract;
+use cpu::Cpu;
+use memory::Memory;
+use input::NoInput;
+use memory::nsf_memory::NsfMemory;
+use rom::VideoStandard;
+use rom::nsf::NsfFile;
+use screen::NoScreen;
+
+pub const NTSC_CPU_FREQUENCY: u32 = 1_789_773;
+pub const PAL_CPU_FREQUENCY: u32 = 1_662_607;
+
+pub struct NsfPlayer<A: ApuContract> {
+    cpu: Cpu<NoScreen, NoInput, A, NsfMemory<A>>,
+    total_songs: u8,
+    track: u8,
+    region: u8,
+    cpu_frequency: u32,
+}
+
+impl<A: ApuContract> NsfPlayer<A> {
+    /// Starts playing the tune's starting song. Tunes that support both NTSC and PAL are played at
+    /// NTSC speed.
+    pub fn new(nsf: &NsfFile, apu: A) -> Self {
+        let (region, cpu_frequency, speed) = match nsf.video_standard {
+            VideoStandard::Pal => (1, PAL_CPU_FREQUENCY, nsf.pal_speed),
+            _ => (0, NTSC_CPU_FREQUENCY, nsf.ntsc_speed),
+        };
+        let play_period = (cpu_frequency as u64 * speed as u64 / 1_000_000) as u32;
+
+        let mem = NsfMemory::new(nsf, apu, play_period);
+        let mut player = NsfPlayer {
+            cpu: Cpu::new(mem),
+            total_songs: nsf.total_songs,
+            track: 0,
+            region: region,
+            cpu_frequency: cpu_frequency,
+        };
+        let starting_track = nsf.starting_song.saturating_sub(1);
+        player.select_track(starting_track);
+        player
+    }
+
+    pub fn track_count(&self) -> u8 {
+        self.total_songs
+    }
+
+    /// The song being played, starting at 0
+    pub fn track(&self) -> u8 {
+        self.track
+    }
+
+    /// Restarts playback at the given song, starting at 0. Out of range songs are ignored.
+    pub fn select_track(&mut self, track: u8) {
+        if track >= self.total_songs {
+            return;
+        }
+
+        self.track = track;
+        self.cpu.memory.start_song(track, self.region);
+        self.cpu.reset();
+    }
+
+    /// The CPU clock rate the tune is played at, in Hz
+    pub fn cpu_frequency(&self) -> u32 {
+        self.cpu_frequency
+    }
+
+    /// Runs the CPU for at least the given number of cycles
+    pub fn run(&mut self, cycles: u64) {
+        let end = self.cpu.cycles + cycles;
+        while self.cpu.cycles < end {
+            self.cpu.step();
+        }
+    }
+
+    pub fn apu(&self) -> &A {
+        self.cpu.memory.audio()
+    }
+
+    pub fn apu_mut(&mut self) -> &mut A {
+        self.cpu.memory.apu_mut()
+    }
+}
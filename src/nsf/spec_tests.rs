@@ -0,0 +1,76 @@
+use super::*;
+use apu::Apu;
+use rom::nsf::NsfFile;
+
+// Cycles between PLAY calls at the default NTSC speed
+const PLAY_PERIOD: u64 = 29780;
+
+#[test]
+fn init_is_called_with_song_and_region() {
+    let mut player = NsfPlayer::new(&nsf_fixture(VideoStandard::Ntsc), Apu::default());
+    assert_eq!(1, player.track());
+    player.run(100);
+    assert_eq!(1, player.cpu.memory.read(0x00));
+    assert_eq!(0, player.cpu.memory.read(0x01));
+    assert_eq!(0, player.cpu.memory.read(0x02));
+
+    let mut player = NsfPlayer::new(&nsf_fixture(VideoStandard::Pal), Apu::default());
+    player.run(100);
+    assert_eq!(1, player.cpu.memory.read(0x01));
+}
+
+#[test]
+fn play_is_called_at_play_rate() {
+    let mut player = NsfPlayer::new(&nsf_fixture(VideoStandard::Ntsc), Apu::default());
+    player.run(PLAY_PERIOD + 100);
+    assert_eq!(1, player.cpu.memory.read(0x02));
+    player.run(PLAY_PERIOD * 2);
+    assert_eq!(3, player.cpu.memory.read(0x02));
+}
+
+#[test]
+fn select_track_restarts_playback() {
+    let mut player = NsfPlayer::new(&nsf_fixture(VideoStandard::Ntsc), Apu::default());
+    player.run(PLAY_PERIOD + 100);
+    player.select_track(2);
+    assert_eq!(2, player.track());
+    assert_eq!(0, player.cpu.memory.read(0x02));
+    player.run(100);
+    assert_eq!(2, player.cpu.memory.read(0x00));
+
+    // There are only three songs
+    player.select_track(3);
+    assert_eq!(2, player.track());
+}
+
+#[test]
+fn cpu_frequency() {
+    let player = NsfPlayer::new(&nsf_fixture(VideoStandard::Ntsc), Apu::default());
+    assert_eq!(NTSC_CPU_FREQUENCY, player.cpu_frequency());
+    let player = NsfPlayer::new(&nsf_fixture(VideoStandard::Pal), Apu::default());
+    assert_eq!(PAL_CPU_FREQUENCY, player.cpu_frequency());
+}
+
+// INIT stores the song in $00 and the region in $01, and PLAY increments $02
+fn nsf_fixture(video_standard: VideoStandard) -> NsfFile {
+    let mut data = vec![0; 0x20];
+    data[0x00..0x05].copy_from_slice(&[0x85, 0x00, 0x86, 0x01, 0x60]);
+    data[0x10..0x13].copy_from_slice(&[0xe6, 0x02, 0x60]);
+    NsfFile {
+        version: 1,
+        total_songs: 3,
+        starting_song: 2,
+        load_address: 0x8000,
+        init_address: 0x8000,
+        play_address: 0x8010,
+        name: String::new(),
+        artist: String::new(),
+        copyright: String::new(),
+        ntsc_speed: 16639,
+        pal_speed: 19997,
+        bankswitch_init: [0; 8],
+        video_standard: video_standard,
+        expansion_chips: 0,
+        data: data,
+    }
+}
@@ -13,6 +13,7 @@ mod cycle_table;
 
 use self::write_latch::WriteLatch;
 use cpu::Interrupt;
+use mapper::Cartridge;
 use ppu::background_renderer::BackgroundRenderer;
 use ppu::control_register::ControlRegister;
 use ppu::cycle_table::CYCLE_TABLE;
@@ -20,10 +21,8 @@ use ppu::mask_register::MaskRegister;
 use ppu::sprite_renderer::{SpritePixel, SpritePriority, SpriteRenderer, SpriteRendererBase};
 use ppu::status_register::StatusRegister;
 use ppu::vram::{Vram, VramBase};
-use rom::NesRom;
 use screen::{NesScreen, Screen};
 use std::io::Write;
-use std::rc::Rc;
 
 const SCANLINES: u64 = 262;
 const CYCLES_PER_SCANLINE: u64 = 341;
@@ -34,12 +33,13 @@ pub type PpuImpl = PpuBase<VramBase, SpriteRendererBase>;
 pub trait Ppu {
     type Scr: Screen;
 
-    fn new(rom: Rc<Box<NesRom>>) -> Self;
+    fn new(cart: Cartridge) -> Self;
     fn write(&mut self, addr: u16, val: u8);
     fn read(&self, addr: u16) -> u8;
     fn step(&mut self) -> Interrupt;
     fn screen(&self) -> &NesScreen;
     fn dump_registers<T: Write>(&self, writer: &mut T);
+    fn dump_vram<T: Write>(&self, writer: &mut T);
 }
 
 #[derive(Debug, PartialEq)]
@@ -65,6 +65,7 @@ pub struct PpuBase<V: Vram, S: SpriteRenderer> {
     write_latch: WriteLatch,
     background_renderer: BackgroundRenderer,
     odd_frame: bool,
+    nmi_on_enable: bool,
 }
 
 impl<V: Vram, S: SpriteRenderer> PpuBase<V, S> {
@@ -109,18 +110,19 @@ impl<V: Vram, S: SpriteRenderer> PpuBase<V, S> {
 impl<V: Vram, S: SpriteRenderer> Ppu for PpuBase<V, S> {
     type Scr = NesScreen;
 
-    fn new(rom: Rc<Box<NesRom>>) -> Self {
+    fn new(cart: Cartridge) -> Self {
         PpuBase {
             cycles: 0,
             control: ControlRegister::default(),
             mask: MaskRegister::default(),
             status: StatusRegister::default(),
-            vram: V::new(rom),
+            vram: V::new(cart),
             sprite_renderer: S::default(),
             screen: Self::Scr::default(),
             write_latch: WriteLatch::default(),
             background_renderer: BackgroundRenderer::default(),
             odd_frame: false,
+            nmi_on_enable: false,
         }
     }
 
@@ -132,7 +134,7 @@ impl<V: Vram, S: SpriteRenderer> Ppu for PpuBase<V, S> {
         // Don't rely on self.cycles after the following line
         self.cycles += 1;
 
-        match CYCLE_TABLE[scanline as usize][x as usize] {
+        let interrupt = match CYCLE_TABLE[scanline as usize][x as usize] {
             0 => Interrupt::None,
             1 => {
                 if self.mask.rendering_enabled() {
@@ -470,6 +472,13 @@ impl<V: Vram, S: SpriteRenderer> Ppu for PpuBase<V, S> {
                 Interrupt::None
             }
             _ => unreachable!(),
+        };
+
+        if self.nmi_on_enable {
+            self.nmi_on_enable = false;
+            Interrupt::Nmi
+        } else {
+            interrupt
         }
     }
 
@@ -480,8 +489,13 @@ impl<V: Vram, S: SpriteRenderer> Ppu for PpuBase<V, S> {
 
         match addr & 7 {
             0x0 => {
+                // Enabling NMI while the vblank flag is set raises one immediately
+                let nmi_enabled = self.control.nmi_on_vblank_start();
                 self.control.write(val);
                 self.vram.control_write(val);
+                if !nmi_enabled && self.control.nmi_on_vblank_start() && self.status.in_vblank() {
+                    self.nmi_on_enable = true;
+                }
             }
             0x1 => self.mask.write(val),
             0x2 => (), // readonly
@@ -549,6 +563,11 @@ impl<V: Vram, S: SpriteRenderer> Ppu for PpuBase<V, S> {
         writer.write_all(&regs).unwrap()
     }
 
+    /// Dump the PPU address space, including the cartridge's pattern tables
+    fn dump_vram<T: Write>(&self, writer: &mut T) {
+        self.vram.dump(writer)
+    }
+
     fn screen(&self) -> &NesScreen {
         &self.screen
     }
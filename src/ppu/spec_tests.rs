@@ -324,6 +324,26 @@ fn vblank_set_and_clear_cycles() {
     }
 }
 
+#[test]
+fn enabling_nmi_during_vblank_raises_nmi() {
+    let mut ppu = mocks::mock_ppu();
+    ppu.status.set_in_vblank();
+    ppu.write(0x2000, 0x80);
+    assert_eq!(Interrupt::Nmi, ppu.step());
+    assert_eq!(Interrupt::None, ppu.step());
+
+    // Only the write that enables it raises one
+    ppu.write(0x2000, 0x80);
+    assert_eq!(Interrupt::None, ppu.step());
+}
+
+#[test]
+fn enabling_nmi_outside_vblank() {
+    let mut ppu = mocks::mock_ppu();
+    ppu.write(0x2000, 0x80);
+    assert_eq!(Interrupt::None, ppu.step());
+}
+
 #[test]
 fn vblank_clear_after_status_read() {
     let ppu = mocks::mock_ppu();
@@ -425,6 +445,7 @@ fn odd_frame_cycle_skip() {
 
 mod mocks {
 
+    use mapper::Cartridge;
     use ppu::PpuBase;
     use ppu::background_renderer::BackgroundRenderer;
     use ppu::control_register::{ControlRegister, IncrementAmount};
@@ -434,10 +455,9 @@ mod mocks {
     use ppu::status_register::StatusRegister;
     use ppu::vram::Vram;
     use ppu::write_latch::{LatchState, WriteLatch};
-    use rom::NesRom;
     use screen::NesScreen;
     use std::cell::Cell;
-    use std::rc::Rc;
+    use std::io::Write;
 
     pub type TestPpu = PpuBase<MockVram, MockSpriteRenderer>;
 
@@ -447,12 +467,13 @@ mod mocks {
             control: ControlRegister::default(),
             mask: MaskRegister::default(),
             status: StatusRegister::default(),
-            vram: MockVram::new(Rc::new(Box::new(NesRom::default()))),
+            vram: MockVram::default(),
             sprite_renderer: MockSpriteRenderer::default(),
             screen: NesScreen::default(),
             write_latch: WriteLatch::default(),
             background_renderer: BackgroundRenderer::default(),
             odd_frame: false,
+            nmi_on_enable: false,
         }
     }
 
@@ -553,7 +574,7 @@ mod mocks {
             0
         }
 
-        fn new(_: Rc<Box<NesRom>>) -> Self {
+        fn new(_: Cartridge) -> Self {
             Self::default()
         }
 
@@ -587,5 +608,7 @@ mod mocks {
         fn fine_x(&self) -> u8 {
             0
         }
+
+        fn dump<T: Write>(&self, _: &mut T) {}
     }
 }
@@ -130,6 +130,17 @@ impl SpriteRendererBase {
         let new_addr = (Wrapping(self.address.get()) + Wrapping(1_u8)).0;
         self.address.set(new_addr)
     }
+
+    fn tile_offset(tile_index: u8, control: ControlRegister) -> u16 {
+        match control.sprite_size() {
+            SpriteSize::X8 => control.sprite_pattern_table_base() | ((tile_index as u16) << 4),
+            SpriteSize::X16 => {
+                let actual_tile_index = tile_index & !1;
+                let sprite_table_select = (tile_index as u16 & 1) << 12;
+                sprite_table_select | actual_tile_index as u16
+            }
+        }
+    }
 }
 
 impl SpriteRenderer for SpriteRendererBase {
@@ -200,7 +211,12 @@ impl SpriteRenderer for SpriteRendererBase {
             let attribute = SpriteAttributes(attribute_byte);
 
             let (pattern_low, pattern_high) = if tile_y > self.sprite_evaluation.scanline() {
-                // It's an unused tile, return all transparent pixels
+                // It's an unused tile, return all transparent pixels. The PPU still performs the
+                // pattern fetches for tile $FF, which cartridges watching the PPU address bus
+                // (e.g. MMC3's scanline counter) rely on.
+                let dummy_offset = Self::tile_offset(0xff, control);
+                vram.read(dummy_offset);
+                vram.read(dummy_offset + 8);
                 (0, 0)
             } else {
                 let tile_index = self.sprite_evaluation
@@ -213,16 +229,7 @@ impl SpriteRenderer for SpriteRendererBase {
                 };
                 debug_assert!(fine_y < 8);
 
-                let tile_offset = match control.sprite_size() {
-                    SpriteSize::X8 => {
-                        control.sprite_pattern_table_base() | ((tile_index as u16) << 4)
-                    }
-                    SpriteSize::X16 => {
-                        let actual_tile_index = tile_index & !1;
-                        let sprite_table_select = (tile_index as u16 & 1) << 12;
-                        sprite_table_select | actual_tile_index as u16
-                    }
-                } + fine_y as u16;
+                let tile_offset = Self::tile_offset(tile_index, control) + fine_y as u16;
 
                 let pattern_low = vram.read(tile_offset);
                 let pattern_high = vram.read(tile_offset + 8);
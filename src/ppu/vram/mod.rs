@@ -1,14 +1,15 @@
 use super::control_register::IncrementAmount;
+use mapper::Cartridge;
 use ppu::write_latch::LatchState;
-use rom::NesRom;
+use rom::Mirroring;
 use std::cell::Cell;
-use std::rc::Rc;
+use std::io::Write;
 
 #[cfg(test)]
 mod spec_tests;
 
 pub trait Vram {
-    fn new(rom: Rc<Box<NesRom>>) -> Self;
+    fn new(cart: Cartridge) -> Self;
     fn write_ppu_addr(&self, latch_state: LatchState);
     fn write_ppu_data(&mut self, val: u8, inc_amount: IncrementAmount);
     fn read_ppu_data(&self, inc_amount: IncrementAmount) -> u8;
@@ -22,25 +23,53 @@ pub trait Vram {
     fn copy_horizontal_pos_to_addr(&self);
     fn copy_vertical_pos_to_addr(&self);
     fn fine_x(&self) -> u8;
+    fn dump<T: Write>(&self, writer: &mut T);
 }
 
 pub struct VramBase {
     address: Cell<u16>,
     name_tables: [u8; 0x1000],
     palette: [u8; 0x20],
-    rom: Rc<Box<NesRom>>, // TODO: mapper
+    cart: Cartridge,
     ppu_data_buffer: Cell<u8>,
     t: Cell<u16>,
     fine_x: Cell<u8>,
 }
 
+impl VramBase {
+    /// Maps a nametable address onto the internal nametable memory according to the cartridge's
+    /// current mirroring arrangement.
+    fn nametable_index(&self, addr: u16) -> usize {
+        let addr = addr as usize & 0x0fff;
+        match self.cart.mirroring() {
+            Mirroring::Horizontal => ((addr & 0x800) >> 1) | (addr & 0x3ff),
+            Mirroring::Vertical => addr & 0x7ff,
+            Mirroring::FourScreen => addr,
+            Mirroring::SingleScreenLower => addr & 0x3ff,
+            Mirroring::SingleScreenUpper => 0x400 | (addr & 0x3ff),
+        }
+    }
+}
+
+fn palette_index(addr: u16) -> usize {
+    let addr = addr as usize & 0x1f;
+    // Certain sprite addresses are mirrored back into background addresses
+    match addr & 0xf {
+        0x0 => 0x0,
+        0x4 => 0x4,
+        0x8 => 0x8,
+        0xc => 0xc,
+        _ => addr,
+    }
+}
+
 impl Vram for VramBase {
-    fn new(rom: Rc<Box<NesRom>>) -> Self {
+    fn new(cart: Cartridge) -> Self {
         VramBase {
             address: Cell::new(0),
             name_tables: [0; 0x1000],
             palette: [0; 0x20],
-            rom: rom,
+            cart: cart,
             ppu_data_buffer: Cell::new(0),
             t: Cell::new(0),
             fine_x: Cell::new(0),
@@ -62,6 +91,7 @@ impl Vram for VramBase {
                 let t = val as u16 | (self.t.get() & 0b0111_1111_0000_0000);
                 self.t.set(t);
                 self.address.set(t);
+                self.cart.ppu_address(t);
             }
         }
     }
@@ -95,22 +125,17 @@ impl Vram for VramBase {
 
     fn write_ppu_data(&mut self, val: u8, inc_amount: IncrementAmount) {
         let addr = self.address.get();
+        if addr < 0x3f00 {
+            self.cart.ppu_address(addr);
+        }
 
         if addr < 0x2000 {
-            panic!("write to cart ram not implemented")
+            self.cart.chr_write(addr, val);
         } else if addr < 0x3f00 {
-            self.name_tables[addr as usize & 0x0fff] = val;
+            let index = self.nametable_index(addr);
+            self.name_tables[index] = val;
         } else if addr < 0x4000 {
-            let addr = addr as usize & 0x1f;
-            // Certain sprite addresses are mirrored back into background addresses
-            let addr = match addr & 0xf {
-                0x0 => 0x0,
-                0x4 => 0x4,
-                0x8 => 0x8,
-                0xc => 0xc,
-                _ => addr,
-            };
-            self.palette[addr] = val;
+            self.palette[palette_index(addr)] = val;
         } else {
             panic!("Invalid VRAM write");
         }
@@ -125,20 +150,13 @@ impl Vram for VramBase {
     #[inline(always)]
     fn read(&self, addr: u16) -> u8 {
         let val = if addr < 0x2000 {
-            self.rom.chr[addr as usize]
+            self.cart.ppu_address(addr);
+            self.cart.chr_read(addr)
         } else if addr < 0x3f00 {
-            self.name_tables[addr as usize & 0x0fff]
+            self.cart.ppu_address(addr);
+            self.name_tables[self.nametable_index(addr)]
         } else if addr < 0x4000 {
-            let addr = addr as usize & 0x1f;
-            // Certain sprite addresses are mirrored back into background addresses
-            let addr = match addr & 0xf {
-                0x0 => 0x0,
-                0x4 => 0x4,
-                0x8 => 0x8,
-                0xc => 0xc,
-                _ => addr,
-            };
-            self.palette[addr]
+            self.palette[palette_index(addr)]
         } else {
             panic!("Invalid vram read");
         };
@@ -250,4 +268,21 @@ impl Vram for VramBase {
     fn fine_x(&self) -> u8 {
         self.fine_x.get()
     }
+
+    /// Dump the PPU address space ($0000-$3FFF). Unlike `read`, this doesn't place the addresses
+    /// on the cartridge's bus, so it has no effect on mappers that watch PPU address lines.
+    fn dump<T: Write>(&self, writer: &mut T) {
+        let pattern_tables: Vec<u8> = (0..0x2000).map(|addr| self.cart.chr_read(addr)).collect();
+        writer.write_all(&pattern_tables).unwrap();
+
+        let name_tables: Vec<u8> = (0x2000..0x3f00)
+            .map(|addr| self.name_tables[self.nametable_index(addr)])
+            .collect();
+        writer.write_all(&name_tables).unwrap();
+
+        let palette: Vec<u8> = (0x3f00..0x4000)
+            .map(|addr| self.palette[palette_index(addr)])
+            .collect();
+        writer.write_all(&palette).unwrap();
+    }
 }
@@ -1,7 +1,8 @@
 use super::*;
+use mapper::Cartridge;
 use ppu::control_register::IncrementAmount;
 use ppu::write_latch::LatchState;
-use rom::NesRom;
+use rom::{Mirroring, NesRom};
 
 #[test]
 fn write_address() {
@@ -19,10 +20,8 @@ fn write_address() {
 }
 
 #[test]
-#[ignore] // TODO: Fix once we have a mapping mechanism in place
 fn internal_memory_mapping_read() {
-    let mut vram = vram_fixture();
-    vram_fixture_with_chr(vec![1; 0x2000]);
+    let mut vram = vram_fixture_with_chr(vec![1; 0x2000]);
     vram.name_tables = [2; 0x1000];
 
     vram.ppu_data_buffer.set(1);
@@ -64,11 +63,13 @@ fn vram_read_buffering_behavior() {
 }
 
 #[test]
-#[ignore] // TODO: Fix once we have a mapping mechanism in place
 fn write_mapping() {
     // Tests pattern and nametable write mappings, palette mapping tested separately
 
-    let mut vram = vram_fixture();
+    let mut rom = NesRom::default();
+    rom.prg = vec![0; 0x4000];
+    rom.mirroring = Mirroring::FourScreen;
+    let mut vram = VramBase::new(Cartridge::new(rom).unwrap());
 
     for _ in 0..0x2000 {
         vram.write_ppu_data(1, IncrementAmount::One)
@@ -78,10 +79,87 @@ fn write_mapping() {
         vram.write_ppu_data(2, IncrementAmount::One)
     }
 
-    //assert_eq!(true, vram.rom.chr.into_iter().all(|val| val == 1));
+    assert_eq!(true, (0..0x2000).all(|addr| vram.cart.chr_read(addr) == 1));
     assert_eq!(true, vram.name_tables.into_iter().all(|val| *val == 2));
 }
 
+#[test]
+fn chr_rom_write_is_ignored() {
+    let mut vram = vram_fixture_with_chr(vec![1; 0x2000]);
+    vram.write_ppu_data(2, IncrementAmount::One);
+    assert_eq!(1, vram.read(0x0000));
+}
+
+#[test]
+fn dump_includes_chr_ram() {
+    let mut vram = vram_fixture_with_chr(Vec::new());
+    vram.address.set(0x1234);
+    vram.write_ppu_data(0xaa, IncrementAmount::One);
+
+    let mut buf = Vec::new();
+    vram.dump(&mut buf);
+    assert_eq!(0x4000, buf.len());
+    assert_eq!(0xaa, buf[0x1234]);
+}
+
+#[test]
+fn horizontal_nametable_mirroring() {
+    let mut vram = vram_fixture_with_mirroring(Mirroring::Horizontal);
+    write_nametable_markers(&mut vram);
+    assert_eq!(2, vram.read(0x2000));
+    assert_eq!(2, vram.read(0x2400));
+    assert_eq!(4, vram.read(0x2800));
+    assert_eq!(4, vram.read(0x2c00));
+}
+
+#[test]
+fn vertical_nametable_mirroring() {
+    let mut vram = vram_fixture_with_mirroring(Mirroring::Vertical);
+    write_nametable_markers(&mut vram);
+    assert_eq!(3, vram.read(0x2000));
+    assert_eq!(4, vram.read(0x2400));
+    assert_eq!(3, vram.read(0x2800));
+    assert_eq!(4, vram.read(0x2c00));
+}
+
+#[test]
+fn four_screen_nametables() {
+    let mut vram = vram_fixture_with_mirroring(Mirroring::FourScreen);
+    write_nametable_markers(&mut vram);
+    assert_eq!(1, vram.read(0x2000));
+    assert_eq!(2, vram.read(0x2400));
+    assert_eq!(3, vram.read(0x2800));
+    assert_eq!(4, vram.read(0x2c00));
+}
+
+#[test]
+fn single_screen_nametable_mirroring() {
+    let mut vram = vram_fixture_with_mirroring(Mirroring::SingleScreenLower);
+    write_nametable_markers(&mut vram);
+    for addr in &[0x2000, 0x2400, 0x2800, 0x2c00] {
+        assert_eq!(4, vram.read(*addr));
+    }
+    assert_eq!(4, vram.name_tables[0]);
+    assert_eq!(0, vram.name_tables[0x400]);
+
+    let mut vram = vram_fixture_with_mirroring(Mirroring::SingleScreenUpper);
+    write_nametable_markers(&mut vram);
+    for addr in &[0x2000, 0x2400, 0x2800, 0x2c00] {
+        assert_eq!(4, vram.read(*addr));
+    }
+    assert_eq!(0, vram.name_tables[0]);
+    assert_eq!(4, vram.name_tables[0x400]);
+}
+
+#[test]
+fn nametables_mirrored_into_3000() {
+    let mut vram = vram_fixture_with_mirroring(Mirroring::FourScreen);
+    write_nametable_markers(&mut vram);
+    assert_eq!(1, vram.read(0x3000));
+    assert_eq!(2, vram.read(0x3400));
+    assert_eq!(3, vram.read(0x3800));
+    assert_eq!(4, vram.read(0x3c00));
+}
 
 #[test]
 fn ppu_addr_mirroring() {
@@ -343,10 +421,27 @@ fn fine_y_increment() {
 
 fn vram_fixture_with_chr(chr: Vec<u8>) -> VramBase {
     let mut rom = NesRom::default();
+    rom.prg = vec![0; 0x4000];
     rom.chr = chr;
-    VramBase::new(Rc::new(Box::new(rom)))
+    VramBase::new(Cartridge::new(rom).unwrap())
+}
+
+fn vram_fixture_with_mirroring(mirroring: Mirroring) -> VramBase {
+    let mut rom = NesRom::default();
+    rom.prg = vec![0; 0x4000];
+    rom.chr = vec![0; 0x2000];
+    rom.mirroring = mirroring;
+    VramBase::new(Cartridge::new(rom).unwrap())
 }
 
 fn vram_fixture() -> VramBase {
     vram_fixture_with_chr(vec![0; 0x2000])
 }
+
+// Writes 1, 2, 3 and 4 to the first byte of each logical nametable, in order
+fn write_nametable_markers(vram: &mut VramBase) {
+    for (i, addr) in [0x2000_u16, 0x2400, 0x2800, 0x2c00].iter().enumerate() {
+        vram.address.set(*addr);
+        vram.write_ppu_data(i as u8 + 1, IncrementAmount::One);
+    }
+}
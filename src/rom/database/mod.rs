@@ -0,0 +1,110 @@
+//! Embedded game database, used to correct bad iNES headers. Entries are keyed by the CRC32 or
+//! SHA-1 of the PRG-ROM followed by the CHR-ROM, which is how dump databases identify headerless
+//! images.
+//!
+//! The checked-in `games.txt` only has a few sample entries. Header correction for a whole library
+//! needs the full database, generated from a NesCartDB export by the `game_database` example.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::{Mirroring, VideoStandard};
+use crc::crc32::{self, Hasher32};
+use sha1::Sha1;
+
+pub struct GameInfo {
+    pub title: String,
+    pub mapper: u16,
+    pub submapper: u8,
+    pub mirroring: Mirroring,
+    pub has_battery: bool,
+    pub video_standard: VideoStandard,
+}
+
+struct Entry {
+    crc32: u32,
+    sha1: Option<String>,
+    info: GameInfo,
+}
+
+lazy_static! {
+    static ref DATABASE: Vec<Entry> = parse(include_str!("games.txt"));
+}
+
+pub fn lookup(prg: &[u8], chr: &[u8]) -> Option<&'static GameInfo> {
+    let mut crc = crc32::Digest::new(crc32::IEEE);
+    crc.write(prg);
+    crc.write(chr);
+
+    let mut sha1 = Sha1::new();
+    sha1.update(prg);
+    sha1.update(chr);
+
+    find(&DATABASE, crc.sum32(), &sha1.digest().to_string())
+}
+
+fn find<'a>(entries: &'a [Entry], crc32: u32, sha1: &str) -> Option<&'a GameInfo> {
+    entries
+        .iter()
+        .find(|entry| entry.crc32 == crc32 || entry.sha1.as_ref().map_or(false, |s| s == sha1))
+        .map(|entry| &entry.info)
+}
+
+// Lines that can't be parsed are skipped, so a bad entry only loses that game's correction
+fn parse(db: &str) -> Vec<Entry> {
+    db.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(parse_entry)
+        .collect()
+}
+
+fn parse_entry(line: &str) -> Option<Entry> {
+    let fields: Vec<&str> = line.splitn(8, char::is_whitespace).collect();
+    if fields.len() != 8 {
+        return None;
+    }
+
+    let mirroring = match fields[4] {
+        "H" => Mirroring::Horizontal,
+        "V" => Mirroring::Vertical,
+        "4" => Mirroring::FourScreen,
+        "L" => Mirroring::SingleScreenLower,
+        "U" => Mirroring::SingleScreenUpper,
+        _ => return None,
+    };
+
+    let video_standard = match fields[6] {
+        "NTSC" => VideoStandard::Ntsc,
+        "PAL" => VideoStandard::Pal,
+        "MULTI" => VideoStandard::MultipleRegion,
+        "DENDY" => VideoStandard::Dendy,
+        _ => return None,
+    };
+
+    let crc32 = match u32::from_str_radix(fields[0], 16) {
+        Ok(crc32) => crc32,
+        Err(_) => return None,
+    };
+    let (mapper, submapper) = match (fields[2].parse(), fields[3].parse()) {
+        (Ok(mapper), Ok(submapper)) => (mapper, submapper),
+        _ => return None,
+    };
+
+    Some(Entry {
+             crc32: crc32,
+             sha1: if fields[1] == "-" {
+                 None
+             } else {
+                 Some(fields[1].to_lowercase())
+             },
+             info: GameInfo {
+                 title: fields[7].trim().to_string(),
+                 mapper: mapper,
+                 submapper: submapper,
+                 mirroring: mirroring,
+                 has_battery: fields[5] == "1",
+                 video_standard: video_standard,
+             },
+         })
+}
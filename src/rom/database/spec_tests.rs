@@ -0,0 +1,61 @@
+use super::*;
+
+#[test]
+fn embedded_database_parses() {
+    // Every entry parses, since bad lines are skipped rather than reported
+    let entries = include_str!("games.txt")
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .count();
+    assert_eq!(false, DATABASE.is_empty());
+    assert_eq!(entries, DATABASE.len());
+}
+
+#[test]
+fn malformed_entries_are_skipped() {
+    let entries = parse("0badf00d - 4 1 4 1 PAL Good
+                         nothex - 4 1 4 1 PAL Bad CRC
+                         0badf00d - 4 1 X 1 PAL Bad mirroring
+                         0badf00d - 4 1 4 1 MARS Bad region
+                         0badf00d - mmc3 1 4 1 PAL Bad mapper
+                         0badf00d - 4 1 4
+");
+    assert_eq!(1, entries.len());
+    assert_eq!("Good", entries[0].info.title);
+}
+
+#[test]
+fn parse_entry() {
+    let entries = parse("# comment\n\n0badf00d - 4 1 4 1 PAL Some Game: Subtitle\n");
+    assert_eq!(1, entries.len());
+    let entry = &entries[0];
+    assert_eq!(0x0badf00d, entry.crc32);
+    assert_eq!(None, entry.sha1);
+    assert_eq!("Some Game: Subtitle", entry.info.title);
+    assert_eq!(4, entry.info.mapper);
+    assert_eq!(1, entry.info.submapper);
+    assert_eq!(Mirroring::FourScreen, entry.info.mirroring);
+    assert_eq!(true, entry.info.has_battery);
+    assert_eq!(VideoStandard::Pal, entry.info.video_standard);
+}
+
+#[test]
+fn find_by_crc32_or_sha1() {
+    let entries = parse("00000001 - 0 0 H 0 NTSC One\n\
+                         00000002 DA39A3EE5E6B4B0D3255BFEF95601890AFD80709 0 0 H 0 NTSC Two\n");
+    assert_eq!("One", find(&entries, 1, "").unwrap().title);
+    assert_eq!("Two",
+               find(&entries, 3, "da39a3ee5e6b4b0d3255bfef95601890afd80709")
+                   .unwrap()
+                   .title);
+    assert_eq!(true, find(&entries, 3, "").is_none());
+}
+
+#[test]
+fn sha1_of_prg_and_chr() {
+    // The SHA-1 of no data, to verify the hex formatting matches the database
+    let mut sha1 = Sha1::new();
+    sha1.update(&[]);
+    assert_eq!("da39a3ee5e6b4b0d3255bfef95601890afd80709",
+               sha1.digest().to_string());
+}
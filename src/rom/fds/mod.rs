@@ -0,0 +1,116 @@
+//! Famicom Disk System images. Both formats store each disk side as a sequence of blocks:
+//!
+//! - .fds: 65500 bytes per side, with an optional 16 byte "FDS<EOF>" header. Blocks are stored
+//!   back to back without their CRCs.
+//! - .qd: 65536 bytes per side with no header. Each block is followed by its two CRC bytes.
+//!
+//! Neither format stores the gaps and block start marks that the drive sees, so they are added back
+//! when the image is loaded.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::RomError;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+const FDS_HEADER_SIZE: usize = 16;
+const FDS_SIDE_SIZE: usize = 65500;
+const QD_SIDE_SIZE: usize = 65536;
+
+// The drive stream is longer than the data, since it includes the gaps between blocks
+const RAW_SIDE_SIZE: usize = 68000;
+const LEADING_GAP_SIZE: usize = 28300 / 8;
+const BLOCK_GAP_SIZE: usize = 976 / 8;
+const BLOCK_START_MARK: u8 = 0x80;
+
+const DISK_INFO_BLOCK: u8 = 1;
+const FILE_AMOUNT_BLOCK: u8 = 2;
+const FILE_HEADER_BLOCK: u8 = 3;
+const FILE_DATA_BLOCK: u8 = 4;
+
+pub struct FdsImage {
+    /// Each side as the drive reads it: gaps, block start marks, block data and CRCs
+    pub sides: Vec<Vec<u8>>,
+}
+
+impl FdsImage {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<FdsImage, RomError> {
+        let mut f = File::open(path)?;
+        let mut vec = Vec::<u8>::new();
+        f.read_to_end(&mut vec)?;
+        FdsImage::from_bytes(&vec)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<FdsImage, RomError> {
+        let (data, side_size, has_crcs) = if bytes.len() >= 4 && &bytes[0..4] == b"FDS\x1a" {
+            (&bytes[FDS_HEADER_SIZE.min(bytes.len())..], FDS_SIDE_SIZE, false)
+        } else if bytes.len() % FDS_SIDE_SIZE == 0 {
+            (bytes, FDS_SIDE_SIZE, false)
+        } else if bytes.len() % QD_SIDE_SIZE == 0 {
+            (bytes, QD_SIDE_SIZE, true)
+        } else {
+            return Err(RomError::MalformedHeader("Unrecognized disk image size."));
+        };
+
+        if data.len() < side_size {
+            return Err(RomError::Truncated {
+                           expected: side_size,
+                           actual: data.len(),
+                       });
+        }
+
+        let sides = data.chunks(side_size)
+            .filter(|side| side.len() == side_size)
+            .map(|side| raw_side(side, has_crcs))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(FdsImage { sides: sides })
+    }
+}
+
+fn raw_side(side: &[u8], has_crcs: bool) -> Result<Vec<u8>, RomError> {
+    if side[0] != DISK_INFO_BLOCK || &side[1..15] != b"*NINTENDO-HVC*" {
+        return Err(RomError::MalformedHeader("Disk side doesn't start with a disk info block."));
+    }
+
+    let mut raw = vec![0; LEADING_GAP_SIZE];
+    let mut pos = 0;
+    let mut file_size = 0;
+    while pos < side.len() {
+        let block_size = match side[pos] {
+            DISK_INFO_BLOCK => 56,
+            FILE_AMOUNT_BLOCK => 2,
+            FILE_HEADER_BLOCK => 16,
+            FILE_DATA_BLOCK => 1 + file_size,
+            // Anything else is the unused remainder of the side
+            _ => break,
+        };
+
+        if pos + block_size > side.len() {
+            break;
+        }
+
+        let block = &side[pos..pos + block_size];
+        if block[0] == FILE_HEADER_BLOCK {
+            file_size = block[13] as usize | (block[14] as usize) << 8;
+        }
+
+        raw.push(BLOCK_START_MARK);
+        raw.extend_from_slice(block);
+        // The drive always reports a good CRC, so the CRC bytes just need to take up space
+        raw.extend_from_slice(&[0, 0]);
+        raw.extend(vec![0; BLOCK_GAP_SIZE]);
+
+        pos += block_size;
+        if has_crcs {
+            pos += 2;
+        }
+    }
+
+    if raw.len() < RAW_SIDE_SIZE {
+        raw.resize(RAW_SIDE_SIZE, 0);
+    }
+    Ok(raw)
+}
@@ -0,0 +1,86 @@
+use super::*;
+
+#[test]
+fn fds_with_header() {
+    let mut bytes = b"FDS\x1a\x02".to_vec();
+    bytes.extend(vec![0; 11]);
+    bytes.extend(side_fixture(false));
+    bytes.extend(side_fixture(false));
+    let image = FdsImage::from_bytes(&bytes).unwrap();
+    assert_eq!(2, image.sides.len());
+    assert_raw_side(&image.sides[0]);
+}
+
+#[test]
+fn fds_without_header() {
+    let image = FdsImage::from_bytes(&side_fixture(false)).unwrap();
+    assert_eq!(1, image.sides.len());
+    assert_raw_side(&image.sides[0]);
+}
+
+#[test]
+fn qd() {
+    let image = FdsImage::from_bytes(&side_fixture(true)).unwrap();
+    assert_eq!(1, image.sides.len());
+    assert_raw_side(&image.sides[0]);
+}
+
+#[test]
+fn side_without_disk_info_block() {
+    let mut bytes = side_fixture(false);
+    bytes[1] = b'X';
+    match FdsImage::from_bytes(&bytes) {
+        Err(RomError::MalformedHeader(_)) => (),
+        _ => panic!("expected a malformed header error"),
+    }
+}
+
+#[test]
+fn unrecognized_size() {
+    match FdsImage::from_bytes(&[0; 100]) {
+        Err(RomError::MalformedHeader(_)) => (),
+        _ => panic!("expected a malformed header error"),
+    }
+}
+
+fn assert_raw_side(raw: &[u8]) {
+    assert_eq!(RAW_SIDE_SIZE, raw.len());
+    assert_eq!(true, raw[..LEADING_GAP_SIZE].iter().all(|b| *b == 0));
+
+    // Disk info block
+    let mut pos = LEADING_GAP_SIZE;
+    assert_eq!(BLOCK_START_MARK, raw[pos]);
+    assert_eq!(DISK_INFO_BLOCK, raw[pos + 1]);
+    assert_eq!(b"*NINTENDO-HVC*", &raw[pos + 2..pos + 16]);
+
+    // File amount block, after the CRC and gap
+    pos += 1 + 56 + 2 + BLOCK_GAP_SIZE;
+    assert_eq!(&[BLOCK_START_MARK, FILE_AMOUNT_BLOCK, 1], &raw[pos..pos + 3]);
+
+    // File header block
+    pos += 1 + 2 + 2 + BLOCK_GAP_SIZE;
+    assert_eq!(&[BLOCK_START_MARK, FILE_HEADER_BLOCK], &raw[pos..pos + 2]);
+
+    // File data block
+    pos += 1 + 16 + 2 + BLOCK_GAP_SIZE;
+    assert_eq!(&[BLOCK_START_MARK, FILE_DATA_BLOCK, 0xaa, 0xbb, 0xcc, 0], &raw[pos..pos + 6]);
+}
+
+// A disk side with one 3 byte file
+fn side_fixture(has_crcs: bool) -> Vec<u8> {
+    let crc: &[u8] = if has_crcs { &[0x12, 0x34] } else { &[] };
+    let mut side = vec![DISK_INFO_BLOCK];
+    side.extend_from_slice(b"*NINTENDO-HVC*");
+    side.extend(vec![0; 41]);
+    side.extend_from_slice(crc);
+    side.extend_from_slice(&[FILE_AMOUNT_BLOCK, 1]);
+    side.extend_from_slice(crc);
+    side.extend_from_slice(&[FILE_HEADER_BLOCK, 0, 0, b'F', b'I', b'L', b'E', b'0', b'0', b'0',
+                             b'0', 0x00, 0x60, 3, 0, 0]);
+    side.extend_from_slice(crc);
+    side.extend_from_slice(&[FILE_DATA_BLOCK, 0xaa, 0xbb, 0xcc]);
+    side.extend_from_slice(crc);
+    let side_size = if has_crcs { QD_SIDE_SIZE } else { FDS_SIDE_SIZE };
+    side.resize(side_size, 0);
+    side
+}
@@ -1,40 +1,162 @@
+#[cfg(test)]
+mod spec_tests;
+mod database;
+pub mod fds;
+pub mod nsf;
+pub mod patch;
+mod unif;
+
+use self::database::GameInfo;
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Read};
+use std::path::Path;
+
+const PRG_ROM_BANK_SIZE: usize = 0x4000;
+const CHR_ROM_BANK_SIZE: usize = 0x2000;
+const RAM_BANK_SIZE: usize = 0x2000;
+const HEADER_SIZE: usize = 16;
+const TRAINER_SIZE: usize = 512;
+
+#[derive(Debug)]
+pub enum RomError {
+    Io(io::Error),
+    /// The file doesn't start with "NES<EOF>"
+    BadMagic,
+    /// The file is smaller than its header says it should be
+    Truncated { expected: usize, actual: usize },
+    UnsupportedMapper(u16),
+    /// A UNIF board name that doesn't correspond to a supported mapper
+    UnsupportedBoard(String),
+    MalformedHeader(&'static str),
+    /// The Famicom Disk System BIOS isn't 8KB
+    BadBios,
+    /// A patch that is malformed, or doesn't match the ROM it is applied to
+    BadPatch(&'static str),
+    /// A trainer on a board that has no PRG-RAM at $7000 to load it into
+    UnsupportedTrainer,
+    /// The PRG-ROM or CHR-ROM is too small for the board to map
+    TooSmall(&'static str),
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RomError::Io(ref e) => write!(f, "Unable to read ROM: {}", e),
+            RomError::BadMagic => write!(f, "Not a valid NES ROM."),
+            RomError::Truncated { expected, actual } => {
+                write!(f,
+                       "ROM is truncated: expected {} bytes but found {}.",
+                       expected,
+                       actual)
+            }
+            RomError::UnsupportedMapper(mapper) => write!(f, "Unsupported mapper ({}).", mapper),
+            RomError::UnsupportedBoard(ref board) => write!(f, "Unsupported board ({}).", board),
+            RomError::MalformedHeader(reason) => write!(f, "Malformed ROM header: {}", reason),
+            RomError::BadBios => write!(f, "Not a valid FDS BIOS."),
+            RomError::BadPatch(reason) => write!(f, "Unable to apply patch: {}", reason),
+            RomError::UnsupportedTrainer => write!(f, "Trainers aren't supported on this board."),
+            RomError::TooSmall(reason) => write!(f, "ROM is too small for its board: {}", reason),
+        }
+    }
+}
+
+impl Error for RomError {
+    fn description(&self) -> &str {
+        match *self {
+            RomError::Io(_) => "unable to read ROM",
+            RomError::BadMagic => "not a valid NES ROM",
+            RomError::Truncated { .. } => "ROM is truncated",
+            RomError::UnsupportedMapper(_) => "unsupported mapper",
+            RomError::UnsupportedBoard(_) => "unsupported board",
+            RomError::MalformedHeader(reason) => reason,
+            RomError::BadBios => "not a valid FDS BIOS",
+            RomError::BadPatch(reason) => reason,
+            RomError::UnsupportedTrainer => "trainer not supported on board",
+            RomError::TooSmall(reason) => reason,
+        }
+    }
 
-#[derive(Copy, Clone, Debug)]
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            RomError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RomError {
+    fn from(e: io::Error) -> Self {
+        RomError::Io(e)
+    }
+}
+
+/// CPU/PPU timing mode
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum VideoStandard {
     Ntsc,
     Pal,
     Indeterminite,
+    /// The game runs on both NTSC and PAL machines (NES 2.0 only)
+    MultipleRegion,
+    /// Famiclone timing (NES 2.0 only)
+    Dendy,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConsoleType {
+    Nes,
+    /// Vs. System, along with the PPU and hardware types from byte 13 of a NES 2.0 header
+    VsSystem { ppu_type: u8, hardware_type: u8 },
+    Playchoice10,
+    /// One of the NES 2.0 extended console types from byte 13
+    Extended(u8),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum RomFormat {
     INesArchaic,
     INes,
     Nes20,
+    Unif,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Mirroring {
     Horizontal,
     Vertical,
     FourScreen,
+    SingleScreenLower,
+    SingleScreenUpper,
 }
 
 #[derive(Clone)]
 pub struct NesRom {
     pub format: RomFormat,
     pub video_standard: VideoStandard,
-    pub mapper: u8,
+    pub mapper: u16,
+    pub submapper: u8,
     pub mirroring: Mirroring,
-    pub prg_rom_banks: u8,
-    pub prg_ram_banks: u8,
-    pub chr_rom_banks: u8,
+    pub prg_rom_banks: u16,
+    /// 8KB units of PRG-RAM, including battery-backed PRG-RAM
+    pub prg_ram_banks: u16,
+    pub chr_rom_banks: u16,
+    /// 8KB units of CHR-RAM, used in place of CHR-ROM on boards that have none
+    pub chr_ram_banks: u16,
+    /// Sizes in bytes of volatile and battery-backed RAM. INes headers only give a PRG-RAM bank
+    /// count, but NES 2.0 headers describe each of these exactly.
+    pub prg_ram_size: usize,
+    pub prg_nvram_size: usize,
+    pub chr_ram_size: usize,
+    pub chr_nvram_size: usize,
     pub has_sram: bool,
     pub has_trainer: bool,
     pub is_pc10: bool,
     pub is_vs_unisystem: bool,
+    pub console_type: ConsoleType,
+    /// The default expansion device from byte 15 of a NES 2.0 header (0 if unspecified)
+    pub expansion_device: u8,
     pub trainer: Vec<u8>,
     pub chr: Vec<u8>,
     pub prg: Vec<u8>,
@@ -46,14 +168,22 @@ impl Default for NesRom {
             format: RomFormat::INes,
             video_standard: VideoStandard::Ntsc,
             mapper: 0,
+            submapper: 0,
             mirroring: Mirroring::Horizontal,
             prg_rom_banks: 2,
             prg_ram_banks: 0,
             chr_rom_banks: 2,
+            chr_ram_banks: 0,
+            prg_ram_size: 0,
+            prg_nvram_size: 0,
+            chr_ram_size: 0,
+            chr_nvram_size: 0,
             has_sram: false,
             has_trainer: false,
             is_pc10: false,
             is_vs_unisystem: false,
+            console_type: ConsoleType::Nes,
+            expansion_device: 0,
             trainer: Vec::new(),
             chr: Vec::new(),
             prg: Vec::new(),
@@ -62,34 +192,89 @@ impl Default for NesRom {
 }
 
 impl NesRom {
-    pub fn read(path: String) -> Result<NesRom, &'static str> {
-        let mut f = match File::open(path) {
-            Ok(file) => file,
-            Err(_) => return Err("Unable to open file."),
+    /// Loads a ROM file. If an IPS, UPS or BPS patch with the same name is next to it, the patch is
+    /// applied first.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<NesRom, RomError> {
+        let mut f = File::open(&path)?;
+        let mut vec = Vec::<u8>::new();
+        f.read_to_end(&mut vec)?;
+        if let Some(patch_path) = patch::find_patch(&path) {
+            vec = patch::apply_file(&vec, patch_path)?;
+        }
+        NesRom::from_bytes(&vec)
+    }
+
+    /// Loads an iNES, NES 2.0 or UNIF image that is already in memory, e.g. one extracted from an
+    /// archive.
+    pub fn from_bytes(bytes: &[u8]) -> Result<NesRom, RomError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(RomError::Truncated {
+                           expected: HEADER_SIZE,
+                           actual: bytes.len(),
+                       });
+        }
+
+        // Some old dumping tools wrote "DiskDude!" over bytes 7-15, which must be ignored
+        let cleaned;
+        let bytes = if &bytes[7..16] == b"DiskDude!" {
+            cleaned = NesRom::without_diskdude(bytes);
+            &cleaned[..]
+        } else {
+            bytes
         };
 
-        let mut vec = Vec::<u8>::new();
-        let bytes_read = match f.read_to_end(&mut vec) {
-            Ok(read) => read,
-            Err(_) => return Err("An error occurred reading the file."),
+        let rom_format = NesRom::determine_format(bytes, bytes.len());
+
+        // Check file header: NES<EOF>
+        if rom_format != RomFormat::Unif &&
+           (bytes[0] != 0x4e || bytes[1] != 0x45 || bytes[2] != 0x53 || bytes[3] != 0x1a) {
+            return Err(RomError::BadMagic);
+        }
+
+        let mut rom = match rom_format {
+            RomFormat::INesArchaic => NesRom::load_ines_archaic(bytes)?,
+            RomFormat::INes => NesRom::load_ines(bytes)?,
+            RomFormat::Nes20 => NesRom::load_nes20(bytes)?,
+            RomFormat::Unif => unif::load(bytes)?,
         };
 
-        // assert that we at least read enough to check the header for now...
-        if bytes_read < 16 {
-            return Err("Invalid ROM format - Unexpected file size.");
+        if let Some(info) = database::lookup(&rom.prg, &rom.chr) {
+            rom.apply_game_info(info);
         }
+        Ok(rom)
+    }
 
-        // Check file header: NES<EOF>
-        if vec[0] != 0x4e || vec[1] != 0x45 || vec[2] != 0x53 || vec[3] != 0x1a {
-            panic!("Not a valid nes rom.");
+    /// Looks the ROM up in the embedded game database, returning its canonical title if found.
+    pub fn identify(&self) -> Option<&'static str> {
+        database::lookup(&self.prg, &self.chr).map(|info| &info.title[..])
+    }
+
+    // Overrides header fields that are commonly wrong in dumps. NES 2.0 and UNIF headers describe
+    // the board exactly, so only the older iNES formats are corrected.
+    fn apply_game_info(&mut self, info: &GameInfo) {
+        if self.format == RomFormat::Nes20 || self.format == RomFormat::Unif {
+            return;
+        }
+
+        self.mapper = info.mapper;
+        self.submapper = info.submapper;
+        self.mirroring = info.mirroring;
+        self.video_standard = info.video_standard;
+        if self.has_sram != info.has_battery {
+            self.has_sram = info.has_battery;
+            let (prg_ram_size, prg_nvram_size) =
+                NesRom::ines_prg_ram_sizes(self.prg_ram_banks, info.has_battery);
+            self.prg_ram_size = prg_ram_size;
+            self.prg_nvram_size = prg_nvram_size;
         }
+    }
 
-        let rom_format = NesRom::determine_format(&vec, bytes_read);
-        match rom_format {
-            RomFormat::INesArchaic => NesRom::load_ines_archaic(&vec),
-            RomFormat::INes => NesRom::load_ines(&vec),
-            RomFormat::Nes20 => Err("Unsupported ROM format (Nes20)."),
+    fn without_diskdude(bytes: &[u8]) -> Vec<u8> {
+        let mut bytes = bytes.to_vec();
+        for b in &mut bytes[7..16] {
+            *b = 0;
         }
+        bytes
     }
 
     fn load_common(bytes: &[u8]) -> (u8, u8, u8, bool, bool, Mirroring) {
@@ -111,42 +296,52 @@ impl NesRom {
         (prg_rom_banks, chr_rom_banks, mapper, has_trainer, has_sram, mirroring)
     }
 
-    fn load_ines_archaic(bytes: &[u8]) -> Result<NesRom, &'static str> {
-        let is_zeroed = &bytes[7..15].iter().all(|&b| b == 0);
-        if !is_zeroed {
-            return Err("Invalid Legacy INes format - bytes 7-15 must be zeroed.");
-        }
-
+    // Bytes 7-15 were unused when archaic headers were written, and often hold junk, so only the
+    // low nibble of the mapper number is known.
+    fn load_ines_archaic(bytes: &[u8]) -> Result<NesRom, RomError> {
         let (prg_rom_banks, chr_rom_banks, mapper_lo, has_trainer, has_sram, mirroring) =
             NesRom::load_common(bytes);
+        let prg_size = prg_rom_banks as usize * PRG_ROM_BANK_SIZE;
+        let chr_size = chr_rom_banks as usize * CHR_ROM_BANK_SIZE;
+        let (trainer, prg, chr) = NesRom::load_data(bytes, has_trainer, prg_size, chr_size)?;
+        let chr_ram_banks = if chr_rom_banks == 0 { 1 } else { 0 };
+        let (prg_ram_size, prg_nvram_size) = NesRom::ines_prg_ram_sizes(1, has_sram);
 
         Ok(NesRom {
                format: RomFormat::INesArchaic,
                video_standard: VideoStandard::Indeterminite,
-               mapper: mapper_lo,
+               mapper: mapper_lo as u16,
+               submapper: 0,
                mirroring: mirroring,
-               prg_rom_banks: prg_rom_banks,
+               prg_rom_banks: prg_rom_banks as u16,
                prg_ram_banks: 1,
-               chr_rom_banks: chr_rom_banks,
+               chr_rom_banks: chr_rom_banks as u16,
+               chr_ram_banks: chr_ram_banks,
+               prg_ram_size: prg_ram_size,
+               prg_nvram_size: prg_nvram_size,
+               chr_ram_size: chr_ram_banks as usize * RAM_BANK_SIZE,
+               chr_nvram_size: 0,
                has_sram: has_sram,
                has_trainer: has_trainer,
                is_pc10: false,
                is_vs_unisystem: false,
-               trainer: Vec::new(), // TODO
-               prg: Vec::new(),
-               chr: Vec::new(),
+               console_type: ConsoleType::Nes,
+               expansion_device: 0,
+               trainer: trainer,
+               prg: prg,
+               chr: chr,
            })
     }
 
-    fn load_ines(bytes: &[u8]) -> Result<NesRom, &'static str> {
+    fn load_ines(bytes: &[u8]) -> Result<NesRom, RomError> {
         let (prg_rom_banks, chr_rom_banks, mapper_lo, has_trainer, has_sram, mirroring) =
             NesRom::load_common(bytes);
 
         let flags = bytes[7];
         let mapper = (flags & 0xf0) | mapper_lo;
-        let is_pc10 = (flags & 0x2) == 1; // FIXME: See clippy warning
+        let is_pc10 = flags & 0x2 != 0;
         let is_vs_unisystem = (flags & 0x1) == 1;
-        let prg_ram_banks = if bytes[8] == 0 { 1 } else { bytes[8] };
+        let prg_ram_banks = if bytes[8] == 0 { 1 } else { bytes[8] as u16 };
         let video_standard = if bytes[9] & 0x01 == 0 {
             VideoStandard::Ntsc
         } else {
@@ -154,58 +349,226 @@ impl NesRom {
         };
 
         if bytes[9] & 0xfe != 0 {
-            return Err("Invalid INes format - unexpected bits set in byte 9");
+            let reason = "Invalid INes format - unexpected bits set in byte 9";
+            return Err(RomError::MalformedHeader(reason));
         }
 
         let is_zeroed = &bytes[10..15].iter().all(|&b| b == 0);
         if !is_zeroed {
-            return Err("Invalid INes format - bytes 10-15 must be zeroed.");
+            let reason = "Invalid INes format - bytes 10-15 must be zeroed.";
+            return Err(RomError::MalformedHeader(reason));
         }
 
-        let mut trainer = Vec::new();
-        let mut chr = Vec::new();
-        let mut prg = Vec::new();
-        let prg_start: usize;
-        let prg_size: usize = prg_rom_banks as usize * 16384;
-        let chr_size: usize = chr_rom_banks as usize * 8192;
-
-        if has_trainer {
-            trainer.extend(bytes[16..528].iter().cloned());
-            prg_start = 529;
-
+        let prg_size = prg_rom_banks as usize * PRG_ROM_BANK_SIZE;
+        let chr_size = chr_rom_banks as usize * CHR_ROM_BANK_SIZE;
+        let (trainer, prg, chr) = NesRom::load_data(bytes, has_trainer, prg_size, chr_size)?;
+        let chr_ram_banks = if chr_rom_banks == 0 { 1 } else { 0 };
+        let (prg_ram_size, prg_nvram_size) = NesRom::ines_prg_ram_sizes(prg_ram_banks, has_sram);
+        let console_type = if is_pc10 {
+            ConsoleType::Playchoice10
+        } else if is_vs_unisystem {
+            ConsoleType::VsSystem {
+                ppu_type: 0,
+                hardware_type: 0,
+            }
         } else {
-            prg_start = 16;
-        }
-
-        let chr_start = prg_start + prg_size;
-
-        prg.extend(bytes[prg_start..(prg_start + prg_size)].iter().cloned());
-        chr.extend(bytes[chr_start..(chr_start + chr_size)].iter().cloned());
+            ConsoleType::Nes
+        };
 
         Ok(NesRom {
                format: RomFormat::INes,
                video_standard: video_standard,
-               mapper: mapper,
+               mapper: mapper as u16,
+               submapper: 0,
                mirroring: mirroring,
-               prg_rom_banks: prg_rom_banks,
+               prg_rom_banks: prg_rom_banks as u16,
                prg_ram_banks: prg_ram_banks,
-               chr_rom_banks: chr_rom_banks,
+               chr_rom_banks: chr_rom_banks as u16,
+               chr_ram_banks: chr_ram_banks,
+               prg_ram_size: prg_ram_size,
+               prg_nvram_size: prg_nvram_size,
+               chr_ram_size: chr_ram_banks as usize * RAM_BANK_SIZE,
+               chr_nvram_size: 0,
                has_sram: has_sram,
                has_trainer: has_trainer,
                is_pc10: is_pc10,
                is_vs_unisystem: is_vs_unisystem,
+               console_type: console_type,
+               expansion_device: 0,
                trainer: trainer,
                prg: prg,
                chr: chr,
            })
     }
 
+    // See https://wiki.nesdev.com/w/index.php/NES_2.0 for the header layout.
+    fn load_nes20(bytes: &[u8]) -> Result<NesRom, RomError> {
+        let (_, _, mapper_lo, has_trainer, has_battery, mirroring) = NesRom::load_common(bytes);
+
+        let flags = bytes[7];
+        let mapper = ((bytes[8] as u16 & 0x0f) << 8) | (flags & 0xf0) as u16 | mapper_lo as u16;
+        let submapper = bytes[8] >> 4;
+
+        let prg_size = NesRom::nes20_rom_size(bytes[4], bytes[9] & 0x0f, PRG_ROM_BANK_SIZE);
+        let prg_size = match prg_size {
+            Some(size) => size,
+            None => return Err(RomError::MalformedHeader("PRG-ROM size is too large.")),
+        };
+        let chr_size = NesRom::nes20_rom_size(bytes[5], bytes[9] >> 4, CHR_ROM_BANK_SIZE);
+        let chr_size = match chr_size {
+            Some(size) => size,
+            None => return Err(RomError::MalformedHeader("CHR-ROM size is too large.")),
+        };
+        let prg_ram_size = NesRom::nes20_ram_size(bytes[10] & 0x0f);
+        let prg_nvram_size = NesRom::nes20_ram_size(bytes[10] >> 4);
+        let chr_ram_size = NesRom::nes20_ram_size(bytes[11] & 0x0f);
+        let chr_nvram_size = NesRom::nes20_ram_size(bytes[11] >> 4);
+
+        let video_standard = match bytes[12] & 0b11 {
+            0 => VideoStandard::Ntsc,
+            1 => VideoStandard::Pal,
+            2 => VideoStandard::MultipleRegion,
+            3 => VideoStandard::Dendy,
+            _ => unreachable!(),
+        };
+
+        let console_type = match flags & 0b11 {
+            0 => ConsoleType::Nes,
+            1 => {
+                ConsoleType::VsSystem {
+                    ppu_type: bytes[13] & 0x0f,
+                    hardware_type: bytes[13] >> 4,
+                }
+            }
+            2 => ConsoleType::Playchoice10,
+            3 => ConsoleType::Extended(bytes[13] & 0x0f),
+            _ => unreachable!(),
+        };
+
+        let (trainer, prg, chr) = NesRom::load_data(bytes, has_trainer, prg_size, chr_size)?;
+
+        let prg_ram_banks = NesRom::bank_count(prg_ram_size + prg_nvram_size, RAM_BANK_SIZE);
+        let chr_ram_banks = NesRom::bank_count(chr_ram_size + chr_nvram_size, RAM_BANK_SIZE);
+        Ok(NesRom {
+               format: RomFormat::Nes20,
+               video_standard: video_standard,
+               mapper: mapper,
+               submapper: submapper,
+               mirroring: mirroring,
+               prg_rom_banks: NesRom::bank_count(prg_size, PRG_ROM_BANK_SIZE) as u16,
+               prg_ram_banks: prg_ram_banks as u16,
+               chr_rom_banks: NesRom::bank_count(chr_size, CHR_ROM_BANK_SIZE) as u16,
+               chr_ram_banks: chr_ram_banks as u16,
+               prg_ram_size: prg_ram_size,
+               prg_nvram_size: prg_nvram_size,
+               chr_ram_size: chr_ram_size,
+               chr_nvram_size: chr_nvram_size,
+               has_sram: has_battery || prg_nvram_size > 0,
+               has_trainer: has_trainer,
+               is_pc10: console_type == ConsoleType::Playchoice10,
+               is_vs_unisystem: flags & 0b11 == 1,
+               console_type: console_type,
+               expansion_device: bytes[15] & 0x3f,
+               trainer: trainer,
+               prg: prg,
+               chr: chr,
+           })
+    }
+
+    fn load_data(bytes: &[u8],
+                 has_trainer: bool,
+                 prg_size: usize,
+                 chr_size: usize)
+                 -> Result<(Vec<u8>, Vec<u8>, Vec<u8>), RomError> {
+        let mut trainer = Vec::new();
+        let mut chr = Vec::new();
+        let mut prg = Vec::new();
+        let prg_start = if has_trainer {
+            HEADER_SIZE + TRAINER_SIZE
+        } else {
+            HEADER_SIZE
+        };
+
+        // NES 2.0 sizes can be large enough to overflow when they're added up
+        let chr_start = prg_start.checked_add(prg_size);
+        let expected = chr_start.and_then(|chr_start| chr_start.checked_add(chr_size));
+        let (chr_start, expected) = match (chr_start, expected) {
+            (Some(chr_start), Some(expected)) => (chr_start, expected),
+            _ => return Err(RomError::MalformedHeader("ROM sizes are too large.")),
+        };
+        if bytes.len() < expected {
+            return Err(RomError::Truncated {
+                           expected: expected,
+                           actual: bytes.len(),
+                       });
+        }
+
+        if has_trainer {
+            trainer.extend(bytes[HEADER_SIZE..prg_start].iter().cloned());
+        }
+        prg.extend(bytes[prg_start..chr_start].iter().cloned());
+        chr.extend(bytes[chr_start..expected].iter().cloned());
+        Ok((trainer, prg, chr))
+    }
+
+    // INes headers have a single PRG-RAM size, which is battery-backed if the battery flag is set.
+    fn ines_prg_ram_sizes(prg_ram_banks: u16, has_sram: bool) -> (usize, usize) {
+        let size = prg_ram_banks as usize * RAM_BANK_SIZE;
+        if has_sram { (0, size) } else { (size, 0) }
+    }
+
+    // The size MSB nibble is normally the upper bits of a bank count. $F instead means the LSB
+    // byte holds an exponent and multiplier: EEEEEEMM = 2^E * (MM * 2 + 1) bytes.
+    // Exponents go up to 63, so sizes that don't fit in a usize give `None`.
+    fn nes20_rom_size(lsb: u8, msb: u8, bank_size: usize) -> Option<usize> {
+        if msb == 0x0f {
+            let exponent = (lsb >> 2) as u32;
+            let multiplier = (lsb & 0b11) as usize * 2 + 1;
+            1_usize
+                .checked_shl(exponent)
+                .and_then(|size| size.checked_mul(multiplier))
+        } else {
+            Some((((msb as usize) << 8) | lsb as usize) * bank_size)
+        }
+    }
+
+    // RAM sizes are given as a shift count, where 0 means none and otherwise 64 << shift bytes.
+    fn nes20_ram_size(shift: u8) -> usize {
+        if shift == 0 { 0 } else { 64 << shift }
+    }
+
+    fn nes20_file_size(bytes: &[u8]) -> Option<usize> {
+        let trainer_size = if bytes[6] & 0x4 != 0 { TRAINER_SIZE } else { 0 };
+        let prg_size = NesRom::nes20_rom_size(bytes[4], bytes[9] & 0x0f, PRG_ROM_BANK_SIZE);
+        let chr_size = NesRom::nes20_rom_size(bytes[5], bytes[9] >> 4, CHR_ROM_BANK_SIZE);
+        match (prg_size, chr_size) {
+            (Some(prg_size), Some(chr_size)) => {
+                (HEADER_SIZE + trainer_size)
+                    .checked_add(prg_size)
+                    .and_then(|size| size.checked_add(chr_size))
+            }
+            _ => None,
+        }
+    }
+
+    fn bank_count(size: usize, bank_size: usize) -> usize {
+        (size + bank_size - 1) / bank_size
+    }
+
     // See http://wiki.nesdev.com/w/index.php/INES#Variant_comparison for
     // explanation of rom format detection.
     fn determine_format(bytes: &[u8], bytes_read: usize) -> RomFormat {
+        if unif::is_unif(bytes) {
+            return RomFormat::Unif;
+        }
 
-        // FIXME: Logic for determining Nes20 format is most certainly wrong.
-        if bytes[7] & 0x0c == 0x08 && bytes[9] as usize <= bytes_read {
+        // A NES 2.0 header is only trusted if the ROM sizes it describes fit in the file, since
+        // byte 9 holds the upper bits of those sizes
+        let nes20_fits = match NesRom::nes20_file_size(bytes) {
+            Some(size) => size <= bytes_read,
+            None => false,
+        };
+        if bytes[7] & 0x0c == 0x08 && nes20_fits {
             RomFormat::Nes20
         } else if bytes[7] & 0x0c == 0x00 && bytes[12] == 0 && bytes[13] == 0 &&
                   bytes[14] == 0 && bytes[15] == 0 {
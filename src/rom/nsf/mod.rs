@@ -0,0 +1,124 @@
+//! NES Sound Format files, which hold the music code and data ripped from a game along with the
+//! addresses of the routines that start a song and play it a frame at a time.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::{RomError, VideoStandard};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+const HEADER_SIZE: usize = 0x80;
+const DEFAULT_NTSC_SPEED: u16 = 16639;
+const DEFAULT_PAL_SPEED: u16 = 19997;
+
+/// Expansion sound chips, as flagged in byte $7B of the header
+pub const VRC6: u8 = 0x01;
+pub const VRC7: u8 = 0x02;
+pub const FDS: u8 = 0x04;
+pub const MMC5: u8 = 0x08;
+pub const NAMCO_163: u8 = 0x10;
+pub const SUNSOFT_5B: u8 = 0x20;
+
+pub struct NsfFile {
+    pub version: u8,
+    pub total_songs: u8,
+    /// The song to play first, starting at 1
+    pub starting_song: u8,
+    pub load_address: u16,
+    pub init_address: u16,
+    pub play_address: u16,
+    pub name: String,
+    pub artist: String,
+    pub copyright: String,
+    /// Microseconds between PLAY calls on NTSC machines
+    pub ntsc_speed: u16,
+    /// Microseconds between PLAY calls on PAL machines
+    pub pal_speed: u16,
+    /// Initial 4KB bank numbers for $8000-$FFFF. The tune uses bank switching if any are non-zero.
+    pub bankswitch_init: [u8; 8],
+    pub video_standard: VideoStandard,
+    pub expansion_chips: u8,
+    pub data: Vec<u8>,
+}
+
+impl NsfFile {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<NsfFile, RomError> {
+        let mut f = File::open(path)?;
+        let mut vec = Vec::<u8>::new();
+        f.read_to_end(&mut vec)?;
+        NsfFile::from_bytes(&vec)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<NsfFile, RomError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(RomError::Truncated {
+                           expected: HEADER_SIZE,
+                           actual: bytes.len(),
+                       });
+        }
+
+        if &bytes[0..5] != b"NESM\x1a" {
+            return Err(RomError::BadMagic);
+        }
+
+        let load_address = word(bytes, 0x08);
+        if load_address < 0x6000 {
+            return Err(RomError::MalformedHeader("NSF load address is below $6000."));
+        }
+
+        let mut bankswitch_init = [0; 8];
+        bankswitch_init.copy_from_slice(&bytes[0x70..0x78]);
+
+        // ---- --DP
+        // Dual NTSC/PAL (D), PAL (P)
+        let video_standard = if bytes[0x7a] & 0x02 != 0 {
+            VideoStandard::MultipleRegion
+        } else if bytes[0x7a] & 0x01 != 0 {
+            VideoStandard::Pal
+        } else {
+            VideoStandard::Ntsc
+        };
+
+        Ok(NsfFile {
+               version: bytes[0x05],
+               total_songs: bytes[0x06],
+               starting_song: bytes[0x07],
+               load_address: load_address,
+               init_address: word(bytes, 0x0a),
+               play_address: word(bytes, 0x0c),
+               name: text(&bytes[0x0e..0x2e]),
+               artist: text(&bytes[0x2e..0x4e]),
+               copyright: text(&bytes[0x4e..0x6e]),
+               ntsc_speed: nonzero_or(word(bytes, 0x6e), DEFAULT_NTSC_SPEED),
+               pal_speed: nonzero_or(word(bytes, 0x78), DEFAULT_PAL_SPEED),
+               bankswitch_init: bankswitch_init,
+               video_standard: video_standard,
+               expansion_chips: bytes[0x7b],
+               data: bytes[HEADER_SIZE..].to_vec(),
+           })
+    }
+
+    pub fn is_bankswitched(&self) -> bool {
+        self.bankswitch_init.iter().any(|bank| *bank != 0)
+    }
+
+    pub fn has_expansion_chip(&self, chip: u8) -> bool {
+        self.expansion_chips & chip != 0
+    }
+}
+
+fn word(bytes: &[u8], offset: usize) -> u16 {
+    bytes[offset] as u16 | (bytes[offset + 1] as u16) << 8
+}
+
+fn nonzero_or(val: u16, default: u16) -> u16 {
+    if val == 0 { default } else { val }
+}
+
+// Header strings are null-terminated unless they fill the whole field
+fn text(bytes: &[u8]) -> String {
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..len]).into_owned()
+}
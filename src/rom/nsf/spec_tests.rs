@@ -0,0 +1,75 @@
+use super::*;
+
+#[test]
+fn from_bytes() {
+    let nsf = NsfFile::from_bytes(&nsf_fixture()).unwrap();
+    assert_eq!(1, nsf.version);
+    assert_eq!(3, nsf.total_songs);
+    assert_eq!(2, nsf.starting_song);
+    assert_eq!(0x8000, nsf.load_address);
+    assert_eq!(0x8010, nsf.init_address);
+    assert_eq!(0x8020, nsf.play_address);
+    assert_eq!("Song", nsf.name);
+    assert_eq!("Artist", nsf.artist);
+    assert_eq!("", nsf.copyright);
+    assert_eq!(16666, nsf.ntsc_speed);
+    assert_eq!(DEFAULT_PAL_SPEED, nsf.pal_speed);
+    assert_eq!(VideoStandard::Ntsc, nsf.video_standard);
+    assert_eq!(false, nsf.is_bankswitched());
+    assert_eq!(true, nsf.has_expansion_chip(FDS));
+    assert_eq!(false, nsf.has_expansion_chip(VRC6));
+    assert_eq!(vec![0xea, 0x60], nsf.data);
+}
+
+#[test]
+fn bankswitched() {
+    let mut bytes = nsf_fixture();
+    bytes[0x77] = 1;
+    let nsf = NsfFile::from_bytes(&bytes).unwrap();
+    assert_eq!(true, nsf.is_bankswitched());
+    assert_eq!([0, 0, 0, 0, 0, 0, 0, 1], nsf.bankswitch_init);
+}
+
+#[test]
+fn video_standard() {
+    let mut bytes = nsf_fixture();
+    bytes[0x7a] = 0x01;
+    assert_eq!(VideoStandard::Pal,
+               NsfFile::from_bytes(&bytes).unwrap().video_standard);
+    bytes[0x7a] = 0x03;
+    assert_eq!(VideoStandard::MultipleRegion,
+               NsfFile::from_bytes(&bytes).unwrap().video_standard);
+}
+
+#[test]
+fn bad_magic() {
+    let mut bytes = nsf_fixture();
+    bytes[0] = b'X';
+    match NsfFile::from_bytes(&bytes) {
+        Err(RomError::BadMagic) => (),
+        _ => panic!("expected a bad magic error"),
+    }
+}
+
+#[test]
+fn truncated_header() {
+    match NsfFile::from_bytes(&[0; 0x20]) {
+        Err(RomError::Truncated { expected: 0x80, actual: 0x20 }) => (),
+        _ => panic!("expected a truncated error"),
+    }
+}
+
+fn nsf_fixture() -> Vec<u8> {
+    let mut bytes = vec![0; HEADER_SIZE];
+    bytes[0..5].copy_from_slice(b"NESM\x1a");
+    bytes[0x05] = 1;
+    bytes[0x06] = 3;
+    bytes[0x07] = 2;
+    bytes[0x08..0x0e].copy_from_slice(&[0x00, 0x80, 0x10, 0x80, 0x20, 0x80]);
+    bytes[0x0e..0x12].copy_from_slice(b"Song");
+    bytes[0x2e..0x34].copy_from_slice(b"Artist");
+    bytes[0x6e..0x70].copy_from_slice(&[0x1a, 0x41]);
+    bytes[0x7b] = FDS;
+    bytes.extend_from_slice(&[0xea, 0x60]);
+    bytes
+}
@@ -0,0 +1,316 @@
+//! Soft-patching, which applies a patch to the raw bytes of a ROM file as it is loaded so that
+//! patched copies don't need to be kept around. Three formats are supported:
+//!
+//! - IPS: Records that overwrite the file at 24-bit offsets. There is no checksum.
+//! - UPS: Runs of bytes XORed with the file at variable-length offsets.
+//! - BPS: Copies from the source file, the patch or the output built so far.
+//!
+//! UPS and BPS patches carry CRC32s of the source file, the output and the patch itself, which are
+//! all checked.
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::RomError;
+use crc::crc32;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const IPS_MAGIC: &'static [u8] = b"PATCH";
+const IPS_EOF: &'static [u8] = b"EOF";
+const UPS_MAGIC: &'static [u8] = b"UPS1";
+const BPS_MAGIC: &'static [u8] = b"BPS1";
+
+// Source, target and patch CRC32s at the end of UPS and BPS patches
+const FOOTER_SIZE: usize = 12;
+
+// UPS and BPS patches give the size of their output up front. Nothing close to this is a real NES
+// image, so larger sizes are assumed to be corrupt rather than allocated.
+const MAX_TARGET_SIZE: usize = 0x1000_0000;
+
+// Patch extensions, in the order they are looked for
+const PATCH_EXTENSIONS: [&'static str; 3] = ["ips", "ups", "bps"];
+
+/// Finds a patch with the same name as the ROM, in the same directory.
+pub fn find_patch<P: AsRef<Path>>(rom_path: P) -> Option<PathBuf> {
+    PATCH_EXTENSIONS
+        .iter()
+        .map(|ext| rom_path.as_ref().with_extension(ext))
+        .find(|path| path.is_file())
+}
+
+/// Reads a patch file and applies it to the given ROM file bytes.
+pub fn apply_file<P: AsRef<Path>>(rom: &[u8], patch_path: P) -> Result<Vec<u8>, RomError> {
+    let mut f = File::open(patch_path)?;
+    let mut patch = Vec::<u8>::new();
+    f.read_to_end(&mut patch)?;
+    apply(rom, &patch)
+}
+
+/// Applies a patch to the given ROM file bytes, detecting the patch format from its header.
+pub fn apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, RomError> {
+    if patch.starts_with(IPS_MAGIC) {
+        apply_ips(rom, patch)
+    } else if patch.starts_with(UPS_MAGIC) {
+        apply_ups(rom, patch)
+    } else if patch.starts_with(BPS_MAGIC) {
+        apply_bps(rom, patch)
+    } else {
+        Err(RomError::BadPatch("Unrecognized patch format."))
+    }
+}
+
+pub fn apply_ips(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, RomError> {
+    let mut reader = PatchReader::new(patch, IPS_MAGIC.len());
+    let mut target = rom.to_vec();
+    loop {
+        let offset = reader.bytes(3)?;
+        if offset == IPS_EOF {
+            break;
+        }
+
+        let offset = (offset[0] as usize) << 16 | (offset[1] as usize) << 8 | offset[2] as usize;
+        let size = reader.u16_be()? as usize;
+        if size == 0 {
+            // Run-length encoded record
+            let count = reader.u16_be()? as usize;
+            let value = reader.byte()?;
+            write_at(&mut target, offset, &vec![value; count]);
+        } else {
+            let data = reader.bytes(size)?;
+            write_at(&mut target, offset, data);
+        }
+    }
+
+    // An extension some patchers use to truncate the file
+    if reader.remaining() == 3 {
+        let size = reader.bytes(3)?;
+        let size = (size[0] as usize) << 16 | (size[1] as usize) << 8 | size[2] as usize;
+        target.truncate(size);
+    }
+
+    Ok(target)
+}
+
+pub fn apply_ups(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, RomError> {
+    let footer = Footer::read(patch, UPS_MAGIC.len())?;
+    let mut reader = PatchReader::new(&patch[..patch.len() - FOOTER_SIZE], UPS_MAGIC.len());
+    let source_size = reader.varint()?;
+    let target_size = reader.target_size()?;
+    footer.check_source(rom, source_size)?;
+
+    let mut target = rom.to_vec();
+    target.resize(target_size, 0);
+    let mut offset = 0;
+    while reader.remaining() > 0 {
+        offset += reader.varint()?;
+
+        // XOR until a zero byte, which ends the run and skips the byte it lines up with
+        loop {
+            let byte = reader.byte()?;
+            if offset < target_size {
+                target[offset] ^= byte;
+            }
+            offset += 1;
+            if byte == 0 {
+                break;
+            }
+        }
+    }
+
+    footer.check_target(&target)?;
+    Ok(target)
+}
+
+pub fn apply_bps(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, RomError> {
+    let footer = Footer::read(patch, BPS_MAGIC.len())?;
+    let mut reader = PatchReader::new(&patch[..patch.len() - FOOTER_SIZE], BPS_MAGIC.len());
+    let source_size = reader.varint()?;
+    let target_size = reader.target_size()?;
+    let metadata_size = reader.varint()?;
+    reader.bytes(metadata_size)?;
+    footer.check_source(rom, source_size)?;
+
+    let mut target = Vec::new();
+    let mut source_offset = 0;
+    let mut target_offset = 0;
+    while reader.remaining() > 0 {
+        // The shift leaves room for the 1 to be added without overflowing
+        let action = reader.varint()?;
+        let length = (action >> 2) + 1;
+        if length > target_size - target.len() {
+            return Err(RomError::BadPatch("Patch output is the wrong size."));
+        }
+
+        match action & 0x3 {
+            // Source read: copy from the same offset in the source
+            0 => {
+                let start = target.len();
+                target.extend_from_slice(source_range(rom, start, length)?);
+            }
+            // Target read: copy from the patch
+            1 => target.extend_from_slice(reader.bytes(length)?),
+            // Source copy: copy from anywhere in the source
+            2 => {
+                source_offset = reader.relative_offset(source_offset)?;
+                target.extend_from_slice(source_range(rom, source_offset, length)?);
+                source_offset += length;
+            }
+            // Target copy: copy from the output so far, a byte at a time since it can overlap
+            _ => {
+                target_offset = reader.relative_offset(target_offset)?;
+                for _ in 0..length {
+                    let byte = target.get(target_offset)
+                        .cloned()
+                        .ok_or(RomError::BadPatch("Patch reads past the end of the output."))?;
+                    target.push(byte);
+                    target_offset += 1;
+                }
+            }
+        }
+    }
+
+    if target.len() != target_size {
+        return Err(RomError::BadPatch("Patch output is the wrong size."));
+    }
+
+    footer.check_target(&target)?;
+    Ok(target)
+}
+
+// The source bytes a BPS action copies, which have to be within the ROM
+fn source_range(rom: &[u8], start: usize, length: usize) -> Result<&[u8], RomError> {
+    start
+        .checked_add(length)
+        .and_then(|end| rom.get(start..end))
+        .ok_or(RomError::BadPatch("Patch reads past the end of the ROM."))
+}
+
+fn write_at(target: &mut Vec<u8>, offset: usize, data: &[u8]) {
+    let end = offset + data.len();
+    if target.len() < end {
+        target.resize(end, 0);
+    }
+    target[offset..end].copy_from_slice(data);
+}
+
+struct Footer {
+    source_crc: u32,
+    target_crc: u32,
+}
+
+impl Footer {
+    fn read(patch: &[u8], magic_size: usize) -> Result<Footer, RomError> {
+        if patch.len() < magic_size + FOOTER_SIZE {
+            return Err(RomError::BadPatch("Patch is truncated."));
+        }
+
+        let footer = &patch[patch.len() - FOOTER_SIZE..];
+        if crc32::checksum_ieee(&patch[..patch.len() - 4]) != u32_le(&footer[8..12]) {
+            return Err(RomError::BadPatch("Patch checksum mismatch."));
+        }
+
+        Ok(Footer {
+               source_crc: u32_le(&footer[0..4]),
+               target_crc: u32_le(&footer[4..8]),
+           })
+    }
+
+    fn check_source(&self, rom: &[u8], source_size: usize) -> Result<(), RomError> {
+        if rom.len() != source_size || crc32::checksum_ieee(rom) != self.source_crc {
+            return Err(RomError::BadPatch("Patch is for a different ROM."));
+        }
+        Ok(())
+    }
+
+    fn check_target(&self, target: &[u8]) -> Result<(), RomError> {
+        if crc32::checksum_ieee(target) != self.target_crc {
+            return Err(RomError::BadPatch("Patched ROM checksum mismatch."));
+        }
+        Ok(())
+    }
+}
+
+fn u32_le(bytes: &[u8]) -> u32 {
+    bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 | (bytes[3] as u32) << 24
+}
+
+struct PatchReader<'a> {
+    patch: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PatchReader<'a> {
+    fn new(patch: &'a [u8], pos: usize) -> Self {
+        PatchReader {
+            patch: patch,
+            pos: pos,
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.patch.len() - self.pos
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], RomError> {
+        if self.remaining() < len {
+            return Err(RomError::BadPatch("Patch is truncated."));
+        }
+        let bytes = &self.patch[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, RomError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16_be(&mut self) -> Result<u16, RomError> {
+        let bytes = self.bytes(2)?;
+        Ok((bytes[0] as u16) << 8 | bytes[1] as u16)
+    }
+
+    // UPS and BPS number encoding: 7 bits per byte, least significant first, with the high bit set
+    // on the last byte. Each continuation also adds one, so that every number has a single
+    // encoding.
+    fn varint(&mut self) -> Result<usize, RomError> {
+        let mut value = 0_usize;
+        let mut shift = 1_usize;
+        loop {
+            let byte = self.byte()?;
+            value = (byte as usize & 0x7f)
+                .checked_mul(shift)
+                .and_then(|digit| value.checked_add(digit))
+                .ok_or(RomError::BadPatch("Patch number is too large."))?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift.checked_mul(0x80)
+                .ok_or(RomError::BadPatch("Patch number is too large."))?;
+            value = value.checked_add(shift)
+                .ok_or(RomError::BadPatch("Patch number is too large."))?;
+        }
+    }
+
+    fn target_size(&mut self) -> Result<usize, RomError> {
+        let size = self.varint()?;
+        if size > MAX_TARGET_SIZE {
+            return Err(RomError::BadPatch("Patch output is too large."));
+        }
+        Ok(size)
+    }
+
+    // BPS relative offsets store the sign in the lowest bit. Moving before the start of the file
+    // is an error, since the offset can't be used.
+    fn relative_offset(&mut self, offset: usize) -> Result<usize, RomError> {
+        let value = self.varint()?;
+        let magnitude = value >> 1;
+        let offset = if value & 1 != 0 {
+            offset.checked_sub(magnitude)
+        } else {
+            offset.checked_add(magnitude)
+        };
+        offset.ok_or(RomError::BadPatch("Patch offset is out of range."))
+    }
+}
@@ -0,0 +1,265 @@
+use super::*;
+use std::env;
+use std::fs;
+use std::process;
+
+#[test]
+fn ips() {
+    let rom = vec![0; 8];
+    let mut patch = b"PATCH".to_vec();
+    patch.extend_from_slice(&[0x00, 0x00, 0x02, 0x00, 0x02, 0x11, 0x22]);
+    // RLE record
+    patch.extend_from_slice(&[0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x02, 0x33]);
+    patch.extend_from_slice(b"EOF");
+    assert_eq!(vec![0, 0, 0x11, 0x22, 0, 0x33, 0x33, 0], apply(&rom, &patch).unwrap());
+}
+
+#[test]
+fn ips_extends_rom() {
+    let rom = vec![0; 2];
+    let mut patch = b"PATCH".to_vec();
+    patch.extend_from_slice(&[0x00, 0x00, 0x03, 0x00, 0x01, 0x11]);
+    patch.extend_from_slice(b"EOF");
+    assert_eq!(vec![0, 0, 0, 0x11], apply(&rom, &patch).unwrap());
+}
+
+#[test]
+fn ips_truncates_rom() {
+    let rom = vec![0; 8];
+    let mut patch = b"PATCH".to_vec();
+    patch.extend_from_slice(b"EOF");
+    patch.extend_from_slice(&[0x00, 0x00, 0x04]);
+    assert_eq!(vec![0; 4], apply(&rom, &patch).unwrap());
+}
+
+#[test]
+fn ips_truncated_patch() {
+    let mut patch = b"PATCH".to_vec();
+    patch.extend_from_slice(&[0x00, 0x00, 0x02, 0x00, 0x02, 0x11]);
+    assert_bad_patch("Patch is truncated.", apply(&[0; 8], &patch));
+}
+
+#[test]
+fn ups() {
+    let rom = vec![1, 2, 3, 4];
+    let target = vec![1, 0xff, 3, 4, 5, 6];
+    let patch = ups_patch(&rom, &target);
+    assert_eq!(target, apply(&rom, &patch).unwrap());
+}
+
+#[test]
+fn ups_shrinks_rom() {
+    let rom = vec![1, 2, 3, 4];
+    let target = vec![1, 2];
+    let patch = ups_patch(&rom, &target);
+    assert_eq!(target, apply(&rom, &patch).unwrap());
+}
+
+#[test]
+fn ups_wrong_rom() {
+    let patch = ups_patch(&[1, 2, 3, 4], &[1, 2, 3, 5]);
+    assert_bad_patch("Patch is for a different ROM.", apply(&[1, 2, 3, 3], &patch));
+}
+
+#[test]
+fn ups_corrupt_patch() {
+    let mut patch = ups_patch(&[1, 2, 3, 4], &[1, 2, 3, 5]);
+    patch[6] ^= 0xff;
+    assert_bad_patch("Patch checksum mismatch.", apply(&[1, 2, 3, 4], &patch));
+}
+
+#[test]
+fn bps() {
+    let rom = vec![1, 2, 3, 4, 5, 6, 7, 8];
+    let target = vec![1, 2, 0xaa, 0xbb, 7, 8, 0xaa, 0xbb, 7, 8, 0xaa];
+
+    let mut body = Vec::new();
+    // Source read of 2 bytes
+    push_varint(&mut body, (2 - 1) << 2);
+    // Target read of 2 bytes
+    push_varint(&mut body, ((2 - 1) << 2) | 1);
+    body.extend_from_slice(&[0xaa, 0xbb]);
+    // Source copy of 2 bytes from offset 6
+    push_varint(&mut body, ((2 - 1) << 2) | 2);
+    push_varint(&mut body, 6 << 1);
+    // Target copy of 5 bytes from offset 2, which overlaps the bytes being written
+    push_varint(&mut body, ((5 - 1) << 2) | 3);
+    push_varint(&mut body, 2 << 1);
+
+    let patch = bps_patch(&rom, &target, &body);
+    assert_eq!(target, apply(&rom, &patch).unwrap());
+}
+
+#[test]
+fn bps_negative_offsets() {
+    let rom = vec![1, 2, 3, 4];
+    let target = vec![3, 4, 1, 2];
+
+    let mut body = Vec::new();
+    push_varint(&mut body, ((2 - 1) << 2) | 2);
+    push_varint(&mut body, 2 << 1);
+    // Back 4 from the end of the previous copy
+    push_varint(&mut body, ((2 - 1) << 2) | 2);
+    push_varint(&mut body, (4 << 1) | 1);
+
+    let patch = bps_patch(&rom, &target, &body);
+    assert_eq!(target, apply(&rom, &patch).unwrap());
+}
+
+#[test]
+fn bps_wrong_output() {
+    let rom = vec![1, 2, 3, 4];
+    let mut body = Vec::new();
+    push_varint(&mut body, (4 - 1) << 2);
+
+    // Claims the output will be different to what the actions produce
+    let patch = bps_patch(&rom, &[1, 2, 3, 5], &body);
+    assert_bad_patch("Patched ROM checksum mismatch.", apply(&rom, &patch));
+}
+
+#[test]
+fn bps_offset_before_start_of_rom() {
+    let rom = vec![1, 2, 3, 4];
+    let mut body = Vec::new();
+    push_varint(&mut body, ((2 - 1) << 2) | 2);
+    push_varint(&mut body, (1 << 1) | 1);
+
+    let patch = bps_patch(&rom, &[1, 2], &body);
+    assert_bad_patch("Patch offset is out of range.", apply(&rom, &patch));
+}
+
+#[test]
+fn bps_output_longer_than_target_size() {
+    let rom = vec![1, 2, 3, 4];
+    let mut body = Vec::new();
+    push_varint(&mut body, (1 - 1) << 2);
+    // A target copy that repeats the first byte far more times than the output has room for
+    push_varint(&mut body, (0x10000 << 2) | 3);
+    push_varint(&mut body, 0);
+
+    let patch = bps_patch(&rom, &[1, 1, 1, 1], &body);
+    assert_bad_patch("Patch output is the wrong size.", apply(&rom, &patch));
+}
+
+#[test]
+fn huge_target_size() {
+    let rom = vec![1, 2, 3, 4];
+    for magic in &[b"UPS1", b"BPS1"] {
+        let mut patch = magic.to_vec();
+        push_varint(&mut patch, rom.len());
+        push_varint(&mut patch, 0x2000_0000);
+        push_varint(&mut patch, 0);
+        push_footer(&mut patch, &rom, &rom);
+        assert_bad_patch("Patch output is too large.", apply(&rom, &patch));
+    }
+}
+
+#[test]
+fn varint_overflow() {
+    let mut patch = vec![0; 12];
+    patch.push(0x80);
+    let mut reader = PatchReader::new(&patch, 0);
+    match reader.varint() {
+        Err(RomError::BadPatch("Patch number is too large.")) => (),
+        _ => panic!("expected a number overflow error"),
+    }
+}
+
+#[test]
+fn unrecognized_format() {
+    assert_bad_patch("Unrecognized patch format.", apply(&[0; 4], b"ZIP"));
+}
+
+#[test]
+fn varint() {
+    let mut bytes = Vec::new();
+    for value in &[0, 1, 0x7f, 0x80, 0x4000, 0x123456] {
+        bytes.clear();
+        push_varint(&mut bytes, *value);
+        assert_eq!(*value, PatchReader::new(&bytes, 0).varint().unwrap());
+    }
+}
+
+#[test]
+fn find_patch_next_to_rom() {
+    let dir = env::temp_dir().join(format!("rs_nes_patch_{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let rom_path = dir.join("game.nes");
+    assert_eq!(None, find_patch(&rom_path));
+
+    File::create(dir.join("game.bps")).unwrap();
+    File::create(dir.join("game.ups")).unwrap();
+    assert_eq!(Some(dir.join("game.ups")), find_patch(&rom_path));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn assert_bad_patch(expected: &str, result: Result<Vec<u8>, RomError>) {
+    match result {
+        Err(RomError::BadPatch(reason)) => assert_eq!(expected, reason),
+        _ => panic!("expected a bad patch error"),
+    }
+}
+
+fn push_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let x = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(0x80 | x);
+            return;
+        }
+        bytes.push(x);
+        value -= 1;
+    }
+}
+
+fn push_footer(patch: &mut Vec<u8>, source: &[u8], target: &[u8]) {
+    for crc in &[crc32::checksum_ieee(source), crc32::checksum_ieee(target)] {
+        push_u32_le(patch, *crc);
+    }
+    let patch_crc = crc32::checksum_ieee(patch);
+    push_u32_le(patch, patch_crc);
+}
+
+fn push_u32_le(bytes: &mut Vec<u8>, val: u32) {
+    bytes.extend_from_slice(&[val as u8, (val >> 8) as u8, (val >> 16) as u8, (val >> 24) as u8]);
+}
+
+// A UPS patch with an XOR run for each changed part of the target
+fn ups_patch(source: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut patch = b"UPS1".to_vec();
+    push_varint(&mut patch, source.len());
+    push_varint(&mut patch, target.len());
+
+    let xor = |i: usize| target[i] ^ source.get(i).cloned().unwrap_or(0);
+    let mut run_end = 0;
+    let mut i = 0;
+    while i < target.len() {
+        if xor(i) == 0 {
+            i += 1;
+            continue;
+        }
+
+        push_varint(&mut patch, i - run_end);
+        while i < target.len() && xor(i) != 0 {
+            patch.push(xor(i));
+            i += 1;
+        }
+        patch.push(0);
+        i += 1;
+        run_end = i;
+    }
+
+    push_footer(&mut patch, source, target);
+    patch
+}
+
+fn bps_patch(source: &[u8], target: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut patch = b"BPS1".to_vec();
+    push_varint(&mut patch, source.len());
+    push_varint(&mut patch, target.len());
+    push_varint(&mut patch, 0);
+    patch.extend_from_slice(body);
+    push_footer(&mut patch, source, target);
+    patch
+}
@@ -0,0 +1,297 @@
+use super::*;
+
+#[test]
+fn nes20_detection() {
+    let rom = nes20_fixture(&[]);
+    assert_eq!(RomFormat::Nes20, NesRom::determine_format(&rom, rom.len()));
+}
+
+#[test]
+fn nes20_header_with_sizes_larger_than_file_is_not_trusted() {
+    // Byte 9 claims far more PRG-ROM than the file contains
+    let rom = nes20_fixture(&[(9, 0x01)]);
+    assert!(RomFormat::Nes20 != NesRom::determine_format(&rom, rom.len()));
+}
+
+#[test]
+fn ines_detection() {
+    let mut rom = nes20_fixture(&[]);
+    rom[7] = 0;
+    assert_eq!(RomFormat::INes, NesRom::determine_format(&rom, rom.len()));
+}
+
+#[test]
+fn nes20_mapper_and_submapper() {
+    let rom = nes20_fixture(&[(6, 0x10), (7, 0x28), (8, 0x31)]);
+    let rom = NesRom::load_nes20(&rom).unwrap();
+    assert_eq!(0x121, rom.mapper);
+    assert_eq!(3, rom.submapper);
+}
+
+#[test]
+fn nes20_rom_sizes() {
+    let rom = NesRom::load_nes20(&nes20_fixture(&[])).unwrap();
+    assert_eq!(2, rom.prg_rom_banks);
+    assert_eq!(1, rom.chr_rom_banks);
+    assert_eq!(0x8000, rom.prg.len());
+    assert_eq!(0x2000, rom.chr.len());
+    assert_eq!(0x01, rom.prg[0]);
+    assert_eq!(0x02, rom.chr[0]);
+}
+
+#[test]
+fn nes20_exponent_multiplier_rom_size() {
+    // 2^14 * (1 * 2 + 1) = 48KB
+    assert_eq!(Some(0xc000), NesRom::nes20_rom_size(0b0011_1001, 0x0f, PRG_ROM_BANK_SIZE));
+    assert_eq!(Some(0x12000), NesRom::nes20_rom_size(0x09, 0x00, CHR_ROM_BANK_SIZE));
+}
+
+#[test]
+fn nes20_oversized_rom_size_is_malformed() {
+    // 2^63 * 7 bytes doesn't fit in a usize
+    assert_eq!(None, NesRom::nes20_rom_size(0xff, 0x0f, PRG_ROM_BANK_SIZE));
+
+    let rom = nes20_fixture(&[(4, 0xff), (9, 0x0f)]);
+    assert!(RomFormat::Nes20 != NesRom::determine_format(&rom, rom.len()));
+    match NesRom::load_nes20(&rom) {
+        Err(RomError::MalformedHeader(_)) => (),
+        _ => panic!("expected a malformed header error"),
+    }
+}
+
+#[test]
+fn nes20_ram_sizes() {
+    let rom = nes20_fixture(&[(5, 0), (10, 0x97), (11, 0x07)]);
+    let rom = NesRom::load_nes20(&rom).unwrap();
+    assert_eq!(0x2000, rom.prg_ram_size);
+    assert_eq!(0x8000, rom.prg_nvram_size);
+    assert_eq!(0x2000, rom.chr_ram_size);
+    assert_eq!(0, rom.chr_nvram_size);
+    assert_eq!(5, rom.prg_ram_banks);
+    assert_eq!(1, rom.chr_ram_banks);
+    assert_eq!(true, rom.has_sram);
+}
+
+#[test]
+fn nes20_largest_ram_size() {
+    // 64 << 15 bytes is 256 8KB banks
+    let rom = nes20_fixture(&[(10, 0x0f)]);
+    let rom = NesRom::load_nes20(&rom).unwrap();
+    assert_eq!(0x200000, rom.prg_ram_size);
+    assert_eq!(256, rom.prg_ram_banks);
+}
+
+#[test]
+fn nes20_timing() {
+    let expected = [VideoStandard::Ntsc,
+                    VideoStandard::Pal,
+                    VideoStandard::MultipleRegion,
+                    VideoStandard::Dendy];
+    for (i, video_standard) in expected.iter().enumerate() {
+        let rom = NesRom::load_nes20(&nes20_fixture(&[(12, i as u8)])).unwrap();
+        assert_eq!(*video_standard, rom.video_standard);
+    }
+}
+
+#[test]
+fn nes20_console_type() {
+    let rom = NesRom::load_nes20(&nes20_fixture(&[(7, 0x09), (13, 0x21)])).unwrap();
+    assert_eq!(ConsoleType::VsSystem {
+                   ppu_type: 1,
+                   hardware_type: 2,
+               },
+               rom.console_type);
+    assert_eq!(true, rom.is_vs_unisystem);
+
+    let rom = NesRom::load_nes20(&nes20_fixture(&[(7, 0x0a)])).unwrap();
+    assert_eq!(ConsoleType::Playchoice10, rom.console_type);
+    assert_eq!(true, rom.is_pc10);
+
+    let rom = NesRom::load_nes20(&nes20_fixture(&[(7, 0x0b), (13, 0x03)])).unwrap();
+    assert_eq!(ConsoleType::Extended(3), rom.console_type);
+}
+
+#[test]
+fn nes20_expansion_device() {
+    let rom = NesRom::load_nes20(&nes20_fixture(&[(15, 0x08)])).unwrap();
+    assert_eq!(0x08, rom.expansion_device);
+}
+
+#[test]
+fn from_bytes_ines() {
+    let mut bytes = nes20_fixture(&[(6, 0x21)]);
+    bytes[7] = 0;
+    let rom = NesRom::from_bytes(&bytes).unwrap();
+    assert_eq!(RomFormat::INes, rom.format);
+    assert_eq!(2, rom.mapper);
+    assert_eq!(Mirroring::Vertical, rom.mirroring);
+    assert_eq!(0x8000, rom.prg.len());
+    assert_eq!(0x2000, rom.chr.len());
+}
+
+#[test]
+fn from_bytes_bad_magic() {
+    let mut bytes = nes20_fixture(&[]);
+    bytes[3] = 0;
+    match NesRom::from_bytes(&bytes) {
+        Err(RomError::BadMagic) => (),
+        _ => panic!("expected a bad magic error"),
+    }
+}
+
+#[test]
+fn from_bytes_short_header() {
+    match NesRom::from_bytes(&[0x4e, 0x45, 0x53, 0x1a]) {
+        Err(RomError::Truncated { expected: 16, actual: 4 }) => (),
+        _ => panic!("expected a truncation error"),
+    }
+}
+
+#[test]
+fn from_bytes_truncated_chr() {
+    let mut bytes = nes20_fixture(&[]);
+    bytes[7] = 0;
+    bytes.truncate(0x9000);
+    match NesRom::from_bytes(&bytes) {
+        Err(RomError::Truncated { expected: 0xa010, actual: 0x9000 }) => (),
+        _ => panic!("expected a truncation error"),
+    }
+}
+
+#[test]
+fn from_bytes_malformed_header() {
+    let mut bytes = nes20_fixture(&[(9, 0x02)]);
+    bytes[7] = 0;
+    match NesRom::from_bytes(&bytes) {
+        Err(RomError::MalformedHeader(_)) => (),
+        _ => panic!("expected a malformed header error"),
+    }
+}
+
+#[test]
+fn io_error_display_includes_cause() {
+    let error = RomError::from(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+    assert_eq!("Unable to read ROM: no such file", format!("{}", error));
+}
+
+#[test]
+fn from_bytes_oversized_rom() {
+    // The largest exponent-multiplier size is an error rather than a crash, whichever format the
+    // header ends up being read as
+    let bytes = nes20_fixture(&[(4, 0xff), (5, 0xff), (9, 0xff)]);
+    assert!(NesRom::from_bytes(&bytes).is_err());
+
+    // Sizes that fit on their own, but overflow when added together
+    let bytes = nes20_fixture(&[(4, 0xf9), (5, 0xf9), (9, 0xff)]);
+    match NesRom::load_nes20(&bytes) {
+        Err(RomError::MalformedHeader(_)) => (),
+        _ => panic!("expected a malformed header error"),
+    }
+}
+
+#[test]
+fn ines_trainer() {
+    let bytes = trainer_fixture();
+    let rom = NesRom::from_bytes(&bytes).unwrap();
+    assert_eq!(true, rom.has_trainer);
+    assert_eq!(vec![3; 0x200], rom.trainer);
+    assert_eq!(true, rom.prg.iter().all(|b| *b == 1));
+    assert_eq!(true, rom.chr.iter().all(|b| *b == 2));
+}
+
+#[test]
+fn archaic_ines_loads_prg_chr_and_trainer() {
+    // Junk in bytes 12-15 marks an archaic header, and everything after the low mapper nibble is
+    // ignored
+    let mut bytes = trainer_fixture();
+    bytes[6] |= 0x30;
+    bytes[7..16].copy_from_slice(b"Ni03Junk!");
+    let rom = NesRom::from_bytes(&bytes).unwrap();
+    assert_eq!(RomFormat::INesArchaic, rom.format);
+    assert_eq!(3, rom.mapper);
+    assert_eq!(vec![3; 0x200], rom.trainer);
+    assert_eq!(0x8000, rom.prg.len());
+    assert_eq!(true, rom.prg.iter().all(|b| *b == 1));
+    assert_eq!(0x2000, rom.chr.len());
+    assert_eq!(true, rom.chr.iter().all(|b| *b == 2));
+}
+
+#[test]
+fn diskdude_header_is_ignored() {
+    let mut bytes = nes20_fixture(&[(6, 0x10)]);
+    bytes[7..16].copy_from_slice(b"DiskDude!");
+    let rom = NesRom::from_bytes(&bytes).unwrap();
+    assert_eq!(RomFormat::INes, rom.format);
+    assert_eq!(1, rom.mapper);
+}
+
+#[test]
+fn game_info_overrides_header() {
+    let mut rom = NesRom::default();
+    rom.prg_ram_banks = 1;
+    rom.apply_game_info(&GameInfo {
+                            title: "Test".to_string(),
+                            mapper: 4,
+                            submapper: 1,
+                            mirroring: Mirroring::Vertical,
+                            has_battery: true,
+                            video_standard: VideoStandard::Pal,
+                        });
+    assert_eq!(4, rom.mapper);
+    assert_eq!(1, rom.submapper);
+    assert_eq!(Mirroring::Vertical, rom.mirroring);
+    assert_eq!(VideoStandard::Pal, rom.video_standard);
+    assert_eq!(true, rom.has_sram);
+    assert_eq!(0x2000, rom.prg_nvram_size);
+    assert_eq!(0, rom.prg_ram_size);
+}
+
+#[test]
+fn game_info_doesnt_override_nes20_or_unif() {
+    for &format in &[RomFormat::Nes20, RomFormat::Unif] {
+        let mut rom = NesRom::default();
+        rom.format = format;
+        rom.apply_game_info(&GameInfo {
+                                title: "Test".to_string(),
+                                mapper: 4,
+                                submapper: 1,
+                                mirroring: Mirroring::Vertical,
+                                has_battery: true,
+                                video_standard: VideoStandard::Pal,
+                            });
+        assert_eq!(0, rom.mapper);
+        assert_eq!(0, rom.submapper);
+        assert_eq!(false, rom.has_sram);
+    }
+}
+
+#[test]
+fn unknown_rom_is_not_identified() {
+    let rom = NesRom::from_bytes(&nes20_fixture(&[])).unwrap();
+    assert_eq!(None, rom.identify());
+}
+
+// An iNES image with a trainer filled with $03 ahead of the PRG-ROM
+fn trainer_fixture() -> Vec<u8> {
+    let mut bytes = nes20_fixture(&[(6, 0x04)]);
+    bytes[7] = 0;
+    let prg_and_chr = bytes.split_off(16);
+    bytes.extend(vec![3; 0x200]);
+    bytes.extend(prg_and_chr);
+    bytes
+}
+
+// A NES 2.0 image with 32KB of PRG-ROM filled with $01 and 8KB of CHR-ROM filled with $02, with
+// the given header bytes overridden
+fn nes20_fixture(header_overrides: &[(usize, u8)]) -> Vec<u8> {
+    let mut header = vec![0x4e, 0x45, 0x53, 0x1a, 2, 1, 0, 0x08, 0, 0, 0, 0, 0, 0, 0, 0];
+    for &(index, val) in header_overrides {
+        header[index] = val;
+    }
+
+    let chr_size = if header[5] == 0 { 0 } else { 0x2000 };
+    let mut rom = header;
+    rom.extend(vec![1; 0x8000]);
+    rom.extend(vec![2; chr_size]);
+    rom
+}
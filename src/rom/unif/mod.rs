@@ -0,0 +1,205 @@
+//! UNIF (.unf) images. After a 32 byte header, the file is a sequence of chunks, each made up of a
+//! four character ID, a little-endian 32-bit length and then the chunk data. Boards are identified
+//! by name rather than by mapper number. See https://wiki.nesdev.com/w/index.php/UNIF
+
+#[cfg(test)]
+mod spec_tests;
+
+use super::{CHR_ROM_BANK_SIZE, ConsoleType, Mirroring, NesRom, PRG_ROM_BANK_SIZE, RAM_BANK_SIZE,
+            RomError, RomFormat, VideoStandard};
+use byte_utils::from_lo_hi;
+
+const HEADER_SIZE: usize = 32;
+const CHUNK_HEADER_SIZE: usize = 8;
+
+pub fn is_unif(bytes: &[u8]) -> bool {
+    bytes.len() >= 4 && &bytes[0..4] == b"UNIF"
+}
+
+pub fn load(bytes: &[u8]) -> Result<NesRom, RomError> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(RomError::Truncated {
+                       expected: HEADER_SIZE,
+                       actual: bytes.len(),
+                   });
+    }
+
+    if !is_unif(bytes) {
+        return Err(RomError::BadMagic);
+    }
+
+    let mut board = None;
+    let mut prg_chunks: [Option<&[u8]>; 16] = [None; 16];
+    let mut chr_chunks: [Option<&[u8]>; 16] = [None; 16];
+    let mut mirroring = Mirroring::Horizontal;
+    let mut has_battery = false;
+    let mut video_standard = VideoStandard::Ntsc;
+
+    let mut offset = HEADER_SIZE;
+    while offset < bytes.len() {
+        let (id, data) = read_chunk(bytes, offset)?;
+        offset += CHUNK_HEADER_SIZE + data.len();
+
+        match id {
+            b"MAPR" => board = Some(board_name(data)),
+            b"MIRR" => mirroring = unif_mirroring(data)?,
+            b"BATR" => has_battery = true,
+            b"TVCI" => video_standard = unif_video_standard(data),
+            _ => {
+                if let Some(index) = rom_chunk_index(id, b"PRG") {
+                    prg_chunks[index] = Some(data);
+                } else if let Some(index) = rom_chunk_index(id, b"CHR") {
+                    chr_chunks[index] = Some(data);
+                }
+                // Other chunks (NAME, READ, DINF, CTRL, PCKn, CCKn...) don't affect emulation
+            }
+        }
+    }
+
+    let board = match board {
+        Some(board) => board,
+        None => return Err(RomError::MalformedHeader("UNIF image has no MAPR chunk.")),
+    };
+
+    let mapper = match board_mapper(&board) {
+        Some(mapper) => mapper,
+        None => return Err(RomError::UnsupportedBoard(board)),
+    };
+
+    let prg = concat_chunks(&prg_chunks);
+    let chr = concat_chunks(&chr_chunks);
+    if prg.is_empty() {
+        return Err(RomError::MalformedHeader("UNIF image has no PRG chunks."));
+    }
+
+    let chr_ram_banks = if chr.is_empty() { 1 } else { 0 };
+    let (prg_ram_size, prg_nvram_size) = if has_battery {
+        (0, RAM_BANK_SIZE)
+    } else {
+        (RAM_BANK_SIZE, 0)
+    };
+
+    Ok(NesRom {
+           format: RomFormat::Unif,
+           video_standard: video_standard,
+           mapper: mapper,
+           submapper: 0,
+           mirroring: mirroring,
+           prg_rom_banks: ((prg.len() + PRG_ROM_BANK_SIZE - 1) / PRG_ROM_BANK_SIZE) as u16,
+           prg_ram_banks: 1,
+           chr_rom_banks: ((chr.len() + CHR_ROM_BANK_SIZE - 1) / CHR_ROM_BANK_SIZE) as u16,
+           chr_ram_banks: chr_ram_banks,
+           prg_ram_size: prg_ram_size,
+           prg_nvram_size: prg_nvram_size,
+           chr_ram_size: chr_ram_banks as usize * RAM_BANK_SIZE,
+           chr_nvram_size: 0,
+           has_sram: has_battery,
+           has_trainer: false,
+           is_pc10: false,
+           is_vs_unisystem: false,
+           console_type: ConsoleType::Nes,
+           expansion_device: 0,
+           trainer: Vec::new(),
+           prg: prg,
+           chr: chr,
+       })
+}
+
+fn read_chunk(bytes: &[u8], offset: usize) -> Result<(&[u8], &[u8]), RomError> {
+    let data_start = offset + CHUNK_HEADER_SIZE;
+    if bytes.len() < data_start {
+        return Err(RomError::Truncated {
+                       expected: data_start,
+                       actual: bytes.len(),
+                   });
+    }
+
+    let id = &bytes[offset..offset + 4];
+    let len_lo = from_lo_hi(bytes[offset + 4], bytes[offset + 5]) as usize;
+    let len_hi = from_lo_hi(bytes[offset + 6], bytes[offset + 7]) as usize;
+    let data_end = data_start + ((len_hi << 16) | len_lo);
+    if bytes.len() < data_end {
+        return Err(RomError::Truncated {
+                       expected: data_end,
+                       actual: bytes.len(),
+                   });
+    }
+
+    Ok((id, &bytes[data_start..data_end]))
+}
+
+// PRG0-PRGF and CHR0-CHRF, where the last character is a hex digit giving the chunk's position
+fn rom_chunk_index(id: &[u8], prefix: &[u8]) -> Option<usize> {
+    if &id[0..3] != prefix {
+        return None;
+    }
+
+    match id[3] {
+        b'0'...b'9' => Some((id[3] - b'0') as usize),
+        b'A'...b'F' => Some((id[3] - b'A') as usize + 10),
+        _ => None,
+    }
+}
+
+fn concat_chunks(chunks: &[Option<&[u8]>]) -> Vec<u8> {
+    let mut mem = Vec::new();
+    for chunk in chunks.iter().filter_map(|chunk| *chunk) {
+        mem.extend_from_slice(chunk);
+    }
+    mem
+}
+
+fn board_name(data: &[u8]) -> String {
+    let len = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..len]).into_owned()
+}
+
+fn unif_mirroring(data: &[u8]) -> Result<Mirroring, RomError> {
+    match data.first() {
+        Some(&0) => Ok(Mirroring::Horizontal),
+        Some(&1) => Ok(Mirroring::Vertical),
+        Some(&2) => Ok(Mirroring::SingleScreenLower),
+        Some(&3) => Ok(Mirroring::SingleScreenUpper),
+        Some(&4) => Ok(Mirroring::FourScreen),
+        // Controlled by the mapper, which will override this
+        Some(&5) => Ok(Mirroring::Horizontal),
+        _ => Err(RomError::MalformedHeader("Invalid UNIF MIRR chunk.")),
+    }
+}
+
+fn unif_video_standard(data: &[u8]) -> VideoStandard {
+    match data.first() {
+        Some(&0) => VideoStandard::Ntsc,
+        Some(&1) => VideoStandard::Pal,
+        Some(&2) => VideoStandard::MultipleRegion,
+        _ => VideoStandard::Indeterminite,
+    }
+}
+
+/// Maps a UNIF board name onto the equivalent iNES mapper number.
+fn board_mapper(board: &str) -> Option<u16> {
+    // Board names are usually prefixed with the manufacturer or market, e.g. NES-SNROM or
+    // HVC-SNROM, which has no bearing on the mapper
+    let name = ["NES-", "HVC-", "UNL-", "BTL-", "BMC-", "IREM-", "AVE-"]
+        .iter()
+        .find(|prefix| board.starts_with(*prefix))
+        .map(|prefix| &board[prefix.len()..])
+        .unwrap_or(board);
+
+    let mapper = match name {
+        "NROM" | "NROM-128" | "NROM-256" | "RROM" | "RROM-128" => 0,
+        "SAROM" | "SBROM" | "SCROM" | "SC1ROM" | "SEROM" | "SFROM" | "SGROM" | "SHROM" |
+        "SJROM" | "SKROM" | "SLROM" | "SL1ROM" | "SL2ROM" | "SL3ROM" | "SLRROM" | "SNROM" |
+        "SOROM" | "SUROM" | "SXROM" => 1,
+        "UNROM" | "UOROM" => 2,
+        "CNROM" => 3,
+        "TBROM" | "TEROM" | "TFROM" | "TGROM" | "TKROM" | "TLROM" | "TL1ROM" | "TL2ROM" |
+        "TNROM" | "TR1ROM" | "TSROM" | "TVROM" | "B4" => 4,
+        "AMROM" | "ANROM" | "AN1ROM" | "AOROM" => 7,
+        "COLORDREAMS" | "CDREAMS" => 11,
+        "BNROM" | "NINA-01" | "NINA-001" => 34,
+        "GNROM" | "MHROM" => 66,
+        _ => return None,
+    };
+    Some(mapper)
+}
@@ -0,0 +1,92 @@
+use super::*;
+
+#[test]
+fn load_unif() {
+    let bytes = unif_fixture(&[chunk(b"MAPR", b"NES-UNROM\0"),
+                               chunk(b"PRG0", &[1; 0x4000]),
+                               chunk(b"PRG1", &[2; 0x4000]),
+                               chunk(b"MIRR", &[1]),
+                               chunk(b"BATR", &[0])]);
+    let rom = NesRom::from_bytes(&bytes).unwrap();
+    assert_eq!(RomFormat::Unif, rom.format);
+    assert_eq!(2, rom.mapper);
+    assert_eq!(2, rom.prg_rom_banks);
+    assert_eq!(0x8000, rom.prg.len());
+    assert_eq!(1, rom.prg[0x3fff]);
+    assert_eq!(2, rom.prg[0x4000]);
+    assert_eq!(0, rom.chr.len());
+    assert_eq!(1, rom.chr_ram_banks);
+    assert_eq!(Mirroring::Vertical, rom.mirroring);
+    assert_eq!(true, rom.has_sram);
+}
+
+#[test]
+fn rom_chunks_are_ordered_by_number() {
+    let bytes = unif_fixture(&[chunk(b"MAPR", b"NES-CNROM\0"),
+                               chunk(b"PRG0", &[1; 0x8000]),
+                               chunk(b"CHRA", &[0xa; 0x2000]),
+                               chunk(b"CHR1", &[1; 0x2000])]);
+    let rom = load(&bytes).unwrap();
+    assert_eq!(3, rom.mapper);
+    assert_eq!(2, rom.chr_rom_banks);
+    assert_eq!(1, rom.chr[0]);
+    assert_eq!(0xa, rom.chr[0x2000]);
+}
+
+#[test]
+fn board_names() {
+    assert_eq!(Some(0), board_mapper("NES-NROM-256"));
+    assert_eq!(Some(1), board_mapper("HVC-SNROM"));
+    assert_eq!(Some(4), board_mapper("NES-TLROM"));
+    assert_eq!(Some(7), board_mapper("NES-AOROM"));
+    assert_eq!(Some(34), board_mapper("AVE-NINA-01"));
+    assert_eq!(Some(66), board_mapper("NES-GNROM"));
+    assert_eq!(None, board_mapper("NES-EKROM"));
+}
+
+#[test]
+fn unsupported_board() {
+    let bytes = unif_fixture(&[chunk(b"MAPR", b"NES-EKROM\0"), chunk(b"PRG0", &[0; 0x8000])]);
+    match load(&bytes) {
+        Err(RomError::UnsupportedBoard(ref board)) if board == "NES-EKROM" => (),
+        _ => panic!("expected an unsupported board error"),
+    }
+}
+
+#[test]
+fn missing_board_name() {
+    let bytes = unif_fixture(&[chunk(b"PRG0", &[0; 0x8000])]);
+    match load(&bytes) {
+        Err(RomError::MalformedHeader(_)) => (),
+        _ => panic!("expected a malformed header error"),
+    }
+}
+
+#[test]
+fn truncated_chunk() {
+    let mut bytes = unif_fixture(&[chunk(b"MAPR", b"NES-NROM\0"), chunk(b"PRG0", &[0; 0x8000])]);
+    let len = bytes.len();
+    bytes.truncate(len - 1);
+    match load(&bytes) {
+        Err(RomError::Truncated { .. }) => (),
+        _ => panic!("expected a truncation error"),
+    }
+}
+
+fn chunk(id: &[u8], data: &[u8]) -> Vec<u8> {
+    let len = data.len();
+    let mut chunk = id.to_vec();
+    chunk.extend_from_slice(&[len as u8, (len >> 8) as u8, (len >> 16) as u8, (len >> 24) as u8]);
+    chunk.extend_from_slice(data);
+    chunk
+}
+
+fn unif_fixture(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = b"UNIF".to_vec();
+    bytes.extend_from_slice(&[7, 0, 0, 0]);
+    bytes.extend_from_slice(&[0; 24]);
+    for chunk in chunks {
+        bytes.extend_from_slice(chunk);
+    }
+    bytes
+}